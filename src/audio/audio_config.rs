@@ -0,0 +1,216 @@
+use std::io::{self, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::audio::audio_input_stream::{AudioBuffer, PullStreamReader, PushStreamReader};
+use crate::audio::audio_output_stream::{PullStreamWriter, PushStreamWriter};
+use crate::audio::wav::{self, WavFileWriter};
+use crate::audio::{AudioInputStream, AudioOutputStream, AudioStreamFormat};
+use crate::common::{AudioSource, PropertyCollection, PropertyId};
+use crate::error::{Error, Result};
+
+// AudioConfig represents specific audio configuration, such as microphone, file, or custom audio streams.
+// Input configs are used by recognizers, output configs by synthesizers.
+pub struct AudioConfig {
+    pub(crate) source: Option<InputSource>,
+    pub(crate) sink: Option<OutputSink>,
+    properties: PropertyCollection,
+}
+
+impl AudioConfig {
+    // from_reader creates an AudioConfig object that reads audio in the given format from reader.
+    pub fn from_reader<R>(reader: R, format: AudioStreamFormat) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        AudioConfig::new(
+            AudioInput::Reader(Box::new(reader)),
+            format,
+            AudioSource::Stream,
+        )
+    }
+
+    // from_wav_file creates an AudioConfig object representing the specified wave file. The RIFF header is
+    // parsed when the audio config is created, so malformed headers and unsupported encodings are reported
+    // as Error::InvalidHeader and Error::UnsupportedFormat; the audio data is streamed from the file during
+    // recognition.
+    pub fn from_wav_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let open_failed = |e| Error::FileOpenFailed(path.display().to_string(), e);
+        let mut file = std::fs::File::open(path).map_err(open_failed)?;
+        let header = wav::read_header(&mut BufReader::new(&file))?;
+        file.seek(SeekFrom::Start(header.data_offset))?;
+        let file = tokio::fs::File::from_std(file);
+        let input: Box<dyn AsyncRead + Send + Unpin> = match header.data_length {
+            Some(length) => Box::new(file.take(u64::from(length))),
+            None => Box::new(file),
+        };
+        Ok(AudioConfig::new(
+            AudioInput::Reader(input),
+            header.format,
+            AudioSource::File,
+        ))
+    }
+
+    // from_stream_input creates an AudioConfig object representing the specified push or pull stream. A push
+    // stream can only be used by a single audio config.
+    pub fn from_stream_input(stream: impl Into<AudioInputStream>) -> Result<Self> {
+        let stream = stream.into();
+        let format = stream.format().clone();
+        let input = match stream {
+            AudioInputStream::Push(stream) => AudioInput::Push(stream.reader()?),
+            AudioInputStream::Pull(stream) => AudioInput::Pull(stream.reader()),
+        };
+        Ok(AudioConfig::new(input, format, AudioSource::Stream))
+    }
+
+    // from_wav_file_output creates an AudioConfig object representing the specified wave file for audio output.
+    // The file is created right away; its RIFF header describes the output format of the synthesizer and is
+    // completed after each synthesis and when the synthesizer is closed.
+    pub fn from_wav_file_output(path: impl AsRef<Path>) -> Result<Self> {
+        let writer = WavFileWriter::create(path.as_ref())?;
+        Ok(AudioConfig::new_output(AudioOutput::WavFile(writer)))
+    }
+
+    // from_stream_output creates an AudioConfig object representing the specified push or pull stream for
+    // audio output. Streams receive the audio without a RIFF header.
+    pub fn from_stream_output(stream: impl Into<AudioOutputStream>) -> Self {
+        let output = match stream.into() {
+            AudioOutputStream::Push(stream) => AudioOutput::Push(stream.writer()),
+            AudioOutputStream::Pull(stream) => AudioOutput::Pull(stream.writer()),
+        };
+        AudioConfig::new_output(output)
+    }
+
+    fn new_output(output: AudioOutput) -> Self {
+        AudioConfig {
+            source: None,
+            sink: Some(OutputSink { output }),
+            properties: PropertyCollection::new(),
+        }
+    }
+
+    fn new(input: AudioInput, format: AudioStreamFormat, kind: AudioSource) -> Self {
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::AudioConfigAudioSource, kind.as_str());
+        properties.set_property(
+            PropertyId::AudioConfigSampleRateForCapture,
+            format.samples_per_second().to_string(),
+        );
+        properties.set_property(
+            PropertyId::AudioConfigBitsPerSampleForCapture,
+            format.bits_per_sample().to_string(),
+        );
+        properties.set_property(
+            PropertyId::AudioConfigNumberOfChannelsForCapture,
+            format.channels().to_string(),
+        );
+        AudioConfig {
+            source: Some(InputSource { input, format }),
+            sink: None,
+            properties,
+        }
+    }
+
+    // set_property sets a property value by ID.
+    pub fn set_property(&mut self, id: PropertyId, value: &str) {
+        self.properties.set_property(id, value);
+    }
+
+    // get_property gets a property value by ID.
+    pub fn get_property(&self, id: PropertyId) -> String {
+        self.properties.get_property(id, "")
+    }
+
+    pub fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+}
+
+pub(crate) enum AudioInput {
+    Reader(Box<dyn AsyncRead + Send + Unpin>),
+    Push(PushStreamReader),
+    Pull(PullStreamReader),
+}
+
+// InputSource is the audio a recognizer reads from, together with its format.
+pub(crate) struct InputSource {
+    input: AudioInput,
+    format: AudioStreamFormat,
+}
+
+impl InputSource {
+    pub(crate) fn format(&self) -> &AudioStreamFormat {
+        &self.format
+    }
+
+    // read_chunk reads up to max_size bytes of audio. None is returned at the end of the audio.
+    // Reading is cancel safe: if the returned future is dropped before completion no audio is lost.
+    pub(crate) async fn read_chunk(&mut self, max_size: usize) -> io::Result<Option<AudioBuffer>> {
+        match &mut self.input {
+            AudioInput::Reader(reader) => {
+                let mut data = vec![0; max_size];
+                let read = reader.read(&mut data).await?;
+                if read == 0 {
+                    return Ok(None);
+                }
+                data.truncate(read);
+                Ok(Some(AudioBuffer {
+                    data,
+                    timestamp: None,
+                    user_id: None,
+                }))
+            }
+            AudioInput::Push(reader) => Ok(reader.read(max_size).await),
+            AudioInput::Pull(reader) => reader.read(max_size).await,
+        }
+    }
+}
+
+enum AudioOutput {
+    WavFile(WavFileWriter),
+    Push(PushStreamWriter),
+    Pull(PullStreamWriter),
+}
+
+// OutputSink is the audio output a synthesizer writes to. Dropping it closes the output.
+pub(crate) struct OutputSink {
+    output: AudioOutput,
+}
+
+impl OutputSink {
+    // begin is called once the output format is known, before any audio is written.
+    pub(crate) fn begin(&mut self, format: &AudioStreamFormat) -> Result<()> {
+        match &mut self.output {
+            AudioOutput::WavFile(writer) => writer.begin(format),
+            AudioOutput::Push(_) | AudioOutput::Pull(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8]) -> Result<()> {
+        match &mut self.output {
+            AudioOutput::WavFile(writer) => writer.write(data)?,
+            AudioOutput::Push(writer) => writer.write(data),
+            AudioOutput::Pull(writer) => writer.write(data),
+        }
+        Ok(())
+    }
+
+    // flush is called at the end of each synthesis, so that the output is complete in between.
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        match &mut self.output {
+            AudioOutput::WavFile(writer) => writer.update_header(),
+            AudioOutput::Push(_) | AudioOutput::Pull(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn close(mut self) -> Result<()> {
+        match &mut self.output {
+            AudioOutput::WavFile(writer) => writer.close()?,
+            AudioOutput::Push(writer) => writer.close(),
+            AudioOutput::Pull(writer) => writer.close(),
+        }
+        Ok(())
+    }
+}
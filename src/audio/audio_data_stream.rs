@@ -0,0 +1,398 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+
+use crate::audio::{AudioStreamContainerFormat, AudioStreamFormat};
+use crate::common::{
+    CancellationError, CancellationErrorDetails, PropertyCollection, PropertyId, StreamStatus,
+};
+use crate::error::{Error, Result};
+use crate::speech_synthesizer::SpeechSynthesisResult;
+
+// AudioDataStream represents audio data stream used for operating audio data as a stream. The stream fills
+// while the audio is still being produced: reads wait for more data until all data has arrived or the
+// stream was canceled.
+pub struct AudioDataStream {
+    shared: Arc<Shared>,
+    position: u32,
+}
+
+struct Shared {
+    state: StdMutex<State>,
+    changed: Notify,
+}
+
+struct State {
+    data: Vec<u8>,
+    status: StreamStatus,
+    format: AudioStreamFormat,
+    properties: PropertyCollection,
+    cancellation: Option<(CancellationError, CancellationErrorDetails)>,
+}
+
+impl AudioDataStream {
+    // new creates an empty stream and the writer that fills it.
+    pub(crate) fn new(format: AudioStreamFormat) -> (AudioDataStream, AudioDataWriter) {
+        let shared = Arc::new(Shared {
+            state: StdMutex::new(State {
+                data: Vec::new(),
+                status: StreamStatus::NoData,
+                format,
+                properties: PropertyCollection::new(),
+                cancellation: None,
+            }),
+            changed: Notify::new(),
+        });
+        let writer = AudioDataWriter {
+            shared: shared.clone(),
+            finished: false,
+        };
+        (
+            AudioDataStream {
+                shared,
+                position: 0,
+            },
+            writer,
+        )
+    }
+
+    // from_result creates an audio data stream holding the audio of a speech synthesis result. The stream
+    // starts at the beginning of the audio and holds the raw audio, without a wave header.
+    pub fn from_result(result: &SpeechSynthesisResult) -> AudioDataStream {
+        AudioDataStream {
+            shared: result.stream.shared.clone(),
+            position: 0,
+        }
+    }
+
+    // get_status gets the current status of the audio data stream.
+    pub fn get_status(&self) -> StreamStatus {
+        self.shared.state.lock().unwrap().status
+    }
+
+    // can_read_data checks whether the stream has enough data to be read, starting from the current position.
+    pub fn can_read_data(&self, bytes_requested: u32) -> bool {
+        self.can_read_data_at(self.position, bytes_requested)
+    }
+
+    // can_read_data_at checks whether the stream has enough data to be read, starting from the specified
+    // position.
+    pub fn can_read_data_at(&self, position: u32, bytes_requested: u32) -> bool {
+        let available = self.shared.state.lock().unwrap().data.len() as u64;
+        u64::from(position) + u64::from(bytes_requested) <= available
+    }
+
+    // read reads audio data from the current position into buffer and advances the position. It waits until
+    // data is available and returns 0 once all data has been read or the stream was canceled.
+    pub async fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let read = self.read_at(self.position, buffer).await?;
+        self.position += read as u32;
+        Ok(read)
+    }
+
+    // read_from_position reads audio data from the specified position into buffer. Afterwards the position
+    // is just behind the data that was read.
+    pub async fn read_from_position(&mut self, buffer: &mut [u8], position: u32) -> Result<usize> {
+        let read = self.read_at(position, buffer).await?;
+        self.position = position + read as u32;
+        Ok(read)
+    }
+
+    async fn read_at(&self, position: u32, buffer: &mut [u8]) -> Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            {
+                let state = self.shared.state.lock().unwrap();
+                let available = state.data.len();
+                let start = position as usize;
+                if start < available {
+                    let read = buffer.len().min(available - start);
+                    buffer[..read].copy_from_slice(&state.data[start..start + read]);
+                    return Ok(read);
+                }
+                if matches!(state.status, StreamStatus::AllData | StreamStatus::Canceled) {
+                    if start > available {
+                        return Err(Error::OutOfRange(format!(
+                            "position {} is beyond the end of the audio data ({} bytes)",
+                            position, available
+                        )));
+                    }
+                    return Ok(0);
+                }
+            }
+            changed.await;
+        }
+    }
+
+    // get_position gets the current position of the audio data stream.
+    pub fn get_position(&self) -> u32 {
+        self.position
+    }
+
+    // set_position sets the current position of the audio data stream. The position must not be beyond the
+    // data received so far.
+    pub fn set_position(&mut self, position: u32) -> Result<()> {
+        let available = self.shared.state.lock().unwrap().data.len();
+        if position as usize > available {
+            return Err(Error::OutOfRange(format!(
+                "position {} is beyond the end of the audio data ({} bytes)",
+                position, available
+            )));
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    // save_to_wave_file saves the audio data from the current position to the end into a wave file, waiting
+    // for the remaining data to arrive. The position is moved to the end of the stream. Only PCM, A-law and
+    // mu-law audio gets a wave header; compressed audio is saved as is, since it carries its own container.
+    pub async fn save_to_wave_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut data = Vec::new();
+        let mut buffer = vec![0; 32 * 1024];
+        loop {
+            let read = self.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&buffer[..read]);
+        }
+        if let Some((error, details)) = self.cancellation() {
+            return Err(Error::Canceled(error, details));
+        }
+        let data_length = u32::try_from(data.len())
+            .map_err(|_| Error::OutOfRange("audio data is too long for a wave file".to_string()))?;
+        let header = match self.format().container_format() {
+            None | Some(AudioStreamContainerFormat::ALaw | AudioStreamContainerFormat::MuLaw) => {
+                self.format().wave_file_header(data_length)
+            }
+            Some(_) => Vec::new(),
+        };
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| Error::FileOpenFailed(path.display().to_string(), e))?;
+        file.write_all(&header).await?;
+        file.write_all(&data).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    // format is the format of the audio data.
+    pub fn format(&self) -> AudioStreamFormat {
+        self.shared.state.lock().unwrap().format.clone()
+    }
+
+    // cancellation is the reason the stream was canceled, if it was.
+    pub fn cancellation(&self) -> Option<(CancellationError, CancellationErrorDetails)> {
+        self.shared.state.lock().unwrap().cancellation.clone()
+    }
+
+    // properties is the collection of properties and their values defined for this AudioDataStream.
+    pub fn properties(&self) -> PropertyCollection {
+        self.shared.state.lock().unwrap().properties.clone()
+    }
+
+    // get_property gets a property value by ID.
+    pub fn get_property(&self, id: PropertyId) -> String {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .properties
+            .get_property(id, "")
+    }
+}
+
+// Clones share the audio data but keep their own position.
+impl Clone for AudioDataStream {
+    fn clone(&self) -> Self {
+        AudioDataStream {
+            shared: self.shared.clone(),
+            position: self.position,
+        }
+    }
+}
+
+impl fmt::Debug for AudioDataStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioDataStream")
+            .field("status", &self.get_status())
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+// AudioDataWriter fills an AudioDataStream. Dropping the writer before finish or cancel cancels the stream.
+pub(crate) struct AudioDataWriter {
+    shared: Arc<Shared>,
+    finished: bool,
+}
+
+impl AudioDataWriter {
+    pub(crate) fn write(&self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        self.update(|state| {
+            state.data.extend_from_slice(data);
+            state.status = StreamStatus::PartialData;
+        });
+    }
+
+    pub(crate) fn set_property(&self, id: PropertyId, value: &str) {
+        self.update(|state| state.properties.set_property(id, value));
+    }
+
+    // finish marks the stream as holding all data.
+    pub(crate) fn finish(mut self) {
+        self.finished = true;
+        self.update(|state| state.status = StreamStatus::AllData);
+    }
+
+    // cancel marks the stream as canceled. Data written so far can still be read.
+    pub(crate) fn cancel(mut self, error: CancellationError, details: CancellationErrorDetails) {
+        self.finished = true;
+        self.update(|state| {
+            state.status = StreamStatus::Canceled;
+            state.cancellation = Some((error, details));
+        });
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        change(&mut self.shared.state.lock().unwrap());
+        self.shared.changed.notify_waiters();
+    }
+}
+
+impl Drop for AudioDataWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.update(|state| {
+                state.status = StreamStatus::Canceled;
+                state.cancellation = Some((
+                    CancellationError::RuntimeError,
+                    CancellationErrorDetails::new("Canceled", "audio data stream was abandoned"),
+                ));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn reads_wait_for_data_until_all_data_arrived() {
+        let (mut stream, writer) =
+            AudioDataStream::new(AudioStreamFormat::get_default_output_format());
+        assert_eq!(stream.get_status(), StreamStatus::NoData);
+        assert!(!stream.can_read_data(1));
+
+        let reader = tokio::spawn(async move {
+            let mut buffer = [0; 4];
+            let mut chunks = Vec::new();
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    return (stream, chunks);
+                }
+                chunks.push(buffer[..read].to_vec());
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        writer.write(&[1, 2, 3]);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        writer.write(&[4, 5, 6, 7, 8, 9]);
+        writer.finish();
+
+        let (stream, chunks) = reader.await.unwrap();
+        assert_eq!(chunks.concat(), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(stream.get_status(), StreamStatus::AllData);
+        assert_eq!(stream.get_position(), 9);
+    }
+
+    #[tokio::test]
+    async fn positions_allow_replaying_audio() {
+        let (mut stream, writer) =
+            AudioDataStream::new(AudioStreamFormat::get_default_output_format());
+        writer.write(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(stream.get_status(), StreamStatus::PartialData);
+        assert!(stream.can_read_data(6));
+        assert!(stream.can_read_data_at(2, 4));
+        assert!(!stream.can_read_data_at(2, 5));
+
+        let mut buffer = [0; 2];
+        assert_eq!(stream.read_from_position(&mut buffer, 3).await.unwrap(), 2);
+        assert_eq!(buffer, [4, 5]);
+        assert_eq!(stream.get_position(), 5);
+
+        stream.set_position(1).unwrap();
+        assert_eq!(stream.read(&mut buffer).await.unwrap(), 2);
+        assert_eq!(buffer, [2, 3]);
+        assert!(matches!(stream.set_position(7), Err(Error::OutOfRange(_))));
+
+        writer.cancel(
+            CancellationError::ServiceError,
+            CancellationErrorDetails::new("Error", "synthesis failed"),
+        );
+        assert_eq!(stream.get_status(), StreamStatus::Canceled);
+        let mut rest = [0; 8];
+        assert_eq!(stream.read(&mut rest).await.unwrap(), 3);
+        assert_eq!(stream.read(&mut rest).await.unwrap(), 0);
+        assert_eq!(
+            stream.cancellation().unwrap().0,
+            CancellationError::ServiceError
+        );
+    }
+
+    #[tokio::test]
+    async fn saves_remaining_audio_to_wave_file() {
+        let format = AudioStreamFormat::get_wave_format_pcm(24000, 16, 1).unwrap();
+        let (mut stream, writer) = AudioDataStream::new(format.clone());
+        writer.write(&[1, 2, 3, 4]);
+        writer.finish();
+
+        let path = std::env::temp_dir().join(format!("speech-sdk-{}.wav", uuid::Uuid::new_v4()));
+        stream.save_to_wave_file(&path).await.unwrap();
+        let file = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file.len(), 44 + 4);
+        assert_eq!(&file[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(file[4..8].try_into().unwrap()), 36 + 4);
+        assert_eq!(u32::from_le_bytes(file[24..28].try_into().unwrap()), 24000);
+        assert_eq!(u32::from_le_bytes(file[40..44].try_into().unwrap()), 4);
+        assert_eq!(&file[44..], [1, 2, 3, 4]);
+
+        let (mut stream, writer) = AudioDataStream::new(format);
+        drop(writer);
+        assert!(matches!(
+            stream.save_to_wave_file(&path).await,
+            Err(Error::Canceled(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn saves_compressed_audio_without_wave_header() {
+        let format = AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::Mp3);
+        let (mut stream, writer) = AudioDataStream::new(format);
+        writer.write(b"ID3\x04");
+        writer.write(&[0xff, 0xfb, 0x90, 0x64]);
+        writer.finish();
+
+        let path = std::env::temp_dir().join(format!("speech-sdk-{}.mp3", uuid::Uuid::new_v4()));
+        stream.save_to_wave_file(&path).await.unwrap();
+        let file = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file, b"ID3\x04\xff\xfb\x90\x64");
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::audio::AudioStreamFormat;
+use crate::common::{PropertyCollection, PropertyId};
+use crate::error::{Error, Result};
+
+// DEFAULT_PUSH_STREAM_CAPACITY is the number of buffers a push stream holds before write waits for the
+// recognizer to catch up.
+const DEFAULT_PUSH_STREAM_CAPACITY: usize = 32;
+
+// AudioInputStream represents audio input stream used for custom audio input configurations.
+pub enum AudioInputStream {
+    Push(PushAudioInputStream),
+    Pull(PullAudioInputStream),
+}
+
+impl From<PushAudioInputStream> for AudioInputStream {
+    fn from(stream: PushAudioInputStream) -> Self {
+        AudioInputStream::Push(stream)
+    }
+}
+
+impl From<PullAudioInputStream> for AudioInputStream {
+    fn from(stream: PullAudioInputStream) -> Self {
+        AudioInputStream::Pull(stream)
+    }
+}
+
+impl AudioInputStream {
+    pub(crate) fn format(&self) -> &AudioStreamFormat {
+        match self {
+            AudioInputStream::Push(stream) => &stream.format,
+            AudioInputStream::Pull(stream) => &stream.format,
+        }
+    }
+}
+
+// AudioBuffer is a buffer of audio read from an input stream, together with the per-buffer properties
+// DataBuffer_TimeStamp and DataBuffer_UserId.
+pub(crate) struct AudioBuffer {
+    pub(crate) data: Vec<u8>,
+    pub(crate) timestamp: Option<String>,
+    pub(crate) user_id: Option<String>,
+}
+
+impl AudioBuffer {
+    // split_off keeps the first max_size bytes and returns the rest. The per-buffer properties stay with
+    // the first part.
+    fn split_off(&mut self, max_size: usize) -> Option<AudioBuffer> {
+        if self.data.len() <= max_size {
+            return None;
+        }
+        Some(AudioBuffer {
+            data: self.data.split_off(max_size),
+            timestamp: None,
+            user_id: None,
+        })
+    }
+}
+
+enum PushItem {
+    Buffer(AudioBuffer),
+    Close,
+}
+
+// PushAudioInputStream represents memory backed push audio input stream used for custom audio input
+// configurations. Clones write to the same stream.
+#[derive(Clone)]
+pub struct PushAudioInputStream {
+    format: AudioStreamFormat,
+    sender: mpsc::Sender<PushItem>,
+    receiver: Arc<StdMutex<Option<mpsc::Receiver<PushItem>>>>,
+    properties: Arc<StdMutex<PropertyCollection>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl PushAudioInputStream {
+    // create_push_stream creates a memory backed PushAudioInputStream using the default format (16kHz, 16bit,
+    // mono PCM).
+    pub fn create_push_stream() -> Self {
+        PushAudioInputStream::create_push_stream_from_format(
+            AudioStreamFormat::get_default_input_format(),
+        )
+    }
+
+    // create_push_stream_from_format creates a memory backed PushAudioInputStream with the specified audio
+    // format.
+    pub fn create_push_stream_from_format(format: AudioStreamFormat) -> Self {
+        PushAudioInputStream::create_push_stream_with_capacity(format, DEFAULT_PUSH_STREAM_CAPACITY)
+    }
+
+    // create_push_stream_with_capacity creates a PushAudioInputStream that buffers at most capacity writes.
+    // Once the buffer is full, write waits until the recognizer has consumed audio.
+    pub fn create_push_stream_with_capacity(format: AudioStreamFormat, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        PushAudioInputStream {
+            format,
+            sender,
+            receiver: Arc::new(StdMutex::new(Some(receiver))),
+            properties: Arc::new(StdMutex::new(PropertyCollection::new())),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // write writes the audio data specified by making an internal copy of the data. The per-buffer properties
+    // set since the previous write are attached to the data. write waits while the stream buffer is full.
+    pub async fn write(&self, data: &[u8]) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::InvalidState("push stream is closed".to_string()));
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        let buffer = {
+            let mut properties = self.properties.lock().unwrap();
+            let buffer = AudioBuffer {
+                data: data.to_vec(),
+                timestamp: properties
+                    .property(PropertyId::DataBufferTimeStamp)
+                    .map(str::to_string),
+                user_id: properties
+                    .property(PropertyId::DataBufferUserId)
+                    .map(str::to_string),
+            };
+            *properties = PropertyCollection::new();
+            buffer
+        };
+        self.sender
+            .send(PushItem::Buffer(buffer))
+            .await
+            .map_err(|_| Error::InvalidState("push stream is no longer read".to_string()))
+    }
+
+    // close closes the stream. The recognizer sees the end of the audio once it has read the data written
+    // before.
+    pub async fn close(&self) {
+        if !self.closed.swap(true, Ordering::SeqCst) {
+            let _ = self.sender.send(PushItem::Close).await;
+        }
+    }
+
+    // set_property sets a per-buffer property that applies to the data of the next write. Only
+    // DataBufferTimeStamp and DataBufferUserId are supported.
+    pub fn set_property(&self, id: PropertyId, value: &str) -> Result<()> {
+        match id {
+            PropertyId::DataBufferTimeStamp | PropertyId::DataBufferUserId => {
+                self.properties.lock().unwrap().set_property(id, value);
+                Ok(())
+            }
+            id => Err(Error::InvalidArgument(format!(
+                "{} is not a per-buffer property",
+                id.name()
+            ))),
+        }
+    }
+
+    // set_property_by_name sets a per-buffer property by name, see set_property.
+    pub fn set_property_by_name(&self, name: &str, value: &str) -> Result<()> {
+        let id = PropertyId::from_name(name)
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown property {:?}", name)))?;
+        self.set_property(id, value)
+    }
+
+    pub(crate) fn reader(&self) -> Result<PushStreamReader> {
+        let receiver = self.receiver.lock().unwrap().take().ok_or_else(|| {
+            Error::InvalidArgument("push stream is already used by an audio config".to_string())
+        })?;
+        Ok(PushStreamReader {
+            receiver,
+            pending: VecDeque::new(),
+            closed: false,
+        })
+    }
+}
+
+// PushStreamReader is the recognizer side of a push stream.
+pub(crate) struct PushStreamReader {
+    receiver: mpsc::Receiver<PushItem>,
+    pending: VecDeque<AudioBuffer>,
+    closed: bool,
+}
+
+impl PushStreamReader {
+    // read returns the next buffer of at most max_size bytes, or None once the stream is closed. It is
+    // cancel safe.
+    pub(crate) async fn read(&mut self, max_size: usize) -> Option<AudioBuffer> {
+        if self.pending.is_empty() && !self.closed {
+            match self.receiver.recv().await {
+                Some(PushItem::Buffer(buffer)) => self.pending.push_back(buffer),
+                // All writers were dropped without closing the stream, which also ends it.
+                Some(PushItem::Close) | None => self.closed = true,
+            }
+        }
+        let mut buffer = self.pending.pop_front()?;
+        if let Some(rest) = buffer.split_off(max_size) {
+            self.pending.push_front(rest);
+        }
+        Some(buffer)
+    }
+}
+
+// PullAudioInputStreamCallback is the interface that defines callback methods for a PullAudioInputStream.
+// The callbacks are called from a blocking thread, so they may block while waiting for audio.
+pub trait PullAudioInputStreamCallback: Send + 'static {
+    // read reads data from audio input stream into buffer, returning the number of bytes read. Returning 0
+    // indicates the end of the stream.
+    fn read(&mut self, buffer: &mut [u8]) -> usize;
+
+    // close closes the audio input stream.
+    fn close(&mut self) {}
+
+    // get_property gets the value of a per-buffer property (DataBufferTimeStamp or DataBufferUserId) for
+    // the data returned by the last read.
+    fn get_property(&self, _id: PropertyId) -> Option<String> {
+        None
+    }
+}
+
+// PullAudioInputStream represents audio input stream used for custom audio input configurations, where the
+// recognizer pulls audio from a user-defined callback.
+pub struct PullAudioInputStream {
+    format: AudioStreamFormat,
+    callback: Box<dyn PullAudioInputStreamCallback>,
+}
+
+impl PullAudioInputStream {
+    // create_pull_stream creates a PullAudioInputStream that delegates to the specified callback interface
+    // for read() and close() methods, using the default format (16kHz, 16bit, mono PCM).
+    pub fn create_pull_stream(callback: impl PullAudioInputStreamCallback) -> Self {
+        PullAudioInputStream::create_pull_stream_from_format(
+            callback,
+            AudioStreamFormat::get_default_input_format(),
+        )
+    }
+
+    // create_pull_stream_from_format creates a PullAudioInputStream that delegates to the specified callback
+    // interface, using the specified format.
+    pub fn create_pull_stream_from_format(
+        callback: impl PullAudioInputStreamCallback,
+        format: AudioStreamFormat,
+    ) -> Self {
+        PullAudioInputStream {
+            format,
+            callback: Box::new(callback),
+        }
+    }
+
+    pub(crate) fn reader(self) -> PullStreamReader {
+        PullStreamReader {
+            callback: Some(self.callback),
+            pending: None,
+            closed: false,
+        }
+    }
+}
+
+type PullRead = (Box<dyn PullAudioInputStreamCallback>, AudioBuffer);
+
+// PullStreamReader is the recognizer side of a pull stream.
+pub(crate) struct PullStreamReader {
+    callback: Option<Box<dyn PullAudioInputStreamCallback>>,
+    pending: Option<JoinHandle<PullRead>>,
+    closed: bool,
+}
+
+impl PullStreamReader {
+    // read calls the read callback on a blocking thread. It is cancel safe: a read that is still running
+    // when the future is dropped is picked up by the next call.
+    pub(crate) async fn read(&mut self, max_size: usize) -> io::Result<Option<AudioBuffer>> {
+        if self.closed {
+            return Ok(None);
+        }
+        if self.pending.is_none() {
+            let Some(mut callback) = self.callback.take() else {
+                return Ok(None);
+            };
+            self.pending = Some(tokio::task::spawn_blocking(move || {
+                let mut data = vec![0; max_size];
+                let read = callback.read(&mut data).min(max_size);
+                data.truncate(read);
+                let buffer = AudioBuffer {
+                    timestamp: callback.get_property(PropertyId::DataBufferTimeStamp),
+                    user_id: callback.get_property(PropertyId::DataBufferUserId),
+                    data,
+                };
+                (callback, buffer)
+            }));
+        }
+        let result = self.pending.as_mut().unwrap().await;
+        self.pending = None;
+        let (mut callback, buffer) = result.map_err(io::Error::other)?;
+        if buffer.data.is_empty() {
+            self.closed = true;
+            callback.close();
+            return Ok(None);
+        }
+        self.callback = Some(callback);
+        Ok(Some(buffer))
+    }
+}
+
+// Dropping the reader before the end of the stream still closes the callback.
+impl Drop for PullStreamReader {
+    fn drop(&mut self) {
+        if let Some(callback) = self.callback.as_mut() {
+            if !self.closed {
+                callback.close();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn push_stream_splits_buffers_and_keeps_per_buffer_properties() {
+        let stream = PushAudioInputStream::create_push_stream();
+        let mut reader = stream.reader().unwrap();
+        assert!(matches!(stream.reader(), Err(Error::InvalidArgument(_))));
+        assert!(matches!(
+            stream.set_property(PropertyId::SpeechServiceConnectionKey, "key"),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        stream
+            .set_property(PropertyId::DataBufferTimeStamp, "1234")
+            .unwrap();
+        stream
+            .set_property_by_name("DataBuffer_UserId", "speaker")
+            .unwrap();
+        stream.write(&[1; 10]).await.unwrap();
+        stream.write(&[2; 4]).await.unwrap();
+        stream.close().await;
+        assert!(matches!(
+            stream.write(&[3]).await,
+            Err(Error::InvalidState(_))
+        ));
+
+        let first = reader.read(6).await.unwrap();
+        assert_eq!(first.data, [1; 6]);
+        assert_eq!(first.timestamp.as_deref(), Some("1234"));
+        assert_eq!(first.user_id.as_deref(), Some("speaker"));
+        let rest = reader.read(6).await.unwrap();
+        assert_eq!(rest.data, [1; 4]);
+        assert_eq!(rest.timestamp, None);
+        let second = reader.read(6).await.unwrap();
+        assert_eq!(second.data, [2; 4]);
+        assert_eq!(second.user_id, None);
+        assert!(reader.read(6).await.is_none());
+        assert!(reader.read(6).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn push_stream_write_waits_while_buffer_is_full() {
+        let stream = PushAudioInputStream::create_push_stream_with_capacity(
+            AudioStreamFormat::get_default_input_format(),
+            1,
+        );
+        let mut reader = stream.reader().unwrap();
+        stream.write(&[1]).await.unwrap();
+        let blocked = tokio::time::timeout(Duration::from_millis(50), stream.write(&[2])).await;
+        assert!(blocked.is_err());
+
+        assert_eq!(reader.read(10).await.unwrap().data, [1]);
+        tokio::time::timeout(Duration::from_secs(5), stream.write(&[3]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reader.read(10).await.unwrap().data, [3]);
+    }
+
+    struct Chunks {
+        chunks: Vec<Vec<u8>>,
+        read: usize,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl PullAudioInputStreamCallback for Chunks {
+        fn read(&mut self, buffer: &mut [u8]) -> usize {
+            let Some(chunk) = self.chunks.get(self.read) else {
+                return 0;
+            };
+            self.read += 1;
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            chunk.len()
+        }
+
+        fn close(&mut self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+
+        fn get_property(&self, id: PropertyId) -> Option<String> {
+            match id {
+                PropertyId::DataBufferTimeStamp => Some(format!("{}", self.read * 100)),
+                _ => None,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pull_stream_reads_from_callback_until_end_of_stream() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let stream = PullAudioInputStream::create_pull_stream(Chunks {
+            chunks: vec![vec![1, 2, 3], vec![4]],
+            read: 0,
+            closed: closed.clone(),
+        });
+        let mut reader = stream.reader();
+
+        let first = reader.read(16).await.unwrap().unwrap();
+        assert_eq!(first.data, [1, 2, 3]);
+        assert_eq!(first.timestamp.as_deref(), Some("100"));
+        assert_eq!(first.user_id, None);
+        let second = reader.read(16).await.unwrap().unwrap();
+        assert_eq!(second.data, [4]);
+        assert_eq!(second.timestamp.as_deref(), Some("200"));
+        assert!(!closed.load(Ordering::SeqCst));
+        assert!(reader.read(16).await.unwrap().is_none());
+        assert!(closed.load(Ordering::SeqCst));
+        assert!(reader.read(16).await.unwrap().is_none());
+    }
+}
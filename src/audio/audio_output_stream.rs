@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::sync::Notify;
+
+// AudioOutputStream represents audio output stream used for custom audio output configurations.
+pub enum AudioOutputStream {
+    Push(PushAudioOutputStream),
+    Pull(PullAudioOutputStream),
+}
+
+impl From<PushAudioOutputStream> for AudioOutputStream {
+    fn from(stream: PushAudioOutputStream) -> Self {
+        AudioOutputStream::Push(stream)
+    }
+}
+
+impl From<PullAudioOutputStream> for AudioOutputStream {
+    fn from(stream: PullAudioOutputStream) -> Self {
+        AudioOutputStream::Pull(stream)
+    }
+}
+
+// PullAudioOutputStream represents memory backed pull audio output stream used for custom audio output
+// configurations. The synthesizer writes the audio it receives to the stream; clones read from the same stream.
+#[derive(Clone)]
+pub struct PullAudioOutputStream {
+    shared: Arc<PullShared>,
+}
+
+struct PullShared {
+    state: StdMutex<PullState>,
+    changed: Notify,
+}
+
+struct PullState {
+    data: VecDeque<u8>,
+    closed: bool,
+}
+
+impl PullAudioOutputStream {
+    // create_pull_stream creates a memory backed PullAudioOutputStream.
+    pub fn create_pull_stream() -> Self {
+        PullAudioOutputStream {
+            shared: Arc::new(PullShared {
+                state: StdMutex::new(PullState {
+                    data: VecDeque::new(),
+                    closed: false,
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    // read reads audio data into buffer, returning the number of bytes read. It waits until data is available
+    // and returns 0 once the stream was closed and all data has been read.
+    pub async fn read(&self, buffer: &mut [u8]) -> usize {
+        if buffer.is_empty() {
+            return 0;
+        }
+        loop {
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if !state.data.is_empty() {
+                    let read = buffer.len().min(state.data.len());
+                    for (byte, data) in buffer.iter_mut().zip(state.data.drain(..read)) {
+                        *byte = data;
+                    }
+                    return read;
+                }
+                if state.closed {
+                    return 0;
+                }
+            }
+            changed.await;
+        }
+    }
+
+    pub(crate) fn writer(self) -> PullStreamWriter {
+        PullStreamWriter {
+            shared: self.shared,
+        }
+    }
+}
+
+// PullStreamWriter is the synthesizer side of a pull stream. Dropping it closes the stream.
+pub(crate) struct PullStreamWriter {
+    shared: Arc<PullShared>,
+}
+
+impl PullStreamWriter {
+    pub(crate) fn write(&self, data: &[u8]) {
+        self.update(|state| state.data.extend(data));
+    }
+
+    pub(crate) fn close(&self) {
+        self.update(|state| state.closed = true);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut PullState)) {
+        change(&mut self.shared.state.lock().unwrap());
+        self.shared.changed.notify_waiters();
+    }
+}
+
+impl Drop for PullStreamWriter {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// PushAudioOutputStreamCallback is the interface that defines callback methods for a PushAudioOutputStream.
+// The callbacks are called from the synthesis task as audio arrives, so they should return quickly.
+pub trait PushAudioOutputStreamCallback: Send + 'static {
+    // write is called with each chunk of audio data received from the service, returning the number of bytes
+    // written.
+    fn write(&mut self, data: &[u8]) -> usize;
+
+    // close closes the audio output stream.
+    fn close(&mut self) {}
+}
+
+// PushAudioOutputStream represents audio output stream used for custom audio output configurations, where the
+// synthesizer pushes audio to a user-defined callback.
+pub struct PushAudioOutputStream {
+    callback: Box<dyn PushAudioOutputStreamCallback>,
+}
+
+impl PushAudioOutputStream {
+    // create_push_stream creates a PushAudioOutputStream that delegates to the specified callback interface for
+    // write() and close() methods.
+    pub fn create_push_stream(callback: impl PushAudioOutputStreamCallback) -> Self {
+        PushAudioOutputStream {
+            callback: Box::new(callback),
+        }
+    }
+
+    pub(crate) fn writer(self) -> PushStreamWriter {
+        PushStreamWriter {
+            callback: self.callback,
+            closed: false,
+        }
+    }
+}
+
+// PushStreamWriter is the synthesizer side of a push stream. Dropping it closes the callback.
+pub(crate) struct PushStreamWriter {
+    callback: Box<dyn PushAudioOutputStreamCallback>,
+    closed: bool,
+}
+
+impl PushStreamWriter {
+    pub(crate) fn write(&mut self, data: &[u8]) {
+        if !self.closed && !data.is_empty() {
+            self.callback.write(data);
+        }
+    }
+
+    pub(crate) fn close(&mut self) {
+        if !self.closed {
+            self.closed = true;
+            self.callback.close();
+        }
+    }
+}
+
+impl Drop for PushStreamWriter {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn pull_stream_reads_wait_for_data_until_closed() {
+        let stream = PullAudioOutputStream::create_pull_stream();
+        let writer = stream.clone().writer();
+        let reader = tokio::spawn(async move {
+            let mut buffer = [0; 4];
+            let mut chunks = Vec::new();
+            loop {
+                let read = stream.read(&mut buffer).await;
+                if read == 0 {
+                    return chunks;
+                }
+                chunks.push(buffer[..read].to_vec());
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        writer.write(&[1, 2, 3, 4, 5, 6]);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        writer.write(&[7]);
+        drop(writer);
+
+        let chunks = reader.await.unwrap();
+        assert_eq!(chunks.concat(), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(chunks[0], [1, 2, 3, 4]);
+    }
+
+    struct Collect(Arc<StdMutex<(Vec<u8>, usize)>>);
+
+    impl PushAudioOutputStreamCallback for Collect {
+        fn write(&mut self, data: &[u8]) -> usize {
+            self.0.lock().unwrap().0.extend_from_slice(data);
+            data.len()
+        }
+
+        fn close(&mut self) {
+            self.0.lock().unwrap().1 += 1;
+        }
+    }
+
+    #[test]
+    fn push_stream_forwards_writes_and_closes_once() {
+        let collected = Arc::new(StdMutex::new((Vec::new(), 0)));
+        let mut writer =
+            PushAudioOutputStream::create_push_stream(Collect(collected.clone())).writer();
+        writer.write(&[1, 2]);
+        writer.write(&[]);
+        writer.write(&[3]);
+        writer.close();
+        writer.write(&[4]);
+        drop(writer);
+        assert_eq!(*collected.lock().unwrap(), (vec![1, 2, 3], 1));
+    }
+}
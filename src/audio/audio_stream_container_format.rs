@@ -0,0 +1,57 @@
+// AudioStreamContainerFormat defines supported audio stream container format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioStreamContainerFormat {
+    // OggOpus indicates OGG OPUS.
+    OggOpus = 0x101,
+
+    // Mp3 indicates MP3.
+    Mp3 = 0x102,
+
+    // Flac indicates FLAC.
+    Flac = 0x103,
+
+    // ALaw indicates ALAW.
+    ALaw = 0x104,
+
+    // MuLaw indicates MULAW.
+    MuLaw = 0x105,
+
+    // AmrNb indicates AMRNB.
+    AmrNb = 0x106,
+
+    // AmrWb indicates AMRWB.
+    AmrWb = 0x107,
+
+    // Any indicates any other or unknown format.
+    Any = 0x108,
+}
+
+impl AudioStreamContainerFormat {
+    // content_type is the MIME type the audio is sent to the service with.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AudioStreamContainerFormat::OggOpus => "audio/ogg; codecs=opus",
+            AudioStreamContainerFormat::Mp3 => "audio/mpeg",
+            AudioStreamContainerFormat::Flac => "audio/flac",
+            // A-law and mu-law audio is sent in a wave container.
+            AudioStreamContainerFormat::ALaw | AudioStreamContainerFormat::MuLaw => "audio/x-wav",
+            AudioStreamContainerFormat::AmrNb => "audio/amr",
+            AudioStreamContainerFormat::AmrWb => "audio/amr-wb",
+            AudioStreamContainerFormat::Any => "application/octet-stream",
+        }
+    }
+
+    // encoding is the name of the encoding in the audio description sent to the service.
+    pub(crate) fn encoding(&self) -> &'static str {
+        match self {
+            AudioStreamContainerFormat::OggOpus => "OGG_OPUS",
+            AudioStreamContainerFormat::Mp3 => "MP3",
+            AudioStreamContainerFormat::Flac => "FLAC",
+            AudioStreamContainerFormat::ALaw => "ALAW",
+            AudioStreamContainerFormat::MuLaw => "MULAW",
+            AudioStreamContainerFormat::AmrNb => "AMRNB",
+            AudioStreamContainerFormat::AmrWb => "AMRWB",
+            AudioStreamContainerFormat::Any => "ANY",
+        }
+    }
+}
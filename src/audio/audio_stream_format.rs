@@ -0,0 +1,319 @@
+use serde_json::json;
+
+use crate::audio::AudioStreamContainerFormat;
+use crate::error::{Error, Result};
+use crate::usp;
+
+// WAVE_FORMAT_PCM is the format tag of uncompressed PCM audio.
+pub(crate) const WAVE_FORMAT_PCM: u16 = 1;
+
+// WAVE_FORMAT_ALAW is the format tag of A-law companded audio.
+pub(crate) const WAVE_FORMAT_ALAW: u16 = 6;
+
+// WAVE_FORMAT_MULAW is the format tag of mu-law companded audio.
+pub(crate) const WAVE_FORMAT_MULAW: u16 = 7;
+
+// SUPPORTED_SAMPLE_RATES are the sample rates accepted for PCM audio.
+const SUPPORTED_SAMPLE_RATES: [u32; 8] = [8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000];
+
+// SUPPORTED_BITS_PER_SAMPLE are the bit depths accepted for PCM audio.
+const SUPPORTED_BITS_PER_SAMPLE: [u8; 4] = [8, 16, 24, 32];
+
+// MAX_CHANNELS is the highest channel count accepted for PCM audio.
+const MAX_CHANNELS: u8 = 16;
+
+// AudioStreamFormat represents specific audio configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioStreamFormat {
+    format_tag: u16,
+    samples_per_second: u32,
+    bits_per_sample: u16,
+    channels: u16,
+    container: Option<AudioStreamContainerFormat>,
+}
+
+impl AudioStreamFormat {
+    // get_default_input_format creates an audio stream format object representing the default audio stream
+    // format (16KHz 16bit mono PCM).
+    pub fn get_default_input_format() -> Self {
+        AudioStreamFormat::pcm(16000, 16, 1)
+    }
+
+    // get_default_output_format creates an audio stream format object representing the default audio stream
+    // format (16KHz 16bit mono PCM).
+    pub fn get_default_output_format() -> Self {
+        AudioStreamFormat::pcm(16000, 16, 1)
+    }
+
+    // get_wave_format_pcm creates an audio stream format object with the specified PCM waveformat
+    // characteristics. Sample rates from 8KHz to 48KHz, 8, 16, 24 or 32 bits per sample and up to 16 channels
+    // are supported; other values are rejected with Error::UnsupportedFormat.
+    pub fn get_wave_format_pcm(
+        samples_per_second: u32,
+        bits_per_sample: u8,
+        channels: u8,
+    ) -> Result<Self> {
+        if !SUPPORTED_SAMPLE_RATES.contains(&samples_per_second) {
+            return Err(Error::UnsupportedFormat(format!(
+                "sample rate {} Hz is not supported, expected one of {:?}",
+                samples_per_second, SUPPORTED_SAMPLE_RATES
+            )));
+        }
+        if !SUPPORTED_BITS_PER_SAMPLE.contains(&bits_per_sample) {
+            return Err(Error::UnsupportedFormat(format!(
+                "{} bits per sample is not supported, expected one of {:?}",
+                bits_per_sample, SUPPORTED_BITS_PER_SAMPLE
+            )));
+        }
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(Error::UnsupportedFormat(format!(
+                "{} channels is not supported, expected 1 to {}",
+                channels, MAX_CHANNELS
+            )));
+        }
+        Ok(AudioStreamFormat::pcm(
+            samples_per_second,
+            bits_per_sample.into(),
+            channels.into(),
+        ))
+    }
+
+    // get_compressed_format creates an audio stream format object with the specified compressed audio
+    // container format, to be used as input format. A-law and mu-law audio is 8KHz 8bit mono; the other
+    // containers describe their audio themselves.
+    pub fn get_compressed_format(container: AudioStreamContainerFormat) -> Self {
+        let (format_tag, samples_per_second, bits_per_sample) = match container {
+            AudioStreamContainerFormat::ALaw => (WAVE_FORMAT_ALAW, 8000, 8),
+            AudioStreamContainerFormat::MuLaw => (WAVE_FORMAT_MULAW, 8000, 8),
+            _ => (WAVE_FORMAT_PCM, 16000, 16),
+        };
+        AudioStreamFormat {
+            format_tag,
+            samples_per_second,
+            bits_per_sample,
+            channels: 1,
+            container: Some(container),
+        }
+    }
+
+    fn pcm(samples_per_second: u32, bits_per_sample: u16, channels: u16) -> Self {
+        AudioStreamFormat {
+            format_tag: WAVE_FORMAT_PCM,
+            samples_per_second,
+            bits_per_sample,
+            channels,
+            container: None,
+        }
+    }
+
+    // from_wave_format creates the format described by the fmt chunk of a wave file. PCM audio is validated
+    // like get_wave_format_pcm; A-law and mu-law audio must be 8KHz 8bit mono.
+    pub(crate) fn from_wave_format(
+        format_tag: u16,
+        samples_per_second: u32,
+        bits_per_sample: u16,
+        channels: u16,
+    ) -> Result<Self> {
+        let container = match format_tag {
+            WAVE_FORMAT_PCM => {
+                return AudioStreamFormat::get_wave_format_pcm(
+                    samples_per_second,
+                    u8::try_from(bits_per_sample).unwrap_or(0),
+                    u8::try_from(channels).unwrap_or(0),
+                );
+            }
+            WAVE_FORMAT_ALAW => AudioStreamContainerFormat::ALaw,
+            WAVE_FORMAT_MULAW => AudioStreamContainerFormat::MuLaw,
+            tag => {
+                return Err(Error::UnsupportedFormat(format!(
+                    "wave format tag 0x{:04x} is not supported",
+                    tag
+                )));
+            }
+        };
+        if (samples_per_second, bits_per_sample, channels) != (8000, 8, 1) {
+            return Err(Error::UnsupportedFormat(format!(
+                "{} audio must be 8KHz 8bit mono, got {} Hz, {} bits, {} channels",
+                container.encoding(),
+                samples_per_second,
+                bits_per_sample,
+                channels
+            )));
+        }
+        Ok(AudioStreamFormat::get_compressed_format(container))
+    }
+
+    // samples_per_second is the number of samples per second of the audio.
+    pub fn samples_per_second(&self) -> u32 {
+        self.samples_per_second
+    }
+
+    // bits_per_sample is the number of bits of each sample of the audio.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    // channels is the number of channels of the audio.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    // container_format is the compressed container format of the audio, or None for PCM audio.
+    pub fn container_format(&self) -> Option<AudioStreamContainerFormat> {
+        self.container
+    }
+
+    pub(crate) fn block_align(&self) -> u16 {
+        self.channels * self.bits_per_sample.div_ceil(8)
+    }
+
+    pub(crate) fn average_bytes_per_second(&self) -> u32 {
+        self.samples_per_second * u32::from(self.block_align())
+    }
+
+    // content_type is the MIME type the audio is sent to the service with.
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self.container {
+            Some(container) => container.content_type(),
+            None => usp::CONTENT_TYPE_WAVE,
+        }
+    }
+
+    // header is sent to the service before the audio data: a RIFF header for PCM, A-law and mu-law audio,
+    // nothing for containers that describe their audio themselves.
+    pub(crate) fn header(&self) -> Vec<u8> {
+        match self.container {
+            None | Some(AudioStreamContainerFormat::ALaw | AudioStreamContainerFormat::MuLaw) => {
+                self.wave_header()
+            }
+            Some(_) => Vec::new(),
+        }
+    }
+
+    // speech_config_description describes the audio in the speech.config message.
+    pub(crate) fn speech_config_description(&self) -> serde_json::Value {
+        json!({
+            "bitspersample": self.bits_per_sample,
+            "channelcount": self.channels,
+            "samplerate": self.samples_per_second,
+            "encoding": self.container.map_or("PCM", |container| container.encoding()),
+        })
+    }
+
+    // wave_header is the RIFF header of the audio. The data length is unknown when streaming, so it is left
+    // as zero. Formats other than PCM carry the (empty) extension size in the fmt chunk.
+    pub(crate) fn wave_header(&self) -> Vec<u8> {
+        let extended = self.format_tag != WAVE_FORMAT_PCM;
+        let mut header = Vec::with_capacity(46);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&(if extended { 18u32 } else { 16u32 }).to_le_bytes());
+        header.extend_from_slice(&self.format_tag.to_le_bytes());
+        header.extend_from_slice(&self.channels.to_le_bytes());
+        header.extend_from_slice(&self.samples_per_second.to_le_bytes());
+        header.extend_from_slice(&self.average_bytes_per_second().to_le_bytes());
+        header.extend_from_slice(&self.block_align().to_le_bytes());
+        header.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+        if extended {
+            header.extend_from_slice(&0u16.to_le_bytes());
+        }
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header
+    }
+
+    // wave_file_header is the RIFF header of a wave file holding data_length bytes of audio.
+    pub(crate) fn wave_file_header(&self, data_length: u32) -> Vec<u8> {
+        let mut header = self.wave_header();
+        let riff_length = (header.len() as u32 - 8).saturating_add(data_length);
+        let data_length_at = header.len() - 4;
+        header[4..8].copy_from_slice(&riff_length.to_le_bytes());
+        header[data_length_at..].copy_from_slice(&data_length.to_le_bytes());
+        header
+    }
+}
+
+impl Default for AudioStreamFormat {
+    fn default() -> Self {
+        AudioStreamFormat::get_default_input_format()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wave_format_pcm_validates_characteristics() {
+        let format = AudioStreamFormat::get_wave_format_pcm(8000, 16, 2).unwrap();
+        assert_eq!(format.samples_per_second(), 8000);
+        assert_eq!(format.bits_per_sample(), 16);
+        assert_eq!(format.channels(), 2);
+        assert_eq!(format.container_format(), None);
+        assert_eq!(format.average_bytes_per_second(), 32000);
+
+        for (rate, bits, channels) in [
+            (7000, 16, 1),
+            (16000, 12, 1),
+            (16000, 16, 0),
+            (16000, 16, 17),
+        ] {
+            assert!(matches!(
+                AudioStreamFormat::get_wave_format_pcm(rate, bits, channels),
+                Err(Error::UnsupportedFormat(_))
+            ));
+        }
+        assert_eq!(
+            AudioStreamFormat::get_default_output_format(),
+            AudioStreamFormat::get_wave_format_pcm(16000, 16, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn telephony_formats_are_sent_as_companded_wave() {
+        let alaw = AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::ALaw);
+        let header = alaw.header();
+        assert_eq!(header.len(), 46);
+        assert_eq!(
+            u16::from_le_bytes([header[20], header[21]]),
+            WAVE_FORMAT_ALAW
+        );
+        assert_eq!(u32::from_le_bytes(header[24..28].try_into().unwrap()), 8000);
+        assert_eq!(alaw.content_type(), "audio/x-wav");
+
+        let mulaw = AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::MuLaw);
+        assert_eq!(
+            u16::from_le_bytes([mulaw.header()[20], mulaw.header()[21]]),
+            WAVE_FORMAT_MULAW
+        );
+        let description = mulaw.speech_config_description();
+        assert_eq!(description["encoding"], "MULAW");
+        assert_eq!(description["samplerate"], 8000);
+        assert_eq!(description["bitspersample"], 8);
+    }
+
+    #[test]
+    fn compressed_containers_are_sent_without_wave_header() {
+        let pcm = AudioStreamFormat::get_default_input_format();
+        assert_eq!(pcm.header().len(), 44);
+        assert_eq!(pcm.speech_config_description()["encoding"], "PCM");
+
+        let opus = AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::OggOpus);
+        assert!(opus.header().is_empty());
+        assert_eq!(opus.content_type(), "audio/ogg; codecs=opus");
+        assert_eq!(opus.speech_config_description()["encoding"], "OGG_OPUS");
+        for container in [
+            AudioStreamContainerFormat::Mp3,
+            AudioStreamContainerFormat::Flac,
+            AudioStreamContainerFormat::AmrNb,
+            AudioStreamContainerFormat::AmrWb,
+            AudioStreamContainerFormat::Any,
+        ] {
+            let format = AudioStreamFormat::get_compressed_format(container);
+            assert_eq!(format.container_format(), Some(container));
+            assert!(format.header().is_empty());
+        }
+    }
+}
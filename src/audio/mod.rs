@@ -0,0 +1,20 @@
+mod audio_config;
+mod audio_data_stream;
+mod audio_input_stream;
+mod audio_output_stream;
+mod audio_stream_container_format;
+mod audio_stream_format;
+mod wav;
+
+pub use audio_config::AudioConfig;
+pub(crate) use audio_config::{InputSource, OutputSink};
+pub use audio_data_stream::AudioDataStream;
+pub(crate) use audio_data_stream::AudioDataWriter;
+pub use audio_input_stream::{
+    AudioInputStream, PullAudioInputStream, PullAudioInputStreamCallback, PushAudioInputStream,
+};
+pub use audio_output_stream::{
+    AudioOutputStream, PullAudioOutputStream, PushAudioOutputStream, PushAudioOutputStreamCallback,
+};
+pub use audio_stream_container_format::AudioStreamContainerFormat;
+pub use audio_stream_format::AudioStreamFormat;
@@ -0,0 +1,410 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::audio::AudioStreamFormat;
+use crate::error::{Error, Result};
+
+// WAVE_FORMAT_EXTENSIBLE is the format tag of a fmt chunk whose actual format is given by a sub format GUID.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+// MAX_FMT_CHUNK_SIZE bounds the size of the fmt chunk read into memory.
+const MAX_FMT_CHUNK_SIZE: u32 = 1024;
+
+// SUBFORMAT_GUID_SUFFIX is the common tail of the KSDATAFORMAT_SUBTYPE_* GUIDs; the first two bytes hold the
+// format tag.
+const SUBFORMAT_GUID_SUFFIX: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+// WavHeader is the result of parsing the chunks of a wave file up to the start of its audio data.
+#[derive(Debug)]
+pub(crate) struct WavHeader {
+    pub(crate) format: AudioStreamFormat,
+
+    // data_length is the length of the data chunk, or None when the file was written while streaming and the
+    // length is unknown, in which case the data extends to the end of the file.
+    pub(crate) data_length: Option<u32>,
+
+    // data_offset is the position of the first byte of audio data in the file.
+    pub(crate) data_offset: u64,
+}
+
+// read_header reads the RIFF header of a wave file and the chunks that precede the audio data, leaving reader
+// positioned at the start of the data. Chunks other than fmt and data, such as LIST, are skipped.
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> Result<WavHeader> {
+    let mut riff = [0; 12];
+    read_exact(reader, &mut riff, "RIFF header")?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(Error::InvalidHeader("not a RIFF/WAVE file".to_string()));
+    }
+
+    let mut offset = riff.len() as u64;
+    let mut format = None;
+    loop {
+        let mut chunk = [0; 8];
+        read_exact(reader, &mut chunk, "chunk header")?;
+        offset += chunk.len() as u64;
+        let id = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        match &id {
+            b"fmt " => {
+                if size > MAX_FMT_CHUNK_SIZE {
+                    return Err(Error::InvalidHeader(format!(
+                        "fmt chunk of {} bytes is too large",
+                        size
+                    )));
+                }
+                let mut body = vec![0; size as usize];
+                read_exact(reader, &mut body, "fmt chunk")?;
+                format = Some(parse_format(&body)?);
+            }
+            b"data" => {
+                let format = format.ok_or_else(|| {
+                    Error::InvalidHeader("data chunk before fmt chunk".to_string())
+                })?;
+                let data_length = match size {
+                    0 | u32::MAX => None,
+                    size => Some(size),
+                };
+                return Ok(WavHeader {
+                    format,
+                    data_length,
+                    data_offset: offset,
+                });
+            }
+            _ => skip(reader, u64::from(size), &id)?,
+        }
+        // Chunks are word aligned.
+        let padding = u64::from(size % 2);
+        skip(reader, padding, &id)?;
+        offset += u64::from(size) + padding;
+    }
+}
+
+fn parse_format(body: &[u8]) -> Result<AudioStreamFormat> {
+    if body.len() < 16 {
+        return Err(Error::InvalidHeader(format!(
+            "fmt chunk is {} bytes, expected at least 16",
+            body.len()
+        )));
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+    let mut format_tag = u16_at(0);
+    let channels = u16_at(2);
+    let samples_per_second = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+    let block_align = u16_at(12);
+    let bits_per_sample = u16_at(14);
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        // cbSize (2), valid bits per sample (2), channel mask (4), sub format GUID (16).
+        if body.len() < 40 || u16_at(16) < 22 {
+            return Err(Error::InvalidHeader(
+                "extensible fmt chunk is too short".to_string(),
+            ));
+        }
+        let guid = &body[24..40];
+        if guid[2..] != SUBFORMAT_GUID_SUFFIX {
+            return Err(Error::UnsupportedFormat(
+                "unknown extensible sub format".to_string(),
+            ));
+        }
+        format_tag = u16::from_le_bytes([guid[0], guid[1]]);
+    }
+
+    let format = AudioStreamFormat::from_wave_format(
+        format_tag,
+        samples_per_second,
+        bits_per_sample,
+        channels,
+    )?;
+    if block_align != format.block_align() {
+        return Err(Error::InvalidHeader(format!(
+            "block align is {}, expected {}",
+            block_align,
+            format.block_align()
+        )));
+    }
+    Ok(format)
+}
+
+// WavFileWriter writes synthesized audio to a wave file. The lengths in the RIFF header are only known once all
+// audio was written, so they are fixed up by update_header, and when the writer is closed or dropped.
+pub(crate) struct WavFileWriter {
+    file: File,
+    // header is the RIFF header written at the start of the file, empty for formats such as MP3 that are
+    // written as they are.
+    header: Option<Vec<u8>>,
+    data_length: u64,
+    closed: bool,
+}
+
+impl WavFileWriter {
+    // create creates or truncates the file at path.
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).map_err(|e| Error::FileOpenFailed(path.display().to_string(), e))?;
+        Ok(WavFileWriter {
+            file,
+            header: None,
+            data_length: 0,
+            closed: false,
+        })
+    }
+
+    // begin writes the header for the audio format. It is written once, before any audio data.
+    pub(crate) fn begin(&mut self, format: &AudioStreamFormat) -> Result<()> {
+        if self.header.is_none() {
+            let header = format.header();
+            self.file.write_all(&header)?;
+            self.header = Some(header);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.file.write_all(data)?;
+        self.data_length += data.len() as u64;
+        Ok(())
+    }
+
+    // update_header writes the lengths of the audio data written so far to the RIFF header.
+    pub(crate) fn update_header(&mut self) -> Result<()> {
+        if let Some(header) = self.header.as_mut().filter(|header| !header.is_empty()) {
+            let data_length = u32::try_from(self.data_length).unwrap_or(u32::MAX);
+            let riff_length = (header.len() as u32 - 8).saturating_add(data_length);
+            let data_length_at = header.len() - 4;
+            header[4..8].copy_from_slice(&riff_length.to_le_bytes());
+            header[data_length_at..].copy_from_slice(&data_length.to_le_bytes());
+            self.file.seek(SeekFrom::Start(0))?;
+            self.file.write_all(header)?;
+            self.file.seek(SeekFrom::End(0))?;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    // close fixes up the header. Audio written afterwards is ignored.
+    pub(crate) fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        self.update_header()?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl Drop for WavFileWriter {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+fn read_exact<R: Read>(reader: &mut R, buffer: &mut [u8], what: &str) -> Result<()> {
+    reader.read_exact(buffer).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => {
+            Error::InvalidHeader(format!("wave file ends within the {}", what))
+        }
+        _ => Error::Io(e),
+    })
+}
+
+fn skip<R: Read>(reader: &mut R, length: u64, id: &[u8; 4]) -> Result<()> {
+    let skipped = io::copy(&mut reader.take(length), &mut io::sink())?;
+    if skipped < length {
+        return Err(Error::InvalidHeader(format!(
+            "wave file ends within the {} chunk",
+            String::from_utf8_lossy(id)
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioConfig, AudioStreamContainerFormat};
+    use crate::error::ErrorCode;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn fmt(format_tag: u16, channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits.div_ceil(8);
+        let mut body = format_tag.to_le_bytes().to_vec();
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&rate.to_le_bytes());
+        body.extend_from_slice(&(rate * u32::from(block_align)).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&bits.to_le_bytes());
+        body
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(&body);
+        file
+    }
+
+    fn error_code(bytes: &[u8]) -> ErrorCode {
+        read_header(&mut &bytes[..]).unwrap_err().code()
+    }
+
+    #[test]
+    fn reads_pcm_header_skipping_list_chunks() {
+        let file = wav(&[
+            chunk(b"LIST", b"INFOISFT\x03\x00\x00\x00abc"),
+            chunk(b"fmt ", &fmt(1, 1, 16000, 16)),
+            chunk(b"data", &[0; 6]),
+        ]);
+        let header = read_header(&mut &file[..]).unwrap();
+        assert_eq!(header.format, AudioStreamFormat::get_default_input_format());
+        assert_eq!(header.data_length, Some(6));
+        assert_eq!(&file[header.data_offset as usize..], &[0; 6]);
+    }
+
+    #[test]
+    fn reads_extensible_and_companded_formats() {
+        let mut extensible = fmt(0xfffe, 2, 48000, 16);
+        extensible.extend_from_slice(&22u16.to_le_bytes());
+        extensible.extend_from_slice(&16u16.to_le_bytes());
+        extensible.extend_from_slice(&3u32.to_le_bytes());
+        extensible.extend_from_slice(&1u16.to_le_bytes());
+        extensible.extend_from_slice(&SUBFORMAT_GUID_SUFFIX);
+        let file = wav(&[chunk(b"fmt ", &extensible), chunk(b"data", &[])]);
+        let header = read_header(&mut &file[..]).unwrap();
+        assert_eq!(
+            header.format,
+            AudioStreamFormat::get_wave_format_pcm(48000, 16, 2).unwrap()
+        );
+        assert_eq!(header.data_length, None);
+
+        let file = wav(&[chunk(b"fmt ", &fmt(7, 1, 8000, 8)), chunk(b"data", &[0xff])]);
+        let header = read_header(&mut &file[..]).unwrap();
+        assert_eq!(
+            header.format.container_format(),
+            Some(AudioStreamContainerFormat::MuLaw)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_headers_and_unsupported_encodings() {
+        assert_eq!(error_code(b"RIFX\0\0\0\0WAVE"), ErrorCode::InvalidHeader);
+        assert_eq!(error_code(b"RIFF\0\0"), ErrorCode::InvalidHeader);
+        assert_eq!(
+            error_code(&wav(&[chunk(b"data", &[0; 4])])),
+            ErrorCode::InvalidHeader
+        );
+        assert_eq!(
+            error_code(&wav(&[chunk(b"fmt ", &[1, 0, 1, 0])])),
+            ErrorCode::InvalidHeader
+        );
+        assert_eq!(
+            error_code(&wav(&[chunk(b"fmt ", &fmt(1, 1, 16000, 16))])),
+            ErrorCode::InvalidHeader
+        );
+        // MPEG layer 3.
+        assert_eq!(
+            error_code(&wav(&[
+                chunk(b"fmt ", &fmt(0x55, 1, 16000, 16)),
+                chunk(b"data", &[])
+            ])),
+            ErrorCode::UnsupportedFormat
+        );
+        assert_eq!(
+            error_code(&wav(&[
+                chunk(b"fmt ", &fmt(1, 1, 12345, 16)),
+                chunk(b"data", &[])
+            ])),
+            ErrorCode::UnsupportedFormat
+        );
+        assert_eq!(
+            error_code(&wav(&[
+                chunk(b"fmt ", &fmt(6, 1, 16000, 8)),
+                chunk(b"data", &[])
+            ])),
+            ErrorCode::UnsupportedFormat
+        );
+    }
+
+    #[test]
+    fn wav_file_writer_fixes_up_the_header() {
+        let path = std::env::temp_dir().join(format!("speech-sdk-{}.wav", uuid::Uuid::new_v4()));
+        let format = AudioStreamFormat::get_wave_format_pcm(24000, 16, 1).unwrap();
+        let mut writer = WavFileWriter::create(&path).unwrap();
+        writer.begin(&format).unwrap();
+        writer.begin(&format).unwrap();
+        writer.write(&[1, 2, 3, 4]).unwrap();
+        writer.update_header().unwrap();
+        writer.write(&[5, 6]).unwrap();
+        drop(writer);
+        let file = std::fs::read(&path).unwrap();
+        let header = read_header(&mut &file[..]).unwrap();
+        assert_eq!(header.format, format);
+        assert_eq!(header.data_length, Some(6));
+        assert_eq!(u32::from_le_bytes(file[4..8].try_into().unwrap()), 36 + 6);
+        assert_eq!(&file[header.data_offset as usize..], [1, 2, 3, 4, 5, 6]);
+
+        // Compressed audio is written without a header.
+        let mut writer = WavFileWriter::create(&path).unwrap();
+        writer
+            .begin(&AudioStreamFormat::get_compressed_format(
+                AudioStreamContainerFormat::Mp3,
+            ))
+            .unwrap();
+        writer.write(&[0xff, 0xfb]).unwrap();
+        writer.close().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), [0xff, 0xfb]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            WavFileWriter::create(&std::env::temp_dir().join("missing").join("out.wav")),
+            Err(Error::FileOpenFailed(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn from_wav_file_streams_only_the_data_chunk() {
+        let path = std::env::temp_dir().join(format!("speech-sdk-{}.wav", uuid::Uuid::new_v4()));
+        let file = wav(&[
+            chunk(b"fmt ", &fmt(1, 1, 8000, 16)),
+            chunk(b"data", &[1, 2, 3, 4]),
+            chunk(b"LIST", b"INFO"),
+        ]);
+        std::fs::write(&path, file).unwrap();
+        let config = AudioConfig::from_wav_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            config.get_property(crate::common::PropertyId::AudioConfigAudioSource),
+            "File"
+        );
+        assert_eq!(
+            config.get_property(crate::common::PropertyId::AudioConfigSampleRateForCapture),
+            "8000"
+        );
+
+        let mut source = config.source.unwrap();
+        let mut audio = Vec::new();
+        while let Some(buffer) = source.read_chunk(3).await.unwrap() {
+            audio.extend_from_slice(&buffer.data);
+        }
+        assert_eq!(audio, [1, 2, 3, 4]);
+
+        assert!(matches!(
+            AudioConfig::from_wav_file(std::env::temp_dir().join("does-not-exist.wav")),
+            Err(Error::FileOpenFailed(..))
+        ));
+    }
+}
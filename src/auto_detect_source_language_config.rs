@@ -0,0 +1,314 @@
+use serde_json::json;
+
+use crate::common::{PropertyCollection, PropertyId};
+use crate::error::{Error, Result};
+use crate::source_language_config::SourceLanguageConfig;
+use crate::speech_recognizer::SpeechRecognitionResult;
+
+// LANGUAGE_ID_ONLY is the name of the property set on source language recognizers, which detect the language
+// without transcribing the audio.
+pub(crate) const LANGUAGE_ID_ONLY: &str = "SPEECH-LanguageIdOnly";
+
+// OPEN_RANGE is the value of SpeechServiceConnectionAutoDetectSourceLanguages when any language may be detected.
+const OPEN_RANGE: &str = "UND";
+
+// AutoDetectSourceLanguageConfig defines the languages the recognizer detects the language of the audio among.
+//
+// By default the language is detected once, at the start of the audio (at-start detection), favoring latency;
+// SpeechServiceConnectionSingleLanguageIdPriority can be set to "Accuracy" on the speech config instead. Setting
+// SpeechServiceConnectionContinuousLanguageIdPriority ("Latency" or "Accuracy") detects the language of every
+// phrase (continuous detection), for audio in which the language changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoDetectSourceLanguageConfig {
+    languages: Vec<SourceLanguageConfig>,
+    open_range: bool,
+}
+
+impl AutoDetectSourceLanguageConfig {
+    // from_languages creates an instance of the AutoDetectSourceLanguageConfig with source languages.
+    pub fn from_languages(languages: &[&str]) -> Result<Self> {
+        let configs = languages
+            .iter()
+            .map(|language| SourceLanguageConfig::from_language(language))
+            .collect::<Result<Vec<_>>>()?;
+        AutoDetectSourceLanguageConfig::from_source_language_configs(configs)
+    }
+
+    // from_open_range creates an instance of the AutoDetectSourceLanguageConfig that detects any language
+    // supported by the service.
+    pub fn from_open_range() -> Self {
+        AutoDetectSourceLanguageConfig {
+            languages: Vec::new(),
+            open_range: true,
+        }
+    }
+
+    // from_source_language_configs creates an instance of the AutoDetectSourceLanguageConfig with a list of
+    // source language configs, which may name a custom model for their language.
+    pub fn from_source_language_configs(configs: Vec<SourceLanguageConfig>) -> Result<Self> {
+        if configs.is_empty() {
+            return Err(Error::InvalidArgument(
+                "at least one source language is required".to_string(),
+            ));
+        }
+        let mut config = AutoDetectSourceLanguageConfig {
+            languages: Vec::new(),
+            open_range: false,
+        };
+        for source in configs {
+            config.add_source_language_config(source)?;
+        }
+        Ok(config)
+    }
+
+    // add_source_language_config adds a source language to the languages detected among.
+    pub fn add_source_language_config(&mut self, config: SourceLanguageConfig) -> Result<()> {
+        if self.open_range {
+            return Err(Error::InvalidArgument(
+                "source languages cannot be added to an open range config".to_string(),
+            ));
+        }
+        let duplicate = self
+            .languages
+            .iter()
+            .any(|language| language.language().eq_ignore_ascii_case(config.language()));
+        if duplicate {
+            return Err(Error::InvalidArgument(format!(
+                "source language {} was already added",
+                config.language()
+            )));
+        }
+        self.languages.push(config);
+        Ok(())
+    }
+
+    // languages is the source languages detected among, empty for an open range config.
+    pub fn languages(&self) -> &[SourceLanguageConfig] {
+        &self.languages
+    }
+
+    // apply stores the config in the properties of a recognizer: the languages in
+    // SpeechServiceConnectionAutoDetectSourceLanguages, and the endpoint id of custom models by language.
+    pub(crate) fn apply(&self, properties: &mut PropertyCollection) {
+        if self.open_range {
+            properties.set_property(
+                PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+                OPEN_RANGE,
+            );
+            return;
+        }
+        let languages: Vec<_> = self.languages.iter().map(|l| l.language()).collect();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+            languages.join(","),
+        );
+        for language in &self.languages {
+            if let Some(endpoint_id) = language.endpoint_id() {
+                properties.set_property_by_string(&model_id_name(language.language()), endpoint_id);
+            }
+        }
+    }
+}
+
+// AutoDetectSourceLanguageResult contains the language detected for a recognition result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoDetectSourceLanguageResult {
+    // language is the detected language, or empty if no language was detected.
+    pub language: String,
+
+    // confidence is the confidence of the service in the detected language, such as "High" or "Low", or empty if
+    // no language was detected.
+    pub confidence: String,
+}
+
+impl AutoDetectSourceLanguageResult {
+    // from_result gets the language detected for the specified result.
+    pub fn from_result(result: &SpeechRecognitionResult) -> Self {
+        AutoDetectSourceLanguageResult {
+            language: result.properties.get_property(
+                PropertyId::SpeechServiceConnectionAutoDetectSourceLanguageResult,
+                "",
+            ),
+            confidence: result
+                .properties
+                .property(PropertyId::SpeechServiceResponseJsonResult)
+                .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+                .and_then(|json| {
+                    json["PrimaryLanguage"]["Confidence"]
+                        .as_str()
+                        .map(str::to_string)
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// model_id_name is the name of the property holding the endpoint id of the custom model for language.
+fn model_id_name(language: &str) -> String {
+    format!("{}SPEECH-ModelId", language)
+}
+
+// detect_continuously switches a source language recognizer to continuous detection, for continuous
+// recognition. The priority of single language detection is kept unless a continuous priority was set.
+pub(crate) fn detect_continuously(properties: &mut PropertyCollection) {
+    if properties
+        .property(PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority)
+        .is_some()
+    {
+        return;
+    }
+    let priority = properties.get_property(
+        PropertyId::SpeechServiceConnectionSingleLanguageIdPriority,
+        "Latency",
+    );
+    properties.set_property(
+        PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority,
+        priority,
+    );
+}
+
+// language_id_context is the part of the speech.context message that enables language detection, or None if no
+// source languages are configured.
+pub(crate) fn language_id_context(
+    properties: &PropertyCollection,
+) -> Result<Option<serde_json::Value>> {
+    let Some(languages) =
+        properties.property(PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages)
+    else {
+        return Ok(None);
+    };
+    let (mode, priority) = match properties
+        .property(PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority)
+    {
+        Some(priority) => ("DetectContinuous", priority),
+        None => (
+            "DetectAtAudioStart",
+            properties
+                .property(PropertyId::SpeechServiceConnectionSingleLanguageIdPriority)
+                .unwrap_or("Latency"),
+        ),
+    };
+    let priority = if priority.eq_ignore_ascii_case("Latency") {
+        "PrioritizeLatency"
+    } else if priority.eq_ignore_ascii_case("Accuracy") {
+        "PrioritizeAccuracy"
+    } else {
+        return Err(Error::InvalidArgument(format!(
+            "unknown language id priority {}",
+            priority
+        )));
+    };
+
+    let on_success = match properties.property_by_string(LANGUAGE_ID_ONLY) {
+        Some(_) => "None",
+        None => "Recognize",
+    };
+    let mut language_id = json!({
+        "mode": mode,
+        "priority": priority,
+        "onSuccess": { "action": on_success },
+        "onUnknown": { "action": "None" },
+    });
+    let languages: Vec<_> = languages
+        .split(',')
+        .map(str::trim)
+        .filter(|language| !language.is_empty() && *language != OPEN_RANGE)
+        .collect();
+    if !languages.is_empty() {
+        language_id["languages"] = json!(languages);
+    }
+    let mut context = json!({ "languageId": language_id });
+    let models: Vec<_> = languages
+        .iter()
+        .filter_map(|language| {
+            let endpoint_id = properties.property_by_string(&model_id_name(language))?;
+            Some(json!({ "language": language, "endpoint": endpoint_id }))
+        })
+        .collect();
+    if !models.is_empty() {
+        context["phraseDetection"] = json!({ "customModels": models });
+    }
+    Ok(Some(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configs_validate_languages() {
+        let config = AutoDetectSourceLanguageConfig::from_languages(&["en-US", "es-MX"]).unwrap();
+        assert_eq!(config.languages().len(), 2);
+        assert!(AutoDetectSourceLanguageConfig::from_languages(&[]).is_err());
+        assert!(AutoDetectSourceLanguageConfig::from_languages(&["en-US", "en-us"]).is_err());
+        assert!(AutoDetectSourceLanguageConfig::from_languages(&["en_US"]).is_err());
+        assert!(SourceLanguageConfig::from_language_and_endpoint_id("en-US", "").is_err());
+
+        let mut open = AutoDetectSourceLanguageConfig::from_open_range();
+        let english = SourceLanguageConfig::from_language("en-US").unwrap();
+        assert!(open.add_source_language_config(english).is_err());
+        let mut properties = PropertyCollection::new();
+        open.apply(&mut properties);
+        let context = language_id_context(&properties).unwrap().unwrap();
+        assert_eq!(context["languageId"]["languages"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn at_start_and_continuous_detection_contexts() {
+        let mut properties = PropertyCollection::new();
+        assert_eq!(language_id_context(&properties).unwrap(), None);
+
+        let config = AutoDetectSourceLanguageConfig::from_source_language_configs(vec![
+            SourceLanguageConfig::from_language("en-US").unwrap(),
+            SourceLanguageConfig::from_language_and_endpoint_id("es-ES", "spanish-model").unwrap(),
+        ])
+        .unwrap();
+        config.apply(&mut properties);
+        assert_eq!(
+            properties.get_property(
+                PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+                ""
+            ),
+            "en-US,es-ES"
+        );
+        assert_eq!(
+            language_id_context(&properties).unwrap().unwrap(),
+            json!({
+                "languageId": {
+                    "languages": ["en-US", "es-ES"],
+                    "mode": "DetectAtAudioStart",
+                    "priority": "PrioritizeLatency",
+                    "onSuccess": { "action": "Recognize" },
+                    "onUnknown": { "action": "None" },
+                },
+                "phraseDetection": {
+                    "customModels": [{ "language": "es-ES", "endpoint": "spanish-model" }],
+                },
+            })
+        );
+
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionSingleLanguageIdPriority,
+            "accuracy",
+        );
+        let context = language_id_context(&properties).unwrap().unwrap();
+        assert_eq!(context["languageId"]["priority"], "PrioritizeAccuracy");
+
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority,
+            "Latency",
+        );
+        let context = language_id_context(&properties).unwrap().unwrap();
+        assert_eq!(context["languageId"]["mode"], "DetectContinuous");
+        assert_eq!(context["languageId"]["priority"], "PrioritizeLatency");
+
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority,
+            "Fast",
+        );
+        assert!(matches!(
+            language_id_context(&properties),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -1,8 +1,14 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
 use thiserror::Error;
-use std::net::SocketAddr;
+use url::Url;
+
+use crate::error::Error;
 
-#[derive(Debug, Error)]
-enum CancellationError {
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CancellationError {
 
     // AuthenticationFailure indicates an authentication error.
 	// An authentication error occurs if subscription key or authorization token is invalid, expired,
@@ -43,10 +49,93 @@ enum CancellationError {
     RuntimeError
 }
 
-type CancellationErrorDetails = serde_json::Value;
+impl CancellationError {
+    // from_http_status maps the HTTP status of a rejected request or WebSocket handshake to a cancellation error.
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            400 => CancellationError::BadRequest,
+            401 => CancellationError::AuthenticationFailure,
+            403 => CancellationError::Forbidden,
+            408 | 504 => CancellationError::ServiceTimeout,
+            429 => CancellationError::TooManyRequests,
+            503 => CancellationError::ServiceUnavailable,
+            _ => CancellationError::ServiceError,
+        }
+    }
+
+    // from_close_code maps the code of a WebSocket close frame sent by the service to a cancellation error.
+    pub fn from_close_code(code: u16) -> Self {
+        match code {
+            1006 => CancellationError::ConnectionFailure,
+            1007 | 1008 => CancellationError::BadRequest,
+            1013 => CancellationError::TooManyRequests,
+            _ => CancellationError::ServiceError,
+        }
+    }
+
+    // from_recognition_status maps the RecognitionStatus of a speech.phrase message to a cancellation error.
+    // None is returned for statuses that do not cancel recognition, such as Success or NoMatch.
+    pub fn from_recognition_status(status: &str) -> Option<Self> {
+        match status {
+            "Success"
+            | "NoMatch"
+            | "InitialSilenceTimeout"
+            | "BabbleTimeout"
+            | "EndOfDictation" => None,
+            "BadRequest" => Some(CancellationError::BadRequest),
+            "Unauthorized" => Some(CancellationError::AuthenticationFailure),
+            "Forbidden" => Some(CancellationError::Forbidden),
+            "TooManyRequests" => Some(CancellationError::TooManyRequests),
+            "ServiceUnavailable" => Some(CancellationError::ServiceUnavailable),
+            "Timeout" => Some(CancellationError::ServiceTimeout),
+            _ => Some(CancellationError::ServiceError),
+        }
+    }
+}
+
+// CancellationErrorDetails describes why the service or the sdk canceled an operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CancellationErrorDetails {
+    // reason is the short reason text, e.g. the recognition status or the HTTP status reported by the service.
+    pub reason: String,
+
+    // detailed_text is the detailed error message, as reported by the service if available.
+    pub detailed_text: String,
+}
+
+impl CancellationErrorDetails {
+    pub fn new(reason: impl Into<String>, detailed_text: impl Into<String>) -> Self {
+        CancellationErrorDetails {
+            reason: reason.into(),
+            detailed_text: detailed_text.into(),
+        }
+    }
+
+    // from_service_payload parses the JSON error body returned by the service, which is either
+    // {"error": {"code": ..., "message": ...}} or {"code": ..., "message": ...}. Payloads that are not JSON are
+    // kept as detailed text.
+    pub fn from_service_payload(default_reason: &str, payload: &str) -> Self {
+        let json = serde_json::from_str::<serde_json::Value>(payload).unwrap_or_default();
+        let error = json.get("error").unwrap_or(&json);
+        let reason = error["code"].as_str().unwrap_or(default_reason);
+        let detailed_text = error["message"].as_str().unwrap_or(payload.trim());
+        CancellationErrorDetails::new(reason, detailed_text)
+    }
+}
+
+impl fmt::Display for CancellationErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.reason.is_empty(), self.detailed_text.is_empty()) {
+            (_, true) => write!(f, "{}", self.reason),
+            (true, false) => write!(f, "{}", self.detailed_text),
+            (false, false) => write!(f, "{}: {}", self.reason, self.detailed_text),
+        }
+    }
+}
 
 // CancellationReason defines the possible reasons a recognition result might be canceled.
-enum CancellationReason {
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancellationReason {
 
     // Error indicates that an error occurred during speech recognition.
     Error(CancellationError, CancellationErrorDetails),
@@ -58,232 +147,346 @@ enum CancellationReason {
     CancelledByUser
 }
 
-enum OutputFormat {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
     Simple,
     Detailed
 }
 
-enum ProfanityOption{
+impl OutputFormat {
+    // as_str is the value of the output format as sent to the service.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Simple => "simple",
+            OutputFormat::Detailed => "detailed",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "simple" => Ok(OutputFormat::Simple),
+            "detailed" => Ok(OutputFormat::Detailed),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown output format {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfanityOption {
     Masked,
     Removed,
     Raw
 }
 
-struct PropertyCollection {
+impl ProfanityOption {
+    // as_str is the value of the profanity option as sent to the service.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProfanityOption::Masked => "masked",
+            ProfanityOption::Removed => "removed",
+            ProfanityOption::Raw => "raw",
+        }
+    }
+}
+
+impl FromStr for ProfanityOption {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "masked" => Ok(ProfanityOption::Masked),
+            "removed" => Ok(ProfanityOption::Removed),
+            "raw" => Ok(ProfanityOption::Raw),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown profanity option {}",
+                s
+            ))),
+        }
+    }
+}
+
+// PropertyId defines speech property ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyId {
     // SpeechServiceConnectionKey is the Cognitive Services Speech Service subscription key. If you are using an
 	// intent recognizer, you need to specify the LUIS endpoint key for your particular LUIS app. Under normal
 	// circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_key: String,
+    SpeechServiceConnectionKey = 1000,
 
     // SpeechServiceConnectionEndpoint is the Cognitive Services Speech Service endpoint (url).
 	// Under normal circumstances, you shouldn't have to use this property directly.
 	// NOTE: This endpoint is not the same as the endpoint used to obtain an access token.
-    speech_service_connection_endpoint: SocketAddr,
+    SpeechServiceConnectionEndpoint = 1001,
 
     // SpeechServiceConnectionRegion is the Cognitive Services Speech Service region. Under normal circumstances,
 	// you shouldn't have to use this property directly.
-    speech_service_connection_region: String,
+    SpeechServiceConnectionRegion = 1002,
 
     // SpeechServiceAuthorizationToken is the Cognitive Services Speech Service authorization token (aka access token).
 	// Under normal circumstances, you shouldn't have to use this property directly.
-    speech_service_authorization_token: String,
+    SpeechServiceAuthorizationToken = 1003,
 
     // SpeechServiceAuthorizationType is the Cognitive Services Speech Service authorization type. Currently unused.
-    speech_service_authorization_type: Option<String>,
+    SpeechServiceAuthorizationType = 1004,
 
     // SpeechServiceConnectionEndpointID is the Cognitive Services Custom Speech Service endpoint id. Under normal
-	// circumstances, you shouldn't have to use this property directly.
+    // circumstances, you shouldn't have to use this property directly.
     // NOTE: The endpoint id is available in the Custom Speech Portal, listed under Endpoint Details.
-    speech_service_connection_endpoint_id: String,
+    SpeechServiceConnectionEndpointId = 1005,
 
     // SpeechServiceConnectionHost is the Cognitive Services Speech Service host (url). Under normal circumstances,
 	// you shouldn't have to use this property directly.
-    service_speech_connection_host: String,
+    SpeechServiceConnectionHost = 1006,
 
     // SpeechServiceConnectionProxyHostName is the host name of the proxy server used to connect to the Cognitive Services
 	// Speech Service. Under normal circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_proxy_host_name: String,
+    SpeechServiceConnectionProxyHostName = 1100,
 
     // SpeechServiceConnectionProxyPort is the port of the proxy server used to connect to the Cognitive Services Speech
 	// Service. Under normal circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_proxy_port: u16,
+    SpeechServiceConnectionProxyPort = 1101,
 
     // SpeechServiceConnectionProxyUserName is the user name of the proxy server used to connect to the Cognitive Services
 	// Speech Service. Under normal circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_proxy_user_name: String,
+    SpeechServiceConnectionProxyUserName = 1102,
 
     // SpeechServiceConnectionProxyPassword is the password of the proxy server used to connect to the Cognitive Services
 	// Speech Service. Under normal circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_proxy_password: String,
+    SpeechServiceConnectionProxyPassword = 1103,
+
+    // SpeechServiceConnectionURL is the URL string built from speech configuration. This property is intended to be read-only.
+    // The SDK is using it internally.
+    SpeechServiceConnectionUrl = 1104,
 
     // SpeechServiceConnectionTranslationToLanguages is the list languages used as target translation
 	// languages. Under normal circumstances, you shouldn't have to use this property directly.
-
-    speech_service_connection_translation_to_languages: Vec<String>,
+    SpeechServiceConnectionTranslationToLanguages = 2000,
 
     // SpeechServiceConnectionTranslationVoice is the name of the Cognitive Service Text to Speech Service voice. Under normal
-	// circumstances, you shouldn't have to use this property directly.
+    // circumstances, you shouldn't have to use this property directly.
     // NOTE: Valid voice names can be found at https://aka.ms/csspeech/voicenames.
-    speech_service_connection_translation_voice: String,
+    SpeechServiceConnectionTranslationVoice = 2001,
 
     // SpeechServiceConnectionTranslationFeatures is the translation features. For internal use.
-    speech_service_connection_translation_features: Vec<String>,
+    SpeechServiceConnectionTranslationFeatures = 2002,
 
     // SpeechServiceConnectionIntentRegion is the Language Understanding Service region. Under normal circumstances, you
 	// shouldn't have to use this property directly.
-    speech_service_connection_intent_region: String,
+    SpeechServiceConnectionIntentRegion = 2003,
 
     // This property is intended to be read-only. The SDK is using it internally.
-    speech_service_connection_reco_mode: RecognitionMode,
+    SpeechServiceConnectionRecoMode = 3000,
 
     // SpeechServiceConnectionRecoLanguage is the spoken language to be recognized (in BCP-47 format). Under normal
 	// circumstances, you shouldn't have to use this property directly.
-    speech_service_connection_reco_language: String,
+    SpeechServiceConnectionRecoLanguage = 3001,
 
     // SpeechSessionID is the session id. This id is a universally unique identifier (aka UUID) representing a specific
 	// binding of an audio input stream and the underlying speech recognition instance to which it is bound. Under normal
 	// circumstances, you shouldn't have to use this property directly.
-    speech_session_id: String,
+    SpeechSessionId = 3002,
 
     // SpeechServiceConnectionUserDefinedQueryParameters are the query parameters provided by users. They will be passed
 	// to the service as URL query parameters.
-    speech_service_connection_user_defined_query_parameters: std::collections::HashMap<String, String>,
+    SpeechServiceConnectionUserDefinedQueryParameters = 3003,
+
+    // SpeechServiceConnectionRecoBackend is the string to specify the backend to be used for speech recognition;
+    // allowed options are online and offline. Under normal circumstances, you shouldn't use this property directly.
+    SpeechServiceConnectionRecoModelBackend = 3004,
+
+    // SpeechServiceConnectionRecoModelName is the name of the model to be used for speech recognition.
+    // Under normal circumstances, you shouldn't use this property directly.
+    SpeechServiceConnectionRecoModelName = 3005,
+
+    // SpeechServiceConnectionRecoModelKey is the decryption key of the model to be used for speech recognition.
+    // Under normal circumstances, you shouldn't use this property directly.
+    SpeechServiceConnectionRecoModelKey = 3006,
+
+    // SpeechServiceConnectionRecoModelIniFile is the path to the ini file of the model to be used for speech recognition.
+    // Under normal circumstances, you shouldn't use this property directly.
+    SpeechServiceConnectionRecoModelIniFile = 3007,
 
     // SpeechServiceConnectionSynthLanguage is the spoken language to be synthesized (e.g. en-US)
-    speech_service_connection_synth_language: String,
+    SpeechServiceConnectionSynthLanguage = 3100,
 
     // SpeechServiceConnectionSynthVoice is the name of the TTS voice to be used for speech synthesis
-    speech_connection_synth_voice: String,
+    SpeechServiceConnectionSynthVoice = 3101,
 
     // SpeechServiceConnectionSynthOutputFormat is the string to specify TTS output audio format.
-    speech_connection_synth_output_format: String,
+    SpeechServiceConnectionSynthOutputFormat = 3102,
 
     // SpeechServiceConnectionSynthEnableCompressedAudioTransmission indicates if use compressed audio format
 	// for speech synthesis audio transmission.
 	// This property only affects when SpeechServiceConnectionSynthOutputFormat is set to a pcm format.
-    speech_service_connection_synth_enable_compressed_audio_transmission: String,
+    SpeechServiceConnectionSynthEnableCompressedAudioTransmission = 3103,
+
+    // SpeechServiceConnectionSynthBackend is the string to specify TTS backend; valid options are online and offline.
+    SpeechServiceConnectionSynthBackend = 3110,
+
+    // SpeechServiceConnectionSynthOfflineDataPath is the data file path(s) for offline synthesis engine.
+    SpeechServiceConnectionSynthOfflineDataPath = 3112,
+
+    // SpeechServiceConnectionSynthOfflineVoice is the name of the offline TTS voice to be used for speech synthesis.
+    SpeechServiceConnectionSynthOfflineVoice = 3113,
+
+    // SpeechServiceConnectionSynthModelKey is the decryption key of the voice to be used for speech synthesis.
+    SpeechServiceConnectionSynthModelKey = 3114,
+
+    // SpeechServiceConnectionVoicesListEndpoint is the Cognitive Services Speech Service voices list api endpoint (url).
+    // Under normal circumstances, you don't need to specify this property, SDK will construct it based on the region/host/endpoint.
+    SpeechServiceConnectionVoicesListEndpoint = 3130,
 
     // SpeechServiceConnectionInitialSilenceTimeoutMs is the initial silence timeout value (in milliseconds) used by the service.
-    speech_service_connection_initial_silence_timeout_ms: u32,
+    SpeechServiceConnectionInitialSilenceTimeoutMs = 3200,
 
     // SpeechServiceConnectionEndSilenceTimeoutMs is the end silence timeout value (in milliseconds) used by the service.
-    speech_service_connection_end_silence_timeout_ms: u32,
+    SpeechServiceConnectionEndSilenceTimeoutMs = 3201,
 
     // value specifying whether audio logging is enabled in the service or not
-    speech_service_connection_enable_audio_logging: bool,
+    SpeechServiceConnectionEnableAudioLogging = 3202,
+
+    // SpeechServiceConnectionSingleLanguageIdPriority is the priority of single language id (Latency or Accuracy).
+    SpeechServiceConnectionSingleLanguageIdPriority = 3203,
+
+    // SpeechServiceConnectionContinuousLanguageIdPriority is the priority of continuous language id (Latency or Accuracy).
+    SpeechServiceConnectionContinuousLanguageIdPriority = 3204,
 
     // SpeechServiceConnectionAutoDetectSourceLanguages is the auto detect source languages
-    speech_service_connection_auto_detect_source_lan: Vec<String>,
+    SpeechServiceConnectionAutoDetectSourceLanguages = 3300,
 
     // SpeechServiceConnectionAutoDetectSourceLanguageResult is the auto detect source language result,
-    speech_service_connection_auto_detect_source_language_result: String,
+    SpeechServiceConnectionAutoDetectSourceLanguageResult = 3301,
+
+    // SpeechServiceResponseRequestDetailedResultTrueFalse the requested Cognitive Services Speech Service response output
+    // format (simple or detailed). Under normal circumstances, you shouldn't have to use this property directly.
+    SpeechServiceResponseRequestDetailedResultTrueFalse = 4000,
+
+    // SpeechServiceResponseRequestProfanityFilterTrueFalse is the requested Cognitive Services Speech Service response
+    // output profanity level: true masks profanity, false returns it raw. SpeechServiceResponseProfanityOption takes
+    // precedence.
+    SpeechServiceResponseRequestProfanityFilterTrueFalse = 4001,
 
     // SpeechServiceResponseProfanityOption is the requested Cognitive Services Speech Service response output profanity setting
-    speech_service_response_profanity_op: ProfanityOption,
+    SpeechServiceResponseProfanityOption = 4002,
 
     // SpeechServiceResponsePostProcessingOption a string value specifying which post processing option should be used
 	// by the service.
-    speech_service_response_post_processing_option: PostProcessingOption,
+    SpeechServiceResponsePostProcessingOption = 4003,
 
     // SpeechServiceResponseRequestWordLevelTimestamps is a boolean value specifying whether to include word-level
 	// timestamps in the response result.
-    speech_service_response_request_word_level_time: bool,
+    SpeechServiceResponseRequestWordLevelTimestamps = 4004,
 
     // SpeechServiceResponseStablePartialResultThreshold is the number of times a word has to be in partial results
 	// to be returned
-    speech_service_response_stable_partial_result_threshold: u8,
+    SpeechServiceResponseStablePartialResultThreshold = 4005,
 
     // SpeechServiceResponseOutputFormatOption is a string value specifying the output format option in the response
 	// result. Internal use only.
-    speech_service_response_output_format_option: OutputFormat,
+    SpeechServiceResponseOutputFormatOption = 4006,
+
+    // SpeechServiceResponseRequestSnr is a boolean value specifying whether to include SNR (signal to noise ratio) in the
+    // response result.
+    SpeechServiceResponseRequestSnr = 4007,
 
     // SpeechServiceResponseTranslationRequestStablePartialResult is a boolean value to request for stabilizing translation
 	// partial results by omitting words in the end.
-    speech_service_response_translation_request_stable_partial_result: bool,
+    SpeechServiceResponseTranslationRequestStablePartialResult = 4100,
 
     // SpeechServiceResponseRequestWordBoundary is a boolean value specifying whether to request WordBoundary events.
-    speech_service_response_request_word_bou: bool,
+    SpeechServiceResponseRequestWordBoundary = 4200,
 
     // SpeechServiceResponseRequestPunctuationBoundary is a boolean value specifying whether to request punctuation boundary
 	// in WordBoundary Events. Default is true.
-    speech_service_response_request_punctuation_boundary: bool,
+    SpeechServiceResponseRequestPunctuationBoundary = 4201,
 
     // SpeechServiceResponseRequestSentenceBoundary ia a boolean value specifying whether to request sentence boundary
 	// in WordBoundary Events. Default is false.
-    speech_service_request_response_sentence_boundary: bool,
+    SpeechServiceResponseRequestSentenceBoundary = 4202,
 
     // SpeechServiceResponseJSONResult is the Cognitive Services Speech Service response output (in JSON format). This
 	// property is available on recognition result objects only.
-    speech_service_response_jsonresult: serde_json::Value,
+    SpeechServiceResponseJsonResult = 5000,
+
+    // SpeechServiceResponseJSONErrorDetails is the Cognitive Services Speech Service error details (in JSON format).
+    // Under normal circumstances, you shouldn't have to use this property directly.
+    SpeechServiceResponseJsonErrorDetails = 5001,
 
     // SpeechServiceResponseRecognitionLatencyMs is the recognition latency in milliseconds. Read-only, available on final
 	// speech/translation/intent results. This measures the latency between when an audio input is received by the SDK, and
 	// the moment the final result is received from the service. The SDK computes the time difference between the last audio
 	// fragment from the audio input that is contributing to the final result, and the time the final result is received from
 	// the speech service.
-    speech_service_response_recognition_latency_ms: u64,
+    SpeechServiceResponseRecognitionLatencyMs = 5002,
 
     // SpeechServiceResponseSynthesisFirstByteLatencyMs is the speech synthesis first byte latency in milliseconds.
 	// Read-only, available on final speech synthesis results.
 	// This measures the latency between when the synthesis is started to be processed, and the moment the first byte audio is available.
-    speech_service_response_synthesis_first_byte_latency_ms: u32,
+    SpeechServiceResponseSynthesisFirstByteLatencyMs = 5010,
 
     // SpeechServiceResponseSynthesisFinishLatencyMs is the speech synthesis all bytes latency in milliseconds.
 	// Read-only, available on final speech synthesis results.
 	// This measures the latency between when the synthesis is started to be processed, and the moment the whole audio is synthesized.
-    speech_service_response_synthesis_finish_latency_ms: u64,
+    SpeechServiceResponseSynthesisFinishLatencyMs = 5011,
 
     // SpeechServiceResponseSynthesisUnderrunTimeMs is the underrun time for speech synthesis in milliseconds.
 	// Read-only, available on results in SynthesisCompleted events.
 	// This measures the total underrun time from AudioConfigPlaybackBufferLengthInMs is filled to synthesis completed.
-    speech_service_response_synthesis_underrun_time_ms: u64,
+    SpeechServiceResponseSynthesisUnderrunTimeMs = 5012,
 
     // SpeechServiceResponseSynthesisBackend indicates which backend the synthesis is finished by.
 	// Read-only, available on speech synthesis results, except for the result in SynthesisStarted event
-    speech_service_response_synthesis_backend: String,
+    SpeechServiceResponseSynthesisBackend = 5020,
 
     // CancellationDetailsReason is the cancellation reason. Currently unused.
-    cancellation_details_reason: CancellationReason,
+    CancellationDetailsReason = 6000,
 
     // CancellationDetailsReasonText the cancellation text. Currently unused.
-    cancellation_details_reason_text: String,
+    CancellationDetailsReasonText = 6001,
 
     // CancellationDetailsReasonDetailedText is the cancellation detailed text. Currently unused.
-    cancellation_details_reason_detailed_text: String,
+    CancellationDetailsReasonDetailedText = 6002,
 
     // LanguageUnderstandingServiceResponseJSONResult is the Language Understanding Service response output (in JSON format).
 	// Available via IntentRecognitionResult.Properties.
-    language_understanding_service_response_json_result: serde_json::Value,
+    LanguageUnderstandingServiceResponseJsonResult = 7000,
 
     // AudioConfigDeviceNameForCapture is the device name for audio capture. Under normal circumstances, you shouldn't have
 	// to use this property directly.
-    audio_config_device_name_for_capture: String,
-    
+    AudioConfigDeviceNameForCapture = 8000,
+
     // AudioConfigNumberOfChannelsForCapture is the number of channels for audio capture. Internal use only.
-    audio_config_number_of_channels_for_capture: u8,
-    
+    AudioConfigNumberOfChannelsForCapture = 8001,
+
     // AudioConfigSampleRateForCapture is the sample rate (in Hz) for audio capture. Internal use only.
-    audio_config_sample_rate_for_capture: u32,
+    AudioConfigSampleRateForCapture = 8002,
 
     // AudioConfigBitsPerSampleForCapture is the number of bits of each sample for audio capture. Internal use only.
-    audio_config_bits_per_sample_for_capture: u8,
+    AudioConfigBitsPerSampleForCapture = 8003,
 
     // AudioConfigAudioSource is the audio source
-    audio_config_audio_source: AudioSource,
+    AudioConfigAudioSource = 8004,
 
     // AudioConfigDeviceNameForRender indicates the device name for audio render. Under normal circumstances,
 	// you shouldn't have to use this property directly. Instead, use NewAudioConfigFromDefaultSpeakerOutput.
-    audio_config_device_name_for_render: String,
+    AudioConfigDeviceNameForRender = 8005,
 
     // AudioConfigPlaybackBufferLengthInMs indicates the playback buffer length in milliseconds, default is 50 milliseconds.
-    audio_config_playback_buffer_length_in_ms: u32,
+    AudioConfigPlaybackBufferLengthInMs = 8006,
 
     // AudioProcessingOptions provides advanced configuration for audio input for features like Voice Activity Detection
 	// and is provided in the form of a JSON string.
-    audio_processing_options: serde_json::Value,
+    AudioConfigAudioProcessingOptions = 8007,
 
     // SpeechLogFilename is the file name to write logs.
-    speech_log_file_name: String,
+    SpeechLogFilename = 9001,
 
     // SegmentationSilenceTimeoutMs specifies a duration of detected silence, measured in milliseconds, after which
 	// speech-to-text will determine a spoken phrase has ended and generate a final Recognized result. Configuring
@@ -294,72 +497,791 @@ struct PropertyCollection {
 	//
 	// For more information about timeout configuration that includes discussion of default behaviors, please visit
 	// https://aka.ms/csspeech/timeouts.
-    segmentation_silence_timeout_ms: u32,
+    SpeechSegmentationSilenceTimeoutMs = 9002,
 
     // ConversationApplicationID is the identifier used to connect to the backend service.
-    conversation_application_id: String,
+    ConversationApplicationId = 10000,
 
     // ConversationDialogType is the type of dialog backend to connect to.
-    conversation_dialog_type: String,
+    ConversationDialogType = 10001,
 
     // ConversationInitialSilenceTimeout is the silence timeout for listening.
-    conversation_initial_silence_timeout: u32,
+    ConversationInitialSilenceTimeout = 10002,
 
     // ConversationFromID is the FromId to be used on speech recognition activities.
-    conversation_from_id: String,
+    ConversationFromId = 10003,
 
     // ConversationConversationID is the ConversationId for the session.
-    conversation_conversation_id: String,
+    ConversationConversationId = 10004,
 
     // ConversationCustomVoiceDeploymentIDs is a list of custom voice deployment ids.
-    conversation_custom_voice_deployment_ids: Vec<String>,
+    ConversationCustomVoiceDeploymentIds = 10005,
+
+    // ConversationSpeechActivityTemplate is a template to stamp properties in the template on the activity generated by
+    // the service for speech.
+    ConversationSpeechActivityTemplate = 10006,
+
+    // ConversationParticipantID is the participant identifier in the current conversation.
+    ConversationParticipantId = 10007,
 
     // DataBufferTimeStamp is the time stamp associated to data buffer written by client when using Pull/Push
 	// audio input streams.
 	// The time stamp is a 64-bit value with a resolution of 90 kHz. It is the same as the presentation timestamp
 	// in an MPEG transport stream. See https://en.wikipedia.org/wiki/Presentation_timestamp
-    data_buffer_time_stamp: u64,
+    DataBufferTimeStamp = 11001,
 
     // DataBufferUserID is the user id associated to data buffer written by client when using Pull/Push audio
 	// input streams.
-    data_buffer_user_id: String
+    DataBufferUserId = 11002,
+
+    // PronunciationAssessmentReferenceText is the reference text of the audio for pronunciation evaluation.
+    // Under normal circumstances, you shouldn't have to use this property directly.
+    PronunciationAssessmentReferenceText = 12001,
+
+    // PronunciationAssessmentGradingSystem is the point system for pronunciation score calibration (FivePoint or
+    // HundredMark). Under normal circumstances, you shouldn't have to use this property directly.
+    PronunciationAssessmentGradingSystem = 12002,
+
+    // PronunciationAssessmentGranularity is the pronunciation evaluation granularity (Phoneme, Word, or FullText).
+    // Under normal circumstances, you shouldn't have to use this property directly.
+    PronunciationAssessmentGranularity = 12003,
+
+    // PronunciationAssessmentEnableMiscue defines if enable miscue calculation. With this enabled, the pronounced words
+    // will be compared to the reference text, and will be marked with omission/insertion based on the comparison.
+    // The default setting is False. Under normal circumstances, you shouldn't have to use this property directly.
+    PronunciationAssessmentEnableMiscue = 12005,
+
+    // PronunciationAssessmentPhonemeAlphabet is the pronunciation evaluation phoneme alphabet. The valid values are
+    // "SAPI" (default) and "IPA".
+    PronunciationAssessmentPhonemeAlphabet = 12006,
+
+    // PronunciationAssessmentNBestPhonemeCount is the pronunciation evaluation nbest phoneme count.
+    PronunciationAssessmentNBestPhonemeCount = 12007,
+
+    // PronunciationAssessmentJSON is the json string of pronunciation assessment parameters.
+    // Under normal circumstances, you shouldn't have to use this property directly.
+    PronunciationAssessmentJson = 12009,
+
+    // PronunciationAssessmentParams is pronunciation assessment parameters. This property is intended to be read-only.
+    // The SDK is using it internally.
+    PronunciationAssessmentParams = 12010,
+
+    // SpeakerRecognitionAPIVersion is the version of the Speaker Recognition API to use.
+    SpeakerRecognitionApiVersion = 13001,
+}
+
+impl PropertyId {
+    // ALL lists every PropertyId in ascending id order.
+    pub const ALL: [PropertyId; 92] = [
+        PropertyId::SpeechServiceConnectionKey,
+        PropertyId::SpeechServiceConnectionEndpoint,
+        PropertyId::SpeechServiceConnectionRegion,
+        PropertyId::SpeechServiceAuthorizationToken,
+        PropertyId::SpeechServiceAuthorizationType,
+        PropertyId::SpeechServiceConnectionEndpointId,
+        PropertyId::SpeechServiceConnectionHost,
+        PropertyId::SpeechServiceConnectionProxyHostName,
+        PropertyId::SpeechServiceConnectionProxyPort,
+        PropertyId::SpeechServiceConnectionProxyUserName,
+        PropertyId::SpeechServiceConnectionProxyPassword,
+        PropertyId::SpeechServiceConnectionUrl,
+        PropertyId::SpeechServiceConnectionTranslationToLanguages,
+        PropertyId::SpeechServiceConnectionTranslationVoice,
+        PropertyId::SpeechServiceConnectionTranslationFeatures,
+        PropertyId::SpeechServiceConnectionIntentRegion,
+        PropertyId::SpeechServiceConnectionRecoMode,
+        PropertyId::SpeechServiceConnectionRecoLanguage,
+        PropertyId::SpeechSessionId,
+        PropertyId::SpeechServiceConnectionUserDefinedQueryParameters,
+        PropertyId::SpeechServiceConnectionRecoModelBackend,
+        PropertyId::SpeechServiceConnectionRecoModelName,
+        PropertyId::SpeechServiceConnectionRecoModelKey,
+        PropertyId::SpeechServiceConnectionRecoModelIniFile,
+        PropertyId::SpeechServiceConnectionSynthLanguage,
+        PropertyId::SpeechServiceConnectionSynthVoice,
+        PropertyId::SpeechServiceConnectionSynthOutputFormat,
+        PropertyId::SpeechServiceConnectionSynthEnableCompressedAudioTransmission,
+        PropertyId::SpeechServiceConnectionSynthBackend,
+        PropertyId::SpeechServiceConnectionSynthOfflineDataPath,
+        PropertyId::SpeechServiceConnectionSynthOfflineVoice,
+        PropertyId::SpeechServiceConnectionSynthModelKey,
+        PropertyId::SpeechServiceConnectionVoicesListEndpoint,
+        PropertyId::SpeechServiceConnectionInitialSilenceTimeoutMs,
+        PropertyId::SpeechServiceConnectionEndSilenceTimeoutMs,
+        PropertyId::SpeechServiceConnectionEnableAudioLogging,
+        PropertyId::SpeechServiceConnectionSingleLanguageIdPriority,
+        PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority,
+        PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+        PropertyId::SpeechServiceConnectionAutoDetectSourceLanguageResult,
+        PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse,
+        PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse,
+        PropertyId::SpeechServiceResponseProfanityOption,
+        PropertyId::SpeechServiceResponsePostProcessingOption,
+        PropertyId::SpeechServiceResponseRequestWordLevelTimestamps,
+        PropertyId::SpeechServiceResponseStablePartialResultThreshold,
+        PropertyId::SpeechServiceResponseOutputFormatOption,
+        PropertyId::SpeechServiceResponseRequestSnr,
+        PropertyId::SpeechServiceResponseTranslationRequestStablePartialResult,
+        PropertyId::SpeechServiceResponseRequestWordBoundary,
+        PropertyId::SpeechServiceResponseRequestPunctuationBoundary,
+        PropertyId::SpeechServiceResponseRequestSentenceBoundary,
+        PropertyId::SpeechServiceResponseJsonResult,
+        PropertyId::SpeechServiceResponseJsonErrorDetails,
+        PropertyId::SpeechServiceResponseRecognitionLatencyMs,
+        PropertyId::SpeechServiceResponseSynthesisFirstByteLatencyMs,
+        PropertyId::SpeechServiceResponseSynthesisFinishLatencyMs,
+        PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs,
+        PropertyId::SpeechServiceResponseSynthesisBackend,
+        PropertyId::CancellationDetailsReason,
+        PropertyId::CancellationDetailsReasonText,
+        PropertyId::CancellationDetailsReasonDetailedText,
+        PropertyId::LanguageUnderstandingServiceResponseJsonResult,
+        PropertyId::AudioConfigDeviceNameForCapture,
+        PropertyId::AudioConfigNumberOfChannelsForCapture,
+        PropertyId::AudioConfigSampleRateForCapture,
+        PropertyId::AudioConfigBitsPerSampleForCapture,
+        PropertyId::AudioConfigAudioSource,
+        PropertyId::AudioConfigDeviceNameForRender,
+        PropertyId::AudioConfigPlaybackBufferLengthInMs,
+        PropertyId::AudioConfigAudioProcessingOptions,
+        PropertyId::SpeechLogFilename,
+        PropertyId::SpeechSegmentationSilenceTimeoutMs,
+        PropertyId::ConversationApplicationId,
+        PropertyId::ConversationDialogType,
+        PropertyId::ConversationInitialSilenceTimeout,
+        PropertyId::ConversationFromId,
+        PropertyId::ConversationConversationId,
+        PropertyId::ConversationCustomVoiceDeploymentIds,
+        PropertyId::ConversationSpeechActivityTemplate,
+        PropertyId::ConversationParticipantId,
+        PropertyId::DataBufferTimeStamp,
+        PropertyId::DataBufferUserId,
+        PropertyId::PronunciationAssessmentReferenceText,
+        PropertyId::PronunciationAssessmentGradingSystem,
+        PropertyId::PronunciationAssessmentGranularity,
+        PropertyId::PronunciationAssessmentEnableMiscue,
+        PropertyId::PronunciationAssessmentPhonemeAlphabet,
+        PropertyId::PronunciationAssessmentNBestPhonemeCount,
+        PropertyId::PronunciationAssessmentJson,
+        PropertyId::PronunciationAssessmentParams,
+        PropertyId::SpeakerRecognitionApiVersion,
+    ];
+
+    // id is the numeric value of the property id as defined by the native speech sdk.
+    pub fn id(&self) -> u32 {
+        *self as u32
+    }
+
+    // name is the string name under which the property is stored in a PropertyCollection.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PropertyId::SpeechServiceConnectionKey => "SpeechServiceConnection_Key",
+            PropertyId::SpeechServiceConnectionEndpoint => "SpeechServiceConnection_Endpoint",
+            PropertyId::SpeechServiceConnectionRegion => "SpeechServiceConnection_Region",
+            PropertyId::SpeechServiceAuthorizationToken => "SpeechServiceAuthorization_Token",
+            PropertyId::SpeechServiceAuthorizationType => "SpeechServiceAuthorization_Type",
+            PropertyId::SpeechServiceConnectionEndpointId => "SpeechServiceConnection_EndpointId",
+            PropertyId::SpeechServiceConnectionHost => "SpeechServiceConnection_Host",
+            PropertyId::SpeechServiceConnectionProxyHostName => {
+                "SpeechServiceConnection_ProxyHostName"
+            }
+            PropertyId::SpeechServiceConnectionProxyPort => "SpeechServiceConnection_ProxyPort",
+            PropertyId::SpeechServiceConnectionProxyUserName => {
+                "SpeechServiceConnection_ProxyUserName"
+            }
+            PropertyId::SpeechServiceConnectionProxyPassword => {
+                "SpeechServiceConnection_ProxyPassword"
+            }
+            PropertyId::SpeechServiceConnectionUrl => "SpeechServiceConnection_Url",
+            PropertyId::SpeechServiceConnectionTranslationToLanguages => {
+                "SpeechServiceConnection_TranslationToLanguages"
+            }
+            PropertyId::SpeechServiceConnectionTranslationVoice => {
+                "SpeechServiceConnection_TranslationVoice"
+            }
+            PropertyId::SpeechServiceConnectionTranslationFeatures => {
+                "SpeechServiceConnection_TranslationFeatures"
+            }
+            PropertyId::SpeechServiceConnectionIntentRegion => {
+                "SpeechServiceConnection_IntentRegion"
+            }
+            PropertyId::SpeechServiceConnectionRecoMode => "SpeechServiceConnection_RecoMode",
+            PropertyId::SpeechServiceConnectionRecoLanguage => {
+                "SpeechServiceConnection_RecoLanguage"
+            }
+            PropertyId::SpeechSessionId => "Speech_SessionId",
+            PropertyId::SpeechServiceConnectionUserDefinedQueryParameters => {
+                "SpeechServiceConnection_UserDefinedQueryParameters"
+            }
+            PropertyId::SpeechServiceConnectionRecoModelBackend => {
+                "SpeechServiceConnection_RecoModelBackend"
+            }
+            PropertyId::SpeechServiceConnectionRecoModelName => {
+                "SpeechServiceConnection_RecoModelName"
+            }
+            PropertyId::SpeechServiceConnectionRecoModelKey => {
+                "SpeechServiceConnection_RecoModelKey"
+            }
+            PropertyId::SpeechServiceConnectionRecoModelIniFile => {
+                "SpeechServiceConnection_RecoModelIniFile"
+            }
+            PropertyId::SpeechServiceConnectionSynthLanguage => {
+                "SpeechServiceConnection_SynthLanguage"
+            }
+            PropertyId::SpeechServiceConnectionSynthVoice => "SpeechServiceConnection_SynthVoice",
+            PropertyId::SpeechServiceConnectionSynthOutputFormat => {
+                "SpeechServiceConnection_SynthOutputFormat"
+            }
+            PropertyId::SpeechServiceConnectionSynthEnableCompressedAudioTransmission => {
+                "SpeechServiceConnection_SynthEnableCompressedAudioTransmission"
+            }
+            PropertyId::SpeechServiceConnectionSynthBackend => {
+                "SpeechServiceConnection_SynthBackend"
+            }
+            PropertyId::SpeechServiceConnectionSynthOfflineDataPath => {
+                "SpeechServiceConnection_SynthOfflineDataPath"
+            }
+            PropertyId::SpeechServiceConnectionSynthOfflineVoice => {
+                "SpeechServiceConnection_SynthOfflineVoice"
+            }
+            PropertyId::SpeechServiceConnectionSynthModelKey => {
+                "SpeechServiceConnection_SynthModelKey"
+            }
+            PropertyId::SpeechServiceConnectionVoicesListEndpoint => {
+                "SpeechServiceConnection_VoicesListEndpoint"
+            }
+            PropertyId::SpeechServiceConnectionInitialSilenceTimeoutMs => {
+                "SpeechServiceConnection_InitialSilenceTimeoutMs"
+            }
+            PropertyId::SpeechServiceConnectionEndSilenceTimeoutMs => {
+                "SpeechServiceConnection_EndSilenceTimeoutMs"
+            }
+            PropertyId::SpeechServiceConnectionEnableAudioLogging => {
+                "SpeechServiceConnection_EnableAudioLogging"
+            }
+            PropertyId::SpeechServiceConnectionSingleLanguageIdPriority => {
+                "SpeechServiceConnection_SingleLanguageIdPriority"
+            }
+            PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority => {
+                "SpeechServiceConnection_ContinuousLanguageIdPriority"
+            }
+            PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages => {
+                "SpeechServiceConnection_AutoDetectSourceLanguages"
+            }
+            PropertyId::SpeechServiceConnectionAutoDetectSourceLanguageResult => {
+                "SpeechServiceConnection_AutoDetectSourceLanguageResult"
+            }
+            PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse => {
+                "SpeechServiceResponse_RequestDetailedResultTrueFalse"
+            }
+            PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse => {
+                "SpeechServiceResponse_RequestProfanityFilterTrueFalse"
+            }
+            PropertyId::SpeechServiceResponseProfanityOption => {
+                "SpeechServiceResponse_ProfanityOption"
+            }
+            PropertyId::SpeechServiceResponsePostProcessingOption => {
+                "SpeechServiceResponse_PostProcessingOption"
+            }
+            PropertyId::SpeechServiceResponseRequestWordLevelTimestamps => {
+                "SpeechServiceResponse_RequestWordLevelTimestamps"
+            }
+            PropertyId::SpeechServiceResponseStablePartialResultThreshold => {
+                "SpeechServiceResponse_StablePartialResultThreshold"
+            }
+            PropertyId::SpeechServiceResponseOutputFormatOption => {
+                "SpeechServiceResponse_OutputFormatOption"
+            }
+            PropertyId::SpeechServiceResponseRequestSnr => "SpeechServiceResponse_RequestSnr",
+            PropertyId::SpeechServiceResponseTranslationRequestStablePartialResult => {
+                "SpeechServiceResponse_TranslationRequestStablePartialResult"
+            }
+            PropertyId::SpeechServiceResponseRequestWordBoundary => {
+                "SpeechServiceResponse_RequestWordBoundary"
+            }
+            PropertyId::SpeechServiceResponseRequestPunctuationBoundary => {
+                "SpeechServiceResponse_RequestPunctuationBoundary"
+            }
+            PropertyId::SpeechServiceResponseRequestSentenceBoundary => {
+                "SpeechServiceResponse_RequestSentenceBoundary"
+            }
+            PropertyId::SpeechServiceResponseJsonResult => "SpeechServiceResponse_JsonResult",
+            PropertyId::SpeechServiceResponseJsonErrorDetails => {
+                "SpeechServiceResponse_JsonErrorDetails"
+            }
+            PropertyId::SpeechServiceResponseRecognitionLatencyMs => {
+                "SpeechServiceResponse_RecognitionLatencyMs"
+            }
+            PropertyId::SpeechServiceResponseSynthesisFirstByteLatencyMs => {
+                "SpeechServiceResponse_SynthesisFirstByteLatencyMs"
+            }
+            PropertyId::SpeechServiceResponseSynthesisFinishLatencyMs => {
+                "SpeechServiceResponse_SynthesisFinishLatencyMs"
+            }
+            PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs => {
+                "SpeechServiceResponse_SynthesisUnderrunTimeMs"
+            }
+            PropertyId::SpeechServiceResponseSynthesisBackend => {
+                "SpeechServiceResponse_SynthesisBackend"
+            }
+            PropertyId::CancellationDetailsReason => "CancellationDetails_Reason",
+            PropertyId::CancellationDetailsReasonText => "CancellationDetails_ReasonText",
+            PropertyId::CancellationDetailsReasonDetailedText => {
+                "CancellationDetails_ReasonDetailedText"
+            }
+            PropertyId::LanguageUnderstandingServiceResponseJsonResult => {
+                "LanguageUnderstandingServiceResponse_JsonResult"
+            }
+            PropertyId::AudioConfigDeviceNameForCapture => "AudioConfig_DeviceNameForCapture",
+            PropertyId::AudioConfigNumberOfChannelsForCapture => {
+                "AudioConfig_NumberOfChannelsForCapture"
+            }
+            PropertyId::AudioConfigSampleRateForCapture => "AudioConfig_SampleRateForCapture",
+            PropertyId::AudioConfigBitsPerSampleForCapture => "AudioConfig_BitsPerSampleForCapture",
+            PropertyId::AudioConfigAudioSource => "AudioConfig_AudioSource",
+            PropertyId::AudioConfigDeviceNameForRender => "AudioConfig_DeviceNameForRender",
+            PropertyId::AudioConfigPlaybackBufferLengthInMs => {
+                "AudioConfig_PlaybackBufferLengthInMs"
+            }
+            PropertyId::AudioConfigAudioProcessingOptions => "AudioConfig_AudioProcessingOptions",
+            PropertyId::SpeechLogFilename => "Speech_LogFilename",
+            PropertyId::SpeechSegmentationSilenceTimeoutMs => "Speech_SegmentationSilenceTimeoutMs",
+            PropertyId::ConversationApplicationId => "Conversation_ApplicationId",
+            PropertyId::ConversationDialogType => "Conversation_DialogType",
+            PropertyId::ConversationInitialSilenceTimeout => "Conversation_Initial_Silence_Timeout",
+            PropertyId::ConversationFromId => "Conversation_From_Id",
+            PropertyId::ConversationConversationId => "Conversation_Conversation_Id",
+            PropertyId::ConversationCustomVoiceDeploymentIds => {
+                "Conversation_Custom_Voice_Deployment_Ids"
+            }
+            PropertyId::ConversationSpeechActivityTemplate => {
+                "Conversation_Speech_Activity_Template"
+            }
+            PropertyId::ConversationParticipantId => "Conversation_ParticipantId",
+            PropertyId::DataBufferTimeStamp => "DataBuffer_TimeStamp",
+            PropertyId::DataBufferUserId => "DataBuffer_UserId",
+            PropertyId::PronunciationAssessmentReferenceText => {
+                "PronunciationAssessment_ReferenceText"
+            }
+            PropertyId::PronunciationAssessmentGradingSystem => {
+                "PronunciationAssessment_GradingSystem"
+            }
+            PropertyId::PronunciationAssessmentGranularity => "PronunciationAssessment_Granularity",
+            PropertyId::PronunciationAssessmentEnableMiscue => {
+                "PronunciationAssessment_EnableMiscue"
+            }
+            PropertyId::PronunciationAssessmentPhonemeAlphabet => {
+                "PronunciationAssessment_PhonemeAlphabet"
+            }
+            PropertyId::PronunciationAssessmentNBestPhonemeCount => {
+                "PronunciationAssessment_NBestPhonemeCount"
+            }
+            PropertyId::PronunciationAssessmentJson => "PronunciationAssessment_Json",
+            PropertyId::PronunciationAssessmentParams => "PronunciationAssessment_Params",
+            PropertyId::SpeakerRecognitionApiVersion => "SpeakerRecognition_Api_Version",
+        }
+    }
+
+    // from_id returns the PropertyId with the given numeric value, if any.
+    pub fn from_id(id: u32) -> Option<PropertyId> {
+        PropertyId::ALL
+            .iter()
+            .copied()
+            .find(|property_id| property_id.id() == id)
+    }
+
+    // from_name returns the PropertyId stored under the given string name, if any.
+    pub fn from_name(name: &str) -> Option<PropertyId> {
+        PropertyId::ALL
+            .iter()
+            .copied()
+            .find(|property_id| property_id.name() == name)
+    }
+}
+
+impl fmt::Display for PropertyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// PropertyCollection is a class to retrieve or set a property value from a property collection.
+// Properties are stored as strings and can be addressed either by PropertyId or by free-form name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropertyCollection {
+    properties: HashMap<String, String>,
 }
 
 impl PropertyCollection {
-    fn speech_service_connection_url() -> String {
-        todo!()
+    pub fn new() -> Self {
+        PropertyCollection::default()
     }
 
-    // SpeechServiceResponseRequestDetailedResultTrueFalse the requested Cognitive Services Speech Service response output
-	// format (simple or detailed). Under normal circumstances, you shouldn't have to use this property directly.
-    fn speech_service_response_request_detailed_result_true_false() -> bool {
-        todo!()
+    // get_property returns value of a property.
+    // If the property value is not defined, the specified default value is returned.
+    pub fn get_property(&self, id: PropertyId, default_value: &str) -> String {
+        self.get_property_by_string(id.name(), default_value)
     }
 
-    // SpeechServiceResponseRequestProfanityFilterTrueFalse is the requested Cognitive Services Speech Service response
-	// output profanity level. Currently unused.
-    fn speech_service_response_request_profanity_filter_true_false() -> bool {
-        todo!()
+    // get_property_by_string returns value of a property by its name.
+    // If the property value is not defined, the specified default value is returned.
+    pub fn get_property_by_string(&self, name: &str, default_value: &str) -> String {
+        self.property_by_string(name)
+            .unwrap_or(default_value)
+            .to_string()
+    }
+
+    // property returns the value of a property, or None if it is not defined.
+    pub fn property(&self, id: PropertyId) -> Option<&str> {
+        self.property_by_string(id.name())
+    }
+
+    // property_by_string returns the value of a property by its name, or None if it is not defined.
+    pub fn property_by_string(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    // set_property sets the value of a property.
+    pub fn set_property(&mut self, id: PropertyId, value: impl Into<String>) {
+        self.set_property_by_string(id.name(), value);
+    }
+
+    // set_property_by_string sets the value of a property by its name.
+    pub fn set_property_by_string(&mut self, name: &str, value: impl Into<String>) {
+        self.properties.insert(name.to_string(), value.into());
+    }
+
+    // remove_property removes a property, returning its previous value.
+    pub fn remove_property(&mut self, id: PropertyId) -> Option<String> {
+        self.properties.remove(id.name())
+    }
+
+    // get_bool returns the value of a boolean property ("true" or "false", case insensitive).
+    // If the property is not defined or cannot be parsed, the specified default value is returned.
+    pub fn get_bool(&self, id: PropertyId, default_value: bool) -> bool {
+        match self.property(id) {
+            Some(value) if value.eq_ignore_ascii_case("true") => true,
+            Some(value) if value.eq_ignore_ascii_case("false") => false,
+            _ => default_value,
+        }
+    }
+
+    // get_u32 returns the value of an unsigned integer property.
+    // If the property is not defined or cannot be parsed, the specified default value is returned.
+    pub fn get_u32(&self, id: PropertyId, default_value: u32) -> u32 {
+        self.property(id)
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(default_value)
+    }
+
+    // get_u64 returns the value of an unsigned 64-bit integer property.
+    // If the property is not defined or cannot be parsed, the specified default value is returned.
+    pub fn get_u64(&self, id: PropertyId, default_value: u64) -> u64 {
+        self.property(id)
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(default_value)
+    }
+
+    // copy_into copies every property of this collection into other, overwriting properties with the same name.
+    pub fn copy_into(&self, other: &mut PropertyCollection) {
+        for (name, value) in &self.properties {
+            other.properties.insert(name.clone(), value.clone());
+        }
+    }
+
+    // iter iterates over the names and values of all defined properties.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    // speech_service_connection_url builds the speech recognition WebSocket URL from the properties in this
+    // collection. An explicit endpoint takes precedence over a host, which takes precedence over a region.
+    // Query parameters that are already part of an explicit endpoint are never overridden; user defined
+    // query parameters take precedence over the ones derived from the other properties.
+    pub fn speech_service_connection_url(&self) -> Result<Url, Error> {
+        let mode = match self.property(PropertyId::SpeechServiceConnectionRecoMode) {
+            Some(mode) => mode.parse()?,
+            None => RecognitionMode::Interactive,
+        };
+        // Continuous language detection is only served by the universal endpoint.
+        let continuous_language_id = self
+            .property(PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages)
+            .is_some()
+            && self
+                .property(PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority)
+                .is_some();
+        let path = if continuous_language_id {
+            "/speech/universal/v2".to_string()
+        } else {
+            format!(
+                "/speech/recognition/{}/cognitiveservices/v1",
+                mode.as_str().to_ascii_lowercase()
+            )
+        };
+
+        let mut query = Vec::new();
+        // The language defaults to en-US, unless it is left to the detection among source languages.
+        let detects_language = self
+            .property(PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages)
+            .is_some();
+        let language = self
+            .property(PropertyId::SpeechServiceConnectionRecoLanguage)
+            .or((!detects_language).then_some("en-US"));
+        if let Some(language) = language {
+            query.push(("language".to_string(), language.to_string()));
+        }
+        // Word level timestamps are only part of detailed results.
+        let word_level_timestamps = self.get_bool(
+            PropertyId::SpeechServiceResponseRequestWordLevelTimestamps,
+            false,
+        );
+        if let Some(format) = self.property(PropertyId::SpeechServiceResponseOutputFormatOption) {
+            let format = format.parse::<OutputFormat>()?;
+            query.push(("format".to_string(), format.as_str().to_string()));
+        } else if word_level_timestamps
+            || self.get_bool(
+                PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse,
+                false,
+            )
+        {
+            query.push((
+                "format".to_string(),
+                OutputFormat::Detailed.as_str().to_string(),
+            ));
+        }
+        if word_level_timestamps {
+            query.push(("wordLevelTimestamps".to_string(), "true".to_string()));
+        }
+        self.push_profanity_and_model(&mut query)?;
+        self.service_url("stt", &path, query)
+    }
+
+    // speech_translation_connection_url builds the speech translation WebSocket URL from the properties in this
+    // collection, with the same precedence of endpoint, host and region and of query parameters as
+    // speech_service_connection_url. The target languages are passed as one "to" parameter each.
+    pub fn speech_translation_connection_url(&self) -> Result<Url, Error> {
+        let mut query = Vec::new();
+        if let Some(language) = self.property(PropertyId::SpeechServiceConnectionRecoLanguage) {
+            query.push(("from".to_string(), language.to_string()));
+        }
+        let targets = self.get_property(
+            PropertyId::SpeechServiceConnectionTranslationToLanguages,
+            "",
+        );
+        for target in targets.split(',').map(str::trim) {
+            if !target.is_empty() {
+                query.push(("to".to_string(), target.to_string()));
+            }
+        }
+        if self.get_bool(
+            PropertyId::SpeechServiceResponseTranslationRequestStablePartialResult,
+            false,
+        ) {
+            query.push(("stableTranslation".to_string(), "true".to_string()));
+        }
+        self.push_profanity_and_model(&mut query)?;
+        self.service_url("s2s", "/speech/translation/cognitiveservices/v1", query)
+    }
+
+    // speech_synthesis_connection_url builds the url of the speech synthesis WebSocket endpoint from the
+    // endpoint, host or region found in the collection, in that order of precedence.
+    pub fn speech_synthesis_connection_url(&self) -> Result<Url, Error> {
+        let mut query = Vec::new();
+        if let Some(endpoint_id) = self.property(PropertyId::SpeechServiceConnectionEndpointId) {
+            query.push(("deploymentId".to_string(), endpoint_id.to_string()));
+        }
+        self.service_url("tts", "/cognitiveservices/websocket/v1", query)
+    }
+
+    // voices_list_url builds the url of the voices list REST api. The voices list endpoint takes precedence; otherwise
+    // the url is built from the endpoint, host or region found in the collection, in that order of precedence.
+    pub fn voices_list_url(&self) -> Result<Url, Error> {
+        let mut url = match self.property(PropertyId::SpeechServiceConnectionVoicesListEndpoint) {
+            Some(endpoint) => Url::parse(endpoint)
+                .map_err(|e| Error::InvalidUrl(format!("{}: {}", endpoint, e)))?,
+            None => {
+                // The voices list is served by the host of the synthesis endpoint.
+                let url = self.service_url("tts", "/cognitiveservices/websocket/v1", Vec::new())?;
+                url.join("/cognitiveservices/voices/list")
+                    .map_err(|e| Error::InvalidUrl(format!("{}: {}", url, e)))?
+            }
+        };
+
+        let scheme = match url.scheme() {
+            "ws" => Some("http"),
+            "wss" => Some("https"),
+            _ => None,
+        };
+        if let Some(scheme) = scheme {
+            url.set_scheme(scheme)
+                .map_err(|_| Error::InvalidUrl(url.to_string()))?;
+        }
+        Ok(url)
+    }
+
+    // push_profanity_and_model adds the profanity option and the endpoint id of a custom model, shared by
+    // recognition and translation, to query.
+    fn push_profanity_and_model(&self, query: &mut Vec<(String, String)>) -> Result<(), Error> {
+        let profanity = match self.property(PropertyId::SpeechServiceResponseProfanityOption) {
+            Some(profanity) => Some(profanity.parse::<ProfanityOption>()?),
+            None => self
+                .property(PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse)
+                .map(|_| {
+                    if self.get_bool(
+                        PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse,
+                        true,
+                    ) {
+                        ProfanityOption::Masked
+                    } else {
+                        ProfanityOption::Raw
+                    }
+                }),
+        };
+        if let Some(profanity) = profanity {
+            query.push(("profanity".to_string(), profanity.as_str().to_string()));
+        }
+        if let Some(endpoint_id) = self.property(PropertyId::SpeechServiceConnectionEndpointId) {
+            query.push(("cid".to_string(), endpoint_id.to_string()));
+        }
+        Ok(())
+    }
+
+    // service_url builds the WebSocket url of a speech service from an explicit endpoint, a host, which is
+    // joined with path, or a region, whose host is named after service, in that order of precedence. The user
+    // defined query parameters take precedence over the ones in query, and query parameters that are already
+    // part of an explicit endpoint are never overridden.
+    fn service_url(
+        &self,
+        service: &str,
+        path: &str,
+        mut query: Vec<(String, String)>,
+    ) -> Result<Url, Error> {
+        let mut url = if let Some(endpoint) =
+            self.property(PropertyId::SpeechServiceConnectionEndpoint)
+        {
+            Url::parse(endpoint).map_err(|e| Error::InvalidUrl(format!("{}: {}", endpoint, e)))?
+        } else if let Some(host) = self.property(PropertyId::SpeechServiceConnectionHost) {
+            let host =
+                Url::parse(host).map_err(|e| Error::InvalidUrl(format!("{}: {}", host, e)))?;
+            host.join(path)
+                .map_err(|e| Error::InvalidUrl(format!("{}: {}", host, e)))?
+        } else if let Some(region) = self.property(PropertyId::SpeechServiceConnectionRegion) {
+            let url = format!(
+                "wss://{}.{}.{}{}",
+                region,
+                service,
+                speech_host_suffix(region),
+                path
+            );
+            Url::parse(&url).map_err(|_| Error::InvalidRegion(region.to_string()))?
+        } else {
+            return Err(Error::InvalidArgument(
+                "one of endpoint, host or region must be set to connect to the speech service"
+                    .to_string(),
+            ));
+        };
+
+        if let Some(parameters) =
+            self.property(PropertyId::SpeechServiceConnectionUserDefinedQueryParameters)
+        {
+            for (name, value) in url::form_urlencoded::parse(parameters.as_bytes()) {
+                query.retain(|(existing, _)| *existing != name);
+                query.push((name.into_owned(), value.into_owned()));
+            }
+        }
+
+        let fixed: Vec<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        query.retain(|(name, _)| !fixed.contains(name));
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url)
     }
 }
 
-enum RecognitionMode {
+// speech_host_suffix is the domain of the speech service in the sovereign cloud the region belongs to.
+pub(crate) fn speech_host_suffix(region: &str) -> &'static str {
+    if region.starts_with("china") {
+        "speech.azure.cn"
+    } else if region.starts_with("usgov") {
+        "speech.azure.us"
+    } else {
+        "speech.microsoft.com"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecognitionMode {
     Interactive,
     Conversation,
     Dictation
 }
 
-enum PostProcessingOption {
+impl RecognitionMode {
+    // as_str is the value of the recognition mode as stored in SpeechServiceConnectionRecoMode.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecognitionMode::Interactive => "INTERACTIVE",
+            RecognitionMode::Conversation => "CONVERSATION",
+            RecognitionMode::Dictation => "DICTATION",
+        }
+    }
+}
+
+impl FromStr for RecognitionMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "INTERACTIVE" => Ok(RecognitionMode::Interactive),
+            "CONVERSATION" => Ok(RecognitionMode::Conversation),
+            "DICTATION" => Ok(RecognitionMode::Dictation),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown recognition mode {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostProcessingOption {
     TrueText
 }
 
-enum AudioSource {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioSource {
     Microphones,
     File,
     Stream
 }
 
-enum ResultReason {
+impl AudioSource {
+    // as_str is the value of the audio source as stored in AudioConfigAudioSource.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioSource::Microphones => "Microphones",
+            AudioSource::File => "File",
+            AudioSource::Stream => "Stream",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultReason {
     // NoMatch indicates speech could not be recognized. More details can be found in the NoMatchDetails object.
     NoMatch,
 
@@ -424,21 +1346,26 @@ enum ResultReason {
     VoicesListRetrieved
 }
 
+#[allow(dead_code)]
 type SPXHandle = usize;
 
-enum ServicePropertyChannel {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServicePropertyChannel {
     UriQueryParameter
 }
 
-enum SpeechSynthesisBoundaryType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+pub enum SpeechSynthesisBoundaryType {
     WordBoundary,
     PunctuationBoundary,
     SentenceBoundary
 }
 
-enum SpeechSynthesisOutputFormat {
-    	// Raw8Khz8BitMonoMULaw stands for raw-8khz-8bit-mono-mulaw
-	Raw8Khz8BitMonoMULaw,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpeechSynthesisOutputFormat {
+    // Raw8Khz8BitMonoMULaw stands for raw-8khz-8bit-mono-mulaw
+    Raw8Khz8BitMonoMULaw = 1,
 
 	// Riff16Khz16KbpsMonoSiren stands for riff-16khz-16kbps-mono-siren
 	// Unsupported by the service. Do not use this value.
@@ -472,8 +1399,8 @@ enum SpeechSynthesisOutputFormat {
 	// Riff16Khz16BitMonoPcm stands for riff-16khz-16bit-mono-pcm
 	Riff16Khz16BitMonoPcm,
 
-	// Riff8Khz16BitMonoPcm stands for riff-8khz-16bit-mono-pcm
-	Riff8Khz16BitMonoPc,
+    // Riff8Khz16BitMonoPcm stands for riff-8khz-16bit-mono-pcm
+    Riff8Khz16BitMonoPcm,
 
 	// Riff24Khz16BitMonoPcm stands for riff-24khz-16bit-mono-pcm
 	Riff24Khz16BitMonoPcm,
@@ -541,32 +1468,452 @@ enum SpeechSynthesisOutputFormat {
 	// Audio24Khz16Bit24KbpsMonoOpus stands for audio-24khz-16bit-24kbps-mono-opus
 	// Audio compressed by OPUS codec without container, with bitrate of 24kbps.
 	Audio24Khz16Bit24KbpsMonoOpus,
+
+    // Raw22050Hz16BitMonoPcm stands for raw-22050hz-16bit-mono-pcm
+    Raw22050Hz16BitMonoPcm,
+
+    // Riff22050Hz16BitMonoPcm stands for riff-22050hz-16bit-mono-pcm
+    Riff22050Hz16BitMonoPcm,
+
+    // Raw44100Hz16BitMonoPcm stands for raw-44100hz-16bit-mono-pcm
+    Raw44100Hz16BitMonoPcm,
+
+    // Riff44100Hz16BitMonoPcm stands for riff-44100hz-16bit-mono-pcm
+    Riff44100Hz16BitMonoPcm,
+}
+
+impl SpeechSynthesisOutputFormat {
+    // ALL lists every output format.
+    pub const ALL: [SpeechSynthesisOutputFormat; 37] = [
+        SpeechSynthesisOutputFormat::Raw8Khz8BitMonoMULaw,
+        SpeechSynthesisOutputFormat::Riff16Khz16KbpsMonoSiren,
+        SpeechSynthesisOutputFormat::Audio16Khz16KbpsMonoSiren,
+        SpeechSynthesisOutputFormat::Audio16Khz32KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio16Khz128KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio16Khz64KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio24Khz48KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio24Khz96KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio24Khz160KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Raw16Khz16BitMonoTrueSilk,
+        SpeechSynthesisOutputFormat::Riff16Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff8Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff24Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff8Khz8BitMonoMULaw,
+        SpeechSynthesisOutputFormat::Raw16Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Raw24Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Raw8Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Ogg16Khz16BitMonoOpus,
+        SpeechSynthesisOutputFormat::Ogg24Khz16BitMonoOpus,
+        SpeechSynthesisOutputFormat::Raw48Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff48Khz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Audio48Khz96KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Audio48Khz192KBitRateMonoMp3,
+        SpeechSynthesisOutputFormat::Ogg48Khz16BitMonoOpus,
+        SpeechSynthesisOutputFormat::Webm16Khz16BitMonoOpus,
+        SpeechSynthesisOutputFormat::Webm24Khz16BitMonoOpus,
+        SpeechSynthesisOutputFormat::Raw24Khz16BitMonoTrueSilk,
+        SpeechSynthesisOutputFormat::Raw8Khz8BitMonoALaw,
+        SpeechSynthesisOutputFormat::Riff8Khz8BitMonoALaw,
+        SpeechSynthesisOutputFormat::Webm24Khz16Bit24KbpsMonoOpus,
+        SpeechSynthesisOutputFormat::Audio16Khz16Bit32KbpsMonoOpus,
+        SpeechSynthesisOutputFormat::Audio24Khz16Bit48KbpsMonoOpus,
+        SpeechSynthesisOutputFormat::Audio24Khz16Bit24KbpsMonoOpus,
+        SpeechSynthesisOutputFormat::Raw22050Hz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff22050Hz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Raw44100Hz16BitMonoPcm,
+        SpeechSynthesisOutputFormat::Riff44100Hz16BitMonoPcm,
+    ];
+
+    // as_str is the name of the output format as sent to the service, e.g. riff-24khz-16bit-mono-pcm.
+    pub fn as_str(&self) -> &'static str {
+        self.info().0
+    }
+
+    // samples_per_second is the sample rate of the audio.
+    pub fn samples_per_second(&self) -> u32 {
+        self.info().1
+    }
+
+    // bits_per_sample is the bit depth of the audio samples; for compressed formats it is the bit depth of the decoded audio.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.info().2
+    }
+
+    // channels is the number of channels of the audio. All output formats are mono.
+    pub fn channels(&self) -> u16 {
+        1
+    }
+
+    // container is the container the audio is delivered in.
+    pub fn container(&self) -> SpeechSynthesisOutputContainer {
+        self.info().3
+    }
+
+    // codec is the encoding of the audio.
+    pub fn codec(&self) -> SpeechSynthesisOutputCodec {
+        self.info().4
+    }
+
+    fn info(
+        &self,
+    ) -> (
+        &'static str,
+        u32,
+        u16,
+        SpeechSynthesisOutputContainer,
+        SpeechSynthesisOutputCodec,
+    ) {
+        match self {
+            SpeechSynthesisOutputFormat::Raw8Khz8BitMonoMULaw => (
+                "raw-8khz-8bit-mono-mulaw",
+                8000,
+                8,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::MuLaw,
+            ),
+            SpeechSynthesisOutputFormat::Riff16Khz16KbpsMonoSiren => (
+                "riff-16khz-16kbps-mono-siren",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Siren,
+            ),
+            SpeechSynthesisOutputFormat::Audio16Khz16KbpsMonoSiren => (
+                "audio-16khz-16kbps-mono-siren",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Siren,
+            ),
+            SpeechSynthesisOutputFormat::Audio16Khz32KBitRateMonoMp3 => (
+                "audio-16khz-32kbitrate-mono-mp3",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio16Khz128KBitRateMonoMp3 => (
+                "audio-16khz-128kbitrate-mono-mp3",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio16Khz64KBitRateMonoMp3 => (
+                "audio-16khz-64kbitrate-mono-mp3",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio24Khz48KBitRateMonoMp3 => (
+                "audio-24khz-48kbitrate-mono-mp3",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio24Khz96KBitRateMonoMp3 => (
+                "audio-24khz-96kbitrate-mono-mp3",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio24Khz160KBitRateMonoMp3 => (
+                "audio-24khz-160kbitrate-mono-mp3",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Raw16Khz16BitMonoTrueSilk => (
+                "raw-16khz-16bit-mono-truesilk",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Silk,
+            ),
+            SpeechSynthesisOutputFormat::Riff16Khz16BitMonoPcm => (
+                "riff-16khz-16bit-mono-pcm",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff8Khz16BitMonoPcm => (
+                "riff-8khz-16bit-mono-pcm",
+                8000,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff24Khz16BitMonoPcm => (
+                "riff-24khz-16bit-mono-pcm",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff8Khz8BitMonoMULaw => (
+                "riff-8khz-8bit-mono-mulaw",
+                8000,
+                8,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::MuLaw,
+            ),
+            SpeechSynthesisOutputFormat::Raw16Khz16BitMonoPcm => (
+                "raw-16khz-16bit-mono-pcm",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Raw24Khz16BitMonoPcm => (
+                "raw-24khz-16bit-mono-pcm",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Raw8Khz16BitMonoPcm => (
+                "raw-8khz-16bit-mono-pcm",
+                8000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Ogg16Khz16BitMonoOpus => (
+                "ogg-16khz-16bit-mono-opus",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Ogg,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Ogg24Khz16BitMonoOpus => (
+                "ogg-24khz-16bit-mono-opus",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Ogg,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Raw48Khz16BitMonoPcm => (
+                "raw-48khz-16bit-mono-pcm",
+                48000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff48Khz16BitMonoPcm => (
+                "riff-48khz-16bit-mono-pcm",
+                48000,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Audio48Khz96KBitRateMonoMp3 => (
+                "audio-48khz-96kbitrate-mono-mp3",
+                48000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Audio48Khz192KBitRateMonoMp3 => (
+                "audio-48khz-192kbitrate-mono-mp3",
+                48000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Mp3,
+            ),
+            SpeechSynthesisOutputFormat::Ogg48Khz16BitMonoOpus => (
+                "ogg-48khz-16bit-mono-opus",
+                48000,
+                16,
+                SpeechSynthesisOutputContainer::Ogg,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Webm16Khz16BitMonoOpus => (
+                "webm-16khz-16bit-mono-opus",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Webm,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Webm24Khz16BitMonoOpus => (
+                "webm-24khz-16bit-mono-opus",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Webm,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Raw24Khz16BitMonoTrueSilk => (
+                "raw-24khz-16bit-mono-truesilk",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Silk,
+            ),
+            SpeechSynthesisOutputFormat::Raw8Khz8BitMonoALaw => (
+                "raw-8khz-8bit-mono-alaw",
+                8000,
+                8,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::ALaw,
+            ),
+            SpeechSynthesisOutputFormat::Riff8Khz8BitMonoALaw => (
+                "riff-8khz-8bit-mono-alaw",
+                8000,
+                8,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::ALaw,
+            ),
+            SpeechSynthesisOutputFormat::Webm24Khz16Bit24KbpsMonoOpus => (
+                "webm-24khz-16bit-24kbps-mono-opus",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Webm,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Audio16Khz16Bit32KbpsMonoOpus => (
+                "audio-16khz-16bit-32kbps-mono-opus",
+                16000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Audio24Khz16Bit48KbpsMonoOpus => (
+                "audio-24khz-16bit-48kbps-mono-opus",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Audio24Khz16Bit24KbpsMonoOpus => (
+                "audio-24khz-16bit-24kbps-mono-opus",
+                24000,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Opus,
+            ),
+            SpeechSynthesisOutputFormat::Raw22050Hz16BitMonoPcm => (
+                "raw-22050hz-16bit-mono-pcm",
+                22050,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff22050Hz16BitMonoPcm => (
+                "riff-22050hz-16bit-mono-pcm",
+                22050,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Raw44100Hz16BitMonoPcm => (
+                "raw-44100hz-16bit-mono-pcm",
+                44100,
+                16,
+                SpeechSynthesisOutputContainer::Raw,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+            SpeechSynthesisOutputFormat::Riff44100Hz16BitMonoPcm => (
+                "riff-44100hz-16bit-mono-pcm",
+                44100,
+                16,
+                SpeechSynthesisOutputContainer::Riff,
+                SpeechSynthesisOutputCodec::Pcm,
+            ),
+        }
+    }
+}
+
+impl FromStr for SpeechSynthesisOutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SpeechSynthesisOutputFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("unknown speech synthesis output format {}", s))
+            })
+    }
+}
+
+impl fmt::Display for SpeechSynthesisOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// SpeechSynthesisOutputContainer is the container the audio of a speech synthesis output format is delivered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpeechSynthesisOutputContainer {
+    // Raw indicates the encoded audio without a container.
+    Raw,
+
+    // Riff indicates a RIFF (wave) container.
+    Riff,
+
+    // Ogg indicates an Ogg container.
+    Ogg,
+
+    // Webm indicates a WebM container.
+    Webm,
 }
 
-enum StreamStatus {
+// SpeechSynthesisOutputCodec is the encoding of the audio of a speech synthesis output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpeechSynthesisOutputCodec {
+    // Pcm indicates uncompressed PCM audio.
+    Pcm,
+
+    // MuLaw indicates mu-law companded audio.
+    MuLaw,
+
+    // ALaw indicates A-law companded audio.
+    ALaw,
+
+    // Mp3 indicates MPEG layer 3 audio.
+    Mp3,
+
+    // Opus indicates OPUS audio.
+    Opus,
+
+    // Silk indicates TrueSilk audio.
+    Silk,
+
+    // Siren indicates Siren audio.
+    Siren,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamStatus {
     // StreamStatusUnknown indicates the audio data stream status is unknown.
-	StreamStatusUnknown,
+    Unknown,
 
-	// StreamStatusNoData indicates that the audio data stream contains no data.
-	StreamStatusNoData,
+    // StreamStatusNoData indicates that the audio data stream contains no data.
+    NoData,
 
-	// StreamStatusPartialData indicates the audio data stream contains partial data of a speak request.
-	StreamStatusPartialData,
+    // StreamStatusPartialData indicates the audio data stream contains partial data of a speak request.
+    PartialData,
 
-	// StreamStatusAllData indicates the audio data stream contains all data of a speak request.
-	StreamStatusAllData,
+    // StreamStatusAllData indicates the audio data stream contains all data of a speak request.
+    AllData,
 
-	// StreamStatusCanceled indicates the audio data stream was canceled.
-	StreamStatusCanceled,
+    // StreamStatusCanceled indicates the audio data stream was canceled.
+    Canceled,
 }
 
-enum SynthesisVoiceGender {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SynthesisVoiceGender {
     GenderUnknown,
     Female,
     Male
 }
 
-enum SynthesisVoiceType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SynthesisVoiceType {
     	// OnlineNeural indicates online neural voice.
 	OnlineNeural,
 
@@ -580,7 +1927,9 @@ enum SynthesisVoiceType {
 	OfflineStandard,
 }
 
-enum VoiceProfileType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+pub enum VoiceProfileType {
     // Text independent speaker identification
 	TextIndependentIdentification,
 
@@ -589,4 +1938,513 @@ enum VoiceProfileType {
 
 	// Text independent speaker verification
 	TextIndependentVerification,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn property_ids_round_trip_through_names_and_ids() {
+        for id in PropertyId::ALL {
+            assert_eq!(PropertyId::from_name(id.name()), Some(id));
+            assert_eq!(PropertyId::from_id(id.id()), Some(id));
+        }
+        assert_eq!(
+            PropertyId::SpeechServiceConnectionRecoModelBackend.id(),
+            3004
+        );
+        assert_eq!(
+            PropertyId::SpeechServiceResponseJsonErrorDetails.name(),
+            "SpeechServiceResponse_JsonErrorDetails"
+        );
+    }
+
+    #[test]
+    fn property_collection_by_id_and_name() {
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::SpeechServiceConnectionRegion, "westus");
+        properties.set_property_by_string("custom-name", "custom-value");
+
+        assert_eq!(
+            properties.get_property_by_string("SpeechServiceConnection_Region", ""),
+            "westus"
+        );
+        assert_eq!(
+            properties.get_property_by_string("custom-name", ""),
+            "custom-value"
+        );
+        assert_eq!(
+            properties.get_property(PropertyId::SpeechServiceConnectionKey, "default"),
+            "default"
+        );
+    }
+
+    #[test]
+    fn property_collection_typed_getters() {
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::SpeechServiceResponseRequestSnr, "True");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionInitialSilenceTimeoutMs,
+            "5000",
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionEndSilenceTimeoutMs,
+            "soon",
+        );
+
+        assert!(properties.get_bool(PropertyId::SpeechServiceResponseRequestSnr, false));
+        assert_eq!(
+            properties.get_u32(
+                PropertyId::SpeechServiceConnectionInitialSilenceTimeoutMs,
+                0
+            ),
+            5000
+        );
+        assert_eq!(
+            properties.get_u32(PropertyId::SpeechServiceConnectionEndSilenceTimeoutMs, 700),
+            700
+        );
+        assert_eq!(properties.get_u64(PropertyId::DataBufferTimeStamp, 42), 42);
+    }
+
+    #[test]
+    fn property_collection_copy_into_overwrites() {
+        let mut source = PropertyCollection::new();
+        source.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "de-DE");
+        source.set_property_by_string("only-in-source", "1");
+        let mut destination = PropertyCollection::new();
+        destination.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "en-US");
+        destination.set_property_by_string("only-in-destination", "2");
+
+        source.copy_into(&mut destination);
+        assert_eq!(
+            destination.property(PropertyId::SpeechServiceConnectionRecoLanguage),
+            Some("de-DE")
+        );
+        assert_eq!(destination.property_by_string("only-in-source"), Some("1"));
+        assert_eq!(
+            destination.property_by_string("only-in-destination"),
+            Some("2")
+        );
+        assert_eq!(destination.len(), 3);
+    }
+
+    fn region_properties(region: &str) -> PropertyCollection {
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::SpeechServiceConnectionRegion, region);
+        properties
+    }
+
+    #[test]
+    fn connection_url_from_region_per_recognition_mode() {
+        let mut properties = region_properties("westus");
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().as_str(),
+            "wss://westus.stt.speech.microsoft.com/speech/recognition/interactive/cognitiveservices/v1?language=en-US"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoMode,
+            RecognitionMode::Conversation.as_str(),
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().path(),
+            "/speech/recognition/conversation/cognitiveservices/v1"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoMode,
+            RecognitionMode::Dictation.as_str(),
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().path(),
+            "/speech/recognition/dictation/cognitiveservices/v1"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+            "en-US,de-DE",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().path(),
+            "/speech/recognition/dictation/cognitiveservices/v1"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionContinuousLanguageIdPriority,
+            "Latency",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().path(),
+            "/speech/universal/v2"
+        );
+    }
+
+    #[test]
+    fn connection_url_from_sovereign_cloud_regions() {
+        let url = region_properties("chinaeast2")
+            .speech_service_connection_url()
+            .unwrap();
+        assert_eq!(url.host_str(), Some("chinaeast2.stt.speech.azure.cn"));
+        let url = region_properties("usgovvirginia")
+            .speech_service_connection_url()
+            .unwrap();
+        assert_eq!(url.host_str(), Some("usgovvirginia.stt.speech.azure.us"));
+    }
+
+    #[test]
+    fn synthesis_connection_url_from_endpoint_host_or_region() {
+        let url = region_properties("westus")
+            .speech_synthesis_connection_url()
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "wss://westus.tts.speech.microsoft.com/cognitiveservices/websocket/v1"
+        );
+        let url = region_properties("chinaeast2")
+            .speech_synthesis_connection_url()
+            .unwrap();
+        assert_eq!(url.host_str(), Some("chinaeast2.tts.speech.azure.cn"));
+
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionHost,
+            "ws://localhost:8080",
+        );
+        properties.set_property(PropertyId::SpeechServiceConnectionEndpointId, "my-voice");
+        assert_eq!(
+            properties
+                .speech_synthesis_connection_url()
+                .unwrap()
+                .as_str(),
+            "ws://localhost:8080/cognitiveservices/websocket/v1?deploymentId=my-voice"
+        );
+
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionEndpoint,
+            "wss://example.com/tts?deploymentId=fixed",
+        );
+        properties.set_property(PropertyId::SpeechServiceConnectionEndpointId, "my-voice");
+        assert_eq!(
+            properties
+                .speech_synthesis_connection_url()
+                .unwrap()
+                .as_str(),
+            "wss://example.com/tts?deploymentId=fixed"
+        );
+        assert!(PropertyCollection::new()
+            .speech_synthesis_connection_url()
+            .is_err());
+    }
+
+    #[test]
+    fn translation_connection_url_lists_target_languages() {
+        let mut properties = region_properties("westeurope");
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "en-US");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionTranslationToLanguages,
+            "de,fr, zh-Hans",
+        );
+        assert_eq!(
+            properties.speech_translation_connection_url().unwrap().as_str(),
+            "wss://westeurope.s2s.speech.microsoft.com/speech/translation/cognitiveservices/v1?from=en-US&to=de&to=fr&to=zh-Hans"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceResponseTranslationRequestStablePartialResult,
+            "true",
+        );
+        let url = properties.speech_translation_connection_url().unwrap();
+        assert_eq!(
+            url.query(),
+            Some("from=en-US&to=de&to=fr&to=zh-Hans&stableTranslation=true")
+        );
+        let url = region_properties("chinaeast2")
+            .speech_translation_connection_url()
+            .unwrap();
+        assert_eq!(url.host_str(), Some("chinaeast2.s2s.speech.azure.cn"));
+        assert!(PropertyCollection::new()
+            .speech_translation_connection_url()
+            .is_err());
+    }
+
+    #[test]
+    fn voices_list_url_from_endpoint_host_or_region() {
+        let url = region_properties("westus").voices_list_url().unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://westus.tts.speech.microsoft.com/cognitiveservices/voices/list"
+        );
+
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionEndpoint,
+            "wss://example.com/tts?deploymentId=voice",
+        );
+        assert_eq!(
+            properties.voices_list_url().unwrap().as_str(),
+            "https://example.com/cognitiveservices/voices/list"
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionVoicesListEndpoint,
+            "http://localhost:8080/voices",
+        );
+        assert_eq!(
+            properties.voices_list_url().unwrap().as_str(),
+            "http://localhost:8080/voices"
+        );
+    }
+
+    #[test]
+    fn speech_synthesis_output_formats_map_to_service_names() {
+        for format in SpeechSynthesisOutputFormat::ALL {
+            assert_eq!(
+                format
+                    .as_str()
+                    .parse::<SpeechSynthesisOutputFormat>()
+                    .unwrap(),
+                format
+            );
+        }
+        assert_eq!(
+            SpeechSynthesisOutputFormat::Riff8Khz16BitMonoPcm.as_str(),
+            "riff-8khz-16bit-mono-pcm"
+        );
+        assert_eq!(SpeechSynthesisOutputFormat::Riff8Khz16BitMonoPcm as u32, 12);
+        assert_eq!(
+            SpeechSynthesisOutputFormat::Riff44100Hz16BitMonoPcm as u32,
+            37
+        );
+        assert!("riff-96khz-16bit-mono-pcm"
+            .parse::<SpeechSynthesisOutputFormat>()
+            .is_err());
+
+        let format: SpeechSynthesisOutputFormat = "RIFF-22050HZ-16BIT-MONO-PCM".parse().unwrap();
+        assert_eq!(format, SpeechSynthesisOutputFormat::Riff22050Hz16BitMonoPcm);
+        assert_eq!(format.samples_per_second(), 22050);
+        assert_eq!(format.bits_per_sample(), 16);
+        assert_eq!(format.container(), SpeechSynthesisOutputContainer::Riff);
+        assert_eq!(format.codec(), SpeechSynthesisOutputCodec::Pcm);
+
+        let format = SpeechSynthesisOutputFormat::Raw8Khz8BitMonoALaw;
+        assert_eq!(
+            (format.samples_per_second(), format.bits_per_sample()),
+            (8000, 8)
+        );
+        assert_eq!(format.codec(), SpeechSynthesisOutputCodec::ALaw);
+        let format = SpeechSynthesisOutputFormat::Webm24Khz16Bit24KbpsMonoOpus;
+        assert_eq!(format.container(), SpeechSynthesisOutputContainer::Webm);
+        assert_eq!(format.codec(), SpeechSynthesisOutputCodec::Opus);
+        assert_eq!(
+            SpeechSynthesisOutputFormat::Audio48Khz192KBitRateMonoMp3.container(),
+            SpeechSynthesisOutputContainer::Raw
+        );
+    }
+
+    #[test]
+    fn connection_url_query_parameters() {
+        let mut properties = region_properties("westus");
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "de-DE");
+        properties.set_property(
+            PropertyId::SpeechServiceResponseOutputFormatOption,
+            "detailed",
+        );
+        properties.set_property(PropertyId::SpeechServiceResponseProfanityOption, "removed");
+        properties.set_property(PropertyId::SpeechServiceConnectionEndpointId, "my-model");
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=de-DE&format=detailed&profanity=removed&cid=my-model")
+        );
+
+        let mut properties = region_properties("westus");
+        properties.set_property(
+            PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse,
+            "true",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=en-US&format=detailed")
+        );
+
+        let mut properties = region_properties("westus");
+        properties.set_property(
+            PropertyId::SpeechServiceResponseRequestWordLevelTimestamps,
+            "true",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=en-US&format=detailed&wordLevelTimestamps=true")
+        );
+    }
+
+    #[test]
+    fn connection_url_maps_profanity_filter_and_defaults_language() {
+        let mut properties = region_properties("westus");
+        properties.set_property(
+            PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse,
+            "true",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=en-US&profanity=masked")
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceResponseRequestProfanityFilterTrueFalse,
+            "false",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=en-US&profanity=raw")
+        );
+        properties.set_property(PropertyId::SpeechServiceResponseProfanityOption, "removed");
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=en-US&profanity=removed")
+        );
+
+        // The language of recognizers detecting the source language is left to the detection.
+        let mut properties = region_properties("westus");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionAutoDetectSourceLanguages,
+            "de-DE,fr-FR",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            None
+        );
+    }
+
+    #[test]
+    fn connection_url_user_defined_query_parameters_override_derived_ones() {
+        let mut properties = region_properties("westus");
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "de-DE");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionUserDefinedQueryParameters,
+            "language=fr-FR&postprocessing=TrueText",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().query(),
+            Some("language=fr-FR&postprocessing=TrueText")
+        );
+    }
+
+    #[test]
+    fn connection_url_from_host_uses_recognition_path() {
+        let mut properties = region_properties("westus");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionHost,
+            "wss://my.speech.host:8443/",
+        );
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoMode, "CONVERSATION");
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "en-US");
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().as_str(),
+            "wss://my.speech.host:8443/speech/recognition/conversation/cognitiveservices/v1?language=en-US"
+        );
+    }
+
+    #[test]
+    fn connection_url_from_endpoint_keeps_path_and_query() {
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionEndpoint,
+            "wss://custom.endpoint/custom/path?language=de-DE",
+        );
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionHost,
+            "wss://ignored.host/",
+        );
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoMode, "DICTATION");
+        properties.set_property(PropertyId::SpeechServiceConnectionRecoLanguage, "en-US");
+        properties.set_property(PropertyId::SpeechServiceResponseProfanityOption, "raw");
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionUserDefinedQueryParameters,
+            "language=fr-FR",
+        );
+        assert_eq!(
+            properties.speech_service_connection_url().unwrap().as_str(),
+            "wss://custom.endpoint/custom/path?language=de-DE&profanity=raw"
+        );
+    }
+
+    #[test]
+    fn connection_url_requires_endpoint_host_or_region() {
+        assert!(matches!(
+            PropertyCollection::new().speech_service_connection_url(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn cancellation_errors_distinguish_quota_from_authentication() {
+        assert_eq!(
+            CancellationError::from_http_status(401),
+            CancellationError::AuthenticationFailure
+        );
+        assert_eq!(
+            CancellationError::from_http_status(403),
+            CancellationError::Forbidden
+        );
+        assert_eq!(
+            CancellationError::from_http_status(429),
+            CancellationError::TooManyRequests
+        );
+        assert_eq!(
+            CancellationError::from_http_status(400),
+            CancellationError::BadRequest
+        );
+        assert_eq!(
+            CancellationError::from_http_status(500),
+            CancellationError::ServiceError
+        );
+        assert_eq!(
+            CancellationError::from_close_code(1007),
+            CancellationError::BadRequest
+        );
+        assert_eq!(
+            CancellationError::from_close_code(1011),
+            CancellationError::ServiceError
+        );
+        assert_eq!(CancellationError::from_recognition_status("Success"), None);
+        assert_eq!(CancellationError::from_recognition_status("NoMatch"), None);
+        assert_eq!(
+            CancellationError::from_recognition_status("Forbidden"),
+            Some(CancellationError::Forbidden)
+        );
+        assert_eq!(
+            CancellationError::from_recognition_status("Error"),
+            Some(CancellationError::ServiceError)
+        );
+    }
+
+    #[test]
+    fn cancellation_error_details_from_service_payloads() {
+        let details = CancellationErrorDetails::from_service_payload(
+            "Forbidden",
+            r#"{"error":{"code":"QuotaExceeded","message":"Out of call volume quota."}}"#,
+        );
+        assert_eq!(
+            details,
+            CancellationErrorDetails::new("QuotaExceeded", "Out of call volume quota.")
+        );
+        assert_eq!(
+            details.to_string(),
+            "QuotaExceeded: Out of call volume quota."
+        );
+
+        let details = CancellationErrorDetails::from_service_payload(
+            "Unauthorized",
+            r#"{"code":"401","message":"Access denied due to invalid subscription key."}"#,
+        );
+        assert_eq!(details.reason, "401");
+
+        let details = CancellationErrorDetails::from_service_payload(
+            "WebSocket close code 1007",
+            "Invalid audio",
+        );
+        assert_eq!(
+            details,
+            CancellationErrorDetails::new("WebSocket close code 1007", "Invalid audio")
+        );
+        assert_eq!(
+            CancellationErrorDetails::from_service_payload("Unauthorized", "").to_string(),
+            "Unauthorized"
+        );
+    }
+}
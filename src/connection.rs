@@ -0,0 +1,130 @@
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use crate::common::{CancellationError, CancellationErrorDetails, PropertyCollection, PropertyId};
+use crate::error::{Error, Result};
+use crate::usp::{Frame, Message};
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+pub(crate) const HEADER_SUBSCRIPTION_KEY: &str = "Ocp-Apim-Subscription-Key";
+pub(crate) const HEADER_CONNECTION_ID: &str = "X-ConnectionId";
+
+// connect opens a WebSocket connection to the speech service, authenticating with the subscription key or
+// authorization token found in properties.
+pub(crate) async fn connect(
+    url: &Url,
+    properties: &PropertyCollection,
+    connection_id: &str,
+) -> Result<(ConnectionSender, ConnectionReceiver)> {
+    let mut url = url.clone();
+    let scheme = match url.scheme() {
+        "http" => Some("ws"),
+        "https" => Some("wss"),
+        _ => None,
+    };
+    if let Some(scheme) = scheme {
+        url.set_scheme(scheme)
+            .map_err(|_| Error::InvalidUrl(url.to_string()))?;
+    }
+
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| Error::InvalidUrl(format!("{}: {}", url, e)))?;
+    let headers = request.headers_mut();
+    if let Some(key) = properties.property(PropertyId::SpeechServiceConnectionKey) {
+        headers.insert(HEADER_SUBSCRIPTION_KEY, header_value(key)?);
+    }
+    if let Some(token) = properties.property(PropertyId::SpeechServiceAuthorizationToken) {
+        headers.insert("Authorization", header_value(&format!("Bearer {}", token))?);
+    }
+    headers.insert(HEADER_CONNECTION_ID, header_value(connection_id)?);
+
+    let (stream, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| match e {
+            WsError::Http(response) => {
+                let status = response.status();
+                let body = response
+                    .body()
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                let reason = status.canonical_reason().unwrap_or("HTTP error");
+                Error::Canceled(
+                    CancellationError::from_http_status(status.as_u16()),
+                    CancellationErrorDetails::from_service_payload(reason, &body),
+                )
+            }
+            e => Error::Connection(format!("failed to connect to {}: {}", url, e)),
+        })?;
+    let (sink, stream) = stream.split();
+    Ok((ConnectionSender { sink }, ConnectionReceiver { stream }))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value)
+        .map_err(|_| Error::InvalidArgument(format!("{:?} is not a valid header value", value)))
+}
+
+// ConnectionSender is the sending half of a speech service connection.
+pub(crate) struct ConnectionSender {
+    sink: SplitSink<Stream, WsMessage>,
+}
+
+impl ConnectionSender {
+    pub(crate) async fn send(&mut self, message: &Message) -> Result<()> {
+        let frame = match message.encode()? {
+            Frame::Text(text) => WsMessage::Text(text),
+            Frame::Binary(data) => WsMessage::Binary(data),
+        };
+        self.sink
+            .send(frame)
+            .await
+            .map_err(|e| Error::NetworkSendFailed(format!("{}: {}", message.path(), e)))
+    }
+
+    pub(crate) async fn close(&mut self) {
+        let _ = self.sink.close().await;
+    }
+}
+
+// ConnectionReceiver is the receiving half of a speech service connection.
+pub(crate) struct ConnectionReceiver {
+    stream: SplitStream<Stream>,
+}
+
+impl ConnectionReceiver {
+    // receive waits for the next message from the service. None is returned once the connection is closed
+    // normally.
+    pub(crate) async fn receive(&mut self) -> Result<Option<Message>> {
+        while let Some(frame) = self.stream.next().await {
+            let frame = frame.map_err(|e| Error::Connection(e.to_string()))?;
+            let frame = match frame {
+                WsMessage::Text(text) => Frame::Text(text),
+                WsMessage::Binary(data) => Frame::Binary(data),
+                WsMessage::Close(Some(close)) if close.code != 1000.into() => {
+                    let code = u16::from(close.code);
+                    return Err(Error::Canceled(
+                        CancellationError::from_close_code(code),
+                        CancellationErrorDetails::from_service_payload(
+                            &format!("WebSocket close code {}", code),
+                            &close.reason,
+                        ),
+                    ));
+                }
+                WsMessage::Close(_) => return Ok(None),
+                WsMessage::Ping(_) | WsMessage::Pong(_) | WsMessage::Frame(_) => continue,
+            };
+            return Message::decode(&frame).map(Some);
+        }
+        Ok(None)
+    }
+}
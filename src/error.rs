@@ -0,0 +1,603 @@
+use std::fmt;
+use std::io;
+
+use thiserror::Error;
+
+use crate::common::{CancellationError, CancellationErrorDetails};
+
+// Error is the error type returned by fallible operations in the speech sdk. Every error maps to one of the
+// SPXERR_* error codes of the C API, see code.
+#[derive(Debug, Error)]
+pub enum Error {
+    // NotImplemented indicates that the function is not implemented.
+    #[error("not implemented: {0}")]
+    NotImplemented(String),
+
+    // NotFound indicates that an object or property was not found.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    // InvalidArgument indicates that a function argument is invalid.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    // Timeout indicates that the operation did not complete in time.
+    #[error("timeout: {0}")]
+    Timeout(String),
+
+    // AlreadyInProgress indicates that the asynchronous operation is already in progress.
+    #[error("already in progress: {0}")]
+    AlreadyInProgress(String),
+
+    // FileOpenFailed indicates that a file could not be opened.
+    #[error("failed to open {0}: {1}")]
+    FileOpenFailed(String, #[source] io::Error),
+
+    // UnexpectedEof indicates that the end of the audio was reached unexpectedly.
+    #[error("unexpected end of file: {0}")]
+    UnexpectedEof(String),
+
+    // InvalidHeader indicates that an invalid audio header was encountered.
+    #[error("invalid header: {0}")]
+    InvalidHeader(String),
+
+    // UnsupportedFormat indicates that the audio format is not supported.
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+
+    // Abort indicates that the operation was aborted.
+    #[error("aborted: {0}")]
+    Abort(String),
+
+    // InvalidState indicates that the operation is not allowed in the current state of the object.
+    #[error("invalid state: {0}")]
+    InvalidState(String),
+
+    // RuntimeError indicates an unexpected runtime error.
+    #[error("runtime error: {0}")]
+    RuntimeError(String),
+
+    // InvalidUrl indicates that an endpoint or host url is malformed or uses an unsupported scheme.
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+
+    // InvalidRegion indicates that the region name is empty or malformed.
+    #[error("invalid region: {0}")]
+    InvalidRegion(String),
+
+    // BufferTooSmall indicates that a buffer is too small for the requested data.
+    #[error("buffer too small: {0}")]
+    BufferTooSmall(String),
+
+    // OutOfRange indicates that a value is out of range.
+    #[error("out of range: {0}")]
+    OutOfRange(String),
+
+    // ContainerFormatNotSupported indicates that a compressed container format is not supported.
+    #[error("container format not supported: {0}")]
+    ContainerFormatNotSupported(String),
+
+    // InvalidLanguage indicates that a language is missing or malformed.
+    #[error("invalid language: {0}")]
+    InvalidLanguage(String),
+
+    // UnsupportedApi indicates that the API is not applicable to the object it is called on.
+    #[error("unsupported api: {0}")]
+    UnsupportedApi(String),
+
+    // Canceled indicates that the speech service canceled the operation. The cancellation error tells why,
+    // e.g. an authentication failure or an exhausted quota.
+    #[error("canceled ({0:?}): {1}")]
+    Canceled(CancellationError, CancellationErrorDetails),
+
+    // DataNotAvailable indicates that the requested data is not available.
+    #[error("data not available: {0}")]
+    DataNotAvailable(String),
+
+    // NetworkSendFailed indicates that sending a message to the speech service failed.
+    #[error("network send failed: {0}")]
+    NetworkSendFailed(String),
+
+    // Protocol indicates that a message exchanged with the speech service could not be encoded or decoded.
+    #[error("speech service protocol error: {0}")]
+    Protocol(String),
+
+    // Connection indicates that the connection to the speech service could not be established or was lost.
+    #[error("connection error: {0}")]
+    Connection(String),
+
+    // Io indicates that reading or writing audio failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    // Other carries any other error code of the C API.
+    #[error("{0}: {1}")]
+    Other(ErrorCode, String),
+}
+
+impl Error {
+    // code is the SPXERR_* error code corresponding to the error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::NotImplemented(_) => ErrorCode::NotImpl,
+            Error::NotFound(_) => ErrorCode::NotFound,
+            Error::InvalidArgument(_) => ErrorCode::InvalidArg,
+            Error::Timeout(_) => ErrorCode::Timeout,
+            Error::AlreadyInProgress(_) => ErrorCode::AlreadyInProgress,
+            Error::FileOpenFailed(..) => ErrorCode::FileOpenFailed,
+            Error::UnexpectedEof(_) => ErrorCode::UnexpectedEof,
+            Error::InvalidHeader(_) => ErrorCode::InvalidHeader,
+            Error::UnsupportedFormat(_) => ErrorCode::UnsupportedFormat,
+            Error::Abort(_) => ErrorCode::Abort,
+            Error::InvalidState(_) => ErrorCode::InvalidState,
+            Error::RuntimeError(_) => ErrorCode::RuntimeError,
+            Error::InvalidUrl(_) => ErrorCode::InvalidUrl,
+            Error::InvalidRegion(_) => ErrorCode::InvalidRegion,
+            Error::BufferTooSmall(_) => ErrorCode::BufferTooSmall,
+            Error::OutOfRange(_) => ErrorCode::OutOfRange,
+            Error::ContainerFormatNotSupported(_) => ErrorCode::ContainerFormatNotSupportedError,
+            Error::InvalidLanguage(_) => ErrorCode::InvalidLanguage,
+            Error::UnsupportedApi(_) => ErrorCode::UnsupportedApiError,
+            Error::Canceled(..) => ErrorCode::Canceled,
+            Error::DataNotAvailable(_) => ErrorCode::DataNotAvailable,
+            Error::NetworkSendFailed(_) => ErrorCode::NetworkSendFailed,
+            Error::Protocol(_) | Error::Connection(_) => ErrorCode::UnexpectedUspSiteFailure,
+            Error::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                ErrorCode::UnexpectedEof
+            }
+            Error::Io(_) => ErrorCode::RuntimeError,
+            Error::Other(code, _) => *code,
+        }
+    }
+
+    // cancellation is the cancellation error and details reported to the caller when the error ends a
+    // recognition or synthesis.
+    pub fn cancellation(&self) -> (CancellationError, CancellationErrorDetails) {
+        let error = match self {
+            Error::Canceled(error, details) => return (*error, details.clone()),
+            Error::Connection(_) | Error::NetworkSendFailed(_) => {
+                CancellationError::ConnectionFailure
+            }
+            Error::Timeout(_) => CancellationError::ServiceTimeout,
+            Error::Protocol(_) => CancellationError::ServiceError,
+            _ => CancellationError::RuntimeError,
+        };
+        (
+            error,
+            CancellationErrorDetails::new(self.code().name(), self.to_string()),
+        )
+    }
+}
+
+// ErrorCode is an error code of the speech sdk, as defined by the SPXERR_* constants of the C API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    // Uninitialized means the object has not been properly initialized.
+    Uninitialized = 0x001,
+
+    // AlreadyInitialized means the object has already been initialized.
+    AlreadyInitialized = 0x002,
+
+    // UnhandledException means an unhandled exception was detected.
+    UnhandledException = 0x003,
+
+    // NotFound means the object or property was not found.
+    NotFound = 0x004,
+
+    // InvalidArg means one or more arguments are not valid.
+    InvalidArg = 0x005,
+
+    // Timeout means the specified timeout value has elapsed.
+    Timeout = 0x006,
+
+    // AlreadyInProgress means the asynchronous operation is already in progress.
+    AlreadyInProgress = 0x007,
+
+    // FileOpenFailed means the attempt to open the file failed.
+    FileOpenFailed = 0x008,
+
+    // UnexpectedEof means the end of the file was reached unexpectedly.
+    UnexpectedEof = 0x009,
+
+    // InvalidHeader means invalid audio header encountered.
+    InvalidHeader = 0x00a,
+
+    // AudioIsPumping means the requested operation cannot be performed while audio is pumping.
+    AudioIsPumping = 0x00b,
+
+    // UnsupportedFormat means unsupported audio format.
+    UnsupportedFormat = 0x00c,
+
+    // Abort means operation aborted.
+    Abort = 0x00d,
+
+    // MicNotAvailable means microphone is not available.
+    MicNotAvailable = 0x00e,
+
+    // InvalidState means an invalid state was encountered.
+    InvalidState = 0x00f,
+
+    // UuidCreateFailed means attempting to create a UUID failed.
+    UuidCreateFailed = 0x010,
+
+    // SetformatUnexpectedStateTransition means an unexpected state transition was encountered when setting
+    // the audio format.
+    SetformatUnexpectedStateTransition = 0x011,
+
+    // ProcessAudioInvalidState means audio was processed in an invalid state.
+    ProcessAudioInvalidState = 0x012,
+
+    // StartRecognizingInvalidStateTransition means recognition was started in an invalid state.
+    StartRecognizingInvalidStateTransition = 0x013,
+
+    // UnexpectedCreateObjectFailure means an unexpected error was encountered when trying to create an
+    // internal object.
+    UnexpectedCreateObjectFailure = 0x014,
+
+    // MicError means an error in the audio-capturing system.
+    MicError = 0x015,
+
+    // NoAudioInput means the requested operation cannot be performed; there is no audio input.
+    NoAudioInput = 0x016,
+
+    // UnexpectedUspSiteFailure means an unexpected error was encountered when trying to access the USP site.
+    UnexpectedUspSiteFailure = 0x017,
+
+    // UnexpectedLuSiteFailure means an unexpected error was encountered when trying to access the
+    // LuAdapterSite site.
+    UnexpectedLuSiteFailure = 0x018,
+
+    // BufferTooSmall means the buffer is too small.
+    BufferTooSmall = 0x019,
+
+    // OutOfMemory means a method failed to allocate memory.
+    OutOfMemory = 0x01a,
+
+    // RuntimeError means an unexpected runtime error occurred.
+    RuntimeError = 0x01b,
+
+    // InvalidUrl means the url specified is invalid.
+    InvalidUrl = 0x01c,
+
+    // InvalidRegion means the region specified is invalid or missing.
+    InvalidRegion = 0x01d,
+
+    // SwitchModeNotAllowed means switch between single shot and continuous recognition is not supported.
+    SwitchModeNotAllowed = 0x01e,
+
+    // ChangeConnectionStatusNotAllowed means changing connection status is not supported in the current
+    // recognition state.
+    ChangeConnectionStatusNotAllowed = 0x01f,
+
+    // ExplicitConnectionNotSupportedByRecognizer means explicit connection management is not supported by
+    // the specified recognizer.
+    ExplicitConnectionNotSupportedByRecognizer = 0x020,
+
+    // InvalidHandle means the handle is invalid.
+    InvalidHandle = 0x021,
+
+    // InvalidRecognizer means the recognizer is invalid.
+    InvalidRecognizer = 0x022,
+
+    // OutOfRange means the value is out of range.
+    OutOfRange = 0x023,
+
+    // ExtensionLibraryNotFound means extension library not found.
+    ExtensionLibraryNotFound = 0x024,
+
+    // UnexpectedTtsEngineSiteFailure means an unexpected error was encountered when trying to access the TTS
+    // engine site.
+    UnexpectedTtsEngineSiteFailure = 0x025,
+
+    // UnexpectedAudioOutputFailure means an unexpected error was encountered when trying to access the audio
+    // output stream.
+    UnexpectedAudioOutputFailure = 0x026,
+
+    // GstreamerInternalError means gstreamer internal error.
+    GstreamerInternalError = 0x027,
+
+    // ContainerFormatNotSupportedError means compressed container format not supported.
+    ContainerFormatNotSupportedError = 0x028,
+
+    // GstreamerNotFoundError means codec extension or gstreamer not found.
+    GstreamerNotFoundError = 0x029,
+
+    // InvalidLanguage means the language specified is missing.
+    InvalidLanguage = 0x02a,
+
+    // UnsupportedApiError means the API is not applicable.
+    UnsupportedApiError = 0x02b,
+
+    // RingbufferDataUnavailable means the ring buffer is unavailable.
+    RingbufferDataUnavailable = 0x02c,
+
+    // UnexpectedConversationSiteFailure means an unexpected error was encountered when trying to access the
+    // Conversation site.
+    UnexpectedConversationSiteFailure = 0x030,
+
+    // UnexpectedConversationTranslatorSiteFailure means an unexpected error was encountered when trying to
+    // access the Conversation site.
+    UnexpectedConversationTranslatorSiteFailure = 0x031,
+
+    // Canceled means an asynchronous operation was canceled before it was executed.
+    Canceled = 0x032,
+
+    // CompressAudioCodecInitifailed means codec for compression could not be initialized.
+    CompressAudioCodecInitifailed = 0x033,
+
+    // DataNotAvailable means data not available.
+    DataNotAvailable = 0x034,
+
+    // InvalidResultReason means invalid result reason.
+    InvalidResultReason = 0x035,
+
+    // UnexpectedRnntSiteFailure means an unexpected error was encountered when trying to access the RNN-T
+    // site.
+    UnexpectedRnntSiteFailure = 0x036,
+
+    // NetworkSendFailed means sending of a network message failed.
+    NetworkSendFailed = 0x037,
+
+    // AudioSysLibraryNotFound means audio extension library not found.
+    AudioSysLibraryNotFound = 0x038,
+
+    // LoudspeakerError means an error in the audio-rendering system.
+    LoudspeakerError = 0x039,
+
+    // VisionSiteFailure means an unexpected error was encountered when trying to access the Vision site.
+    VisionSiteFailure = 0x050,
+
+    // MediaInvalidStream means stream number provided was invalid in the current context.
+    MediaInvalidStream = 0x060,
+
+    // MediaInvalidOffset means offset required is invalid in the current context.
+    MediaInvalidOffset = 0x061,
+
+    // MediaNoMoreData means no more data is available in source.
+    MediaNoMoreData = 0x062,
+
+    // MediaNotStarted means source has not been started.
+    MediaNotStarted = 0x063,
+
+    // MediaAlreadyStarted means source has already been started.
+    MediaAlreadyStarted = 0x064,
+
+    // MediaDeviceCreationFailed means media device creation failed.
+    MediaDeviceCreationFailed = 0x065,
+
+    // MediaNoDeviceAvailable means no devices of the selected category are available.
+    MediaNoDeviceAvailable = 0x066,
+
+    // VadCouldNotUseWithKeywordRecognizer means enabled Voice Activity Detection while using keyword
+    // recognition is not allowed.
+    VadCouldNotUseWithKeywordRecognizer = 0x067,
+
+    // CouldNotCreateEngineAdapter means the specified RecoEngineAdapter could not be created.
+    CouldNotCreateEngineAdapter = 0x070,
+
+    // NotImpl means the function is not implemented.
+    NotImpl = 0xfff,
+}
+
+impl ErrorCode {
+    // ALL lists every error code.
+    pub const ALL: [ErrorCode; 65] = [
+        ErrorCode::Uninitialized,
+        ErrorCode::AlreadyInitialized,
+        ErrorCode::UnhandledException,
+        ErrorCode::NotFound,
+        ErrorCode::InvalidArg,
+        ErrorCode::Timeout,
+        ErrorCode::AlreadyInProgress,
+        ErrorCode::FileOpenFailed,
+        ErrorCode::UnexpectedEof,
+        ErrorCode::InvalidHeader,
+        ErrorCode::AudioIsPumping,
+        ErrorCode::UnsupportedFormat,
+        ErrorCode::Abort,
+        ErrorCode::MicNotAvailable,
+        ErrorCode::InvalidState,
+        ErrorCode::UuidCreateFailed,
+        ErrorCode::SetformatUnexpectedStateTransition,
+        ErrorCode::ProcessAudioInvalidState,
+        ErrorCode::StartRecognizingInvalidStateTransition,
+        ErrorCode::UnexpectedCreateObjectFailure,
+        ErrorCode::MicError,
+        ErrorCode::NoAudioInput,
+        ErrorCode::UnexpectedUspSiteFailure,
+        ErrorCode::UnexpectedLuSiteFailure,
+        ErrorCode::BufferTooSmall,
+        ErrorCode::OutOfMemory,
+        ErrorCode::RuntimeError,
+        ErrorCode::InvalidUrl,
+        ErrorCode::InvalidRegion,
+        ErrorCode::SwitchModeNotAllowed,
+        ErrorCode::ChangeConnectionStatusNotAllowed,
+        ErrorCode::ExplicitConnectionNotSupportedByRecognizer,
+        ErrorCode::InvalidHandle,
+        ErrorCode::InvalidRecognizer,
+        ErrorCode::OutOfRange,
+        ErrorCode::ExtensionLibraryNotFound,
+        ErrorCode::UnexpectedTtsEngineSiteFailure,
+        ErrorCode::UnexpectedAudioOutputFailure,
+        ErrorCode::GstreamerInternalError,
+        ErrorCode::ContainerFormatNotSupportedError,
+        ErrorCode::GstreamerNotFoundError,
+        ErrorCode::InvalidLanguage,
+        ErrorCode::UnsupportedApiError,
+        ErrorCode::RingbufferDataUnavailable,
+        ErrorCode::UnexpectedConversationSiteFailure,
+        ErrorCode::UnexpectedConversationTranslatorSiteFailure,
+        ErrorCode::Canceled,
+        ErrorCode::CompressAudioCodecInitifailed,
+        ErrorCode::DataNotAvailable,
+        ErrorCode::InvalidResultReason,
+        ErrorCode::UnexpectedRnntSiteFailure,
+        ErrorCode::NetworkSendFailed,
+        ErrorCode::AudioSysLibraryNotFound,
+        ErrorCode::LoudspeakerError,
+        ErrorCode::VisionSiteFailure,
+        ErrorCode::MediaInvalidStream,
+        ErrorCode::MediaInvalidOffset,
+        ErrorCode::MediaNoMoreData,
+        ErrorCode::MediaNotStarted,
+        ErrorCode::MediaAlreadyStarted,
+        ErrorCode::MediaDeviceCreationFailed,
+        ErrorCode::MediaNoDeviceAvailable,
+        ErrorCode::VadCouldNotUseWithKeywordRecognizer,
+        ErrorCode::CouldNotCreateEngineAdapter,
+        ErrorCode::NotImpl,
+    ];
+
+    // value is the numeric value of the error code.
+    pub fn value(&self) -> u32 {
+        *self as u32
+    }
+
+    // from_value looks up an error code by its numeric value.
+    pub fn from_value(value: u32) -> Option<ErrorCode> {
+        ErrorCode::ALL
+            .iter()
+            .copied()
+            .find(|code| code.value() == value)
+    }
+
+    // name is the name of the constant in the C API, e.g. "SPXERR_INVALID_ARG".
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorCode::Uninitialized => "SPXERR_UNINITIALIZED",
+            ErrorCode::AlreadyInitialized => "SPXERR_ALREADY_INITIALIZED",
+            ErrorCode::UnhandledException => "SPXERR_UNHANDLED_EXCEPTION",
+            ErrorCode::NotFound => "SPXERR_NOT_FOUND",
+            ErrorCode::InvalidArg => "SPXERR_INVALID_ARG",
+            ErrorCode::Timeout => "SPXERR_TIMEOUT",
+            ErrorCode::AlreadyInProgress => "SPXERR_ALREADY_IN_PROGRESS",
+            ErrorCode::FileOpenFailed => "SPXERR_FILE_OPEN_FAILED",
+            ErrorCode::UnexpectedEof => "SPXERR_UNEXPECTED_EOF",
+            ErrorCode::InvalidHeader => "SPXERR_INVALID_HEADER",
+            ErrorCode::AudioIsPumping => "SPXERR_AUDIO_IS_PUMPING",
+            ErrorCode::UnsupportedFormat => "SPXERR_UNSUPPORTED_FORMAT",
+            ErrorCode::Abort => "SPXERR_ABORT",
+            ErrorCode::MicNotAvailable => "SPXERR_MIC_NOT_AVAILABLE",
+            ErrorCode::InvalidState => "SPXERR_INVALID_STATE",
+            ErrorCode::UuidCreateFailed => "SPXERR_UUID_CREATE_FAILED",
+            ErrorCode::SetformatUnexpectedStateTransition => {
+                "SPXERR_SETFORMAT_UNEXPECTED_STATE_TRANSITION"
+            }
+            ErrorCode::ProcessAudioInvalidState => "SPXERR_PROCESS_AUDIO_INVALID_STATE",
+            ErrorCode::StartRecognizingInvalidStateTransition => {
+                "SPXERR_START_RECOGNIZING_INVALID_STATE_TRANSITION"
+            }
+            ErrorCode::UnexpectedCreateObjectFailure => "SPXERR_UNEXPECTED_CREATE_OBJECT_FAILURE",
+            ErrorCode::MicError => "SPXERR_MIC_ERROR",
+            ErrorCode::NoAudioInput => "SPXERR_NO_AUDIO_INPUT",
+            ErrorCode::UnexpectedUspSiteFailure => "SPXERR_UNEXPECTED_USP_SITE_FAILURE",
+            ErrorCode::UnexpectedLuSiteFailure => "SPXERR_UNEXPECTED_LU_SITE_FAILURE",
+            ErrorCode::BufferTooSmall => "SPXERR_BUFFER_TOO_SMALL",
+            ErrorCode::OutOfMemory => "SPXERR_OUT_OF_MEMORY",
+            ErrorCode::RuntimeError => "SPXERR_RUNTIME_ERROR",
+            ErrorCode::InvalidUrl => "SPXERR_INVALID_URL",
+            ErrorCode::InvalidRegion => "SPXERR_INVALID_REGION",
+            ErrorCode::SwitchModeNotAllowed => "SPXERR_SWITCH_MODE_NOT_ALLOWED",
+            ErrorCode::ChangeConnectionStatusNotAllowed => {
+                "SPXERR_CHANGE_CONNECTION_STATUS_NOT_ALLOWED"
+            }
+            ErrorCode::ExplicitConnectionNotSupportedByRecognizer => {
+                "SPXERR_EXPLICIT_CONNECTION_NOT_SUPPORTED_BY_RECOGNIZER"
+            }
+            ErrorCode::InvalidHandle => "SPXERR_INVALID_HANDLE",
+            ErrorCode::InvalidRecognizer => "SPXERR_INVALID_RECOGNIZER",
+            ErrorCode::OutOfRange => "SPXERR_OUT_OF_RANGE",
+            ErrorCode::ExtensionLibraryNotFound => "SPXERR_EXTENSION_LIBRARY_NOT_FOUND",
+            ErrorCode::UnexpectedTtsEngineSiteFailure => {
+                "SPXERR_UNEXPECTED_TTS_ENGINE_SITE_FAILURE"
+            }
+            ErrorCode::UnexpectedAudioOutputFailure => "SPXERR_UNEXPECTED_AUDIO_OUTPUT_FAILURE",
+            ErrorCode::GstreamerInternalError => "SPXERR_GSTREAMER_INTERNAL_ERROR",
+            ErrorCode::ContainerFormatNotSupportedError => {
+                "SPXERR_CONTAINER_FORMAT_NOT_SUPPORTED_ERROR"
+            }
+            ErrorCode::GstreamerNotFoundError => "SPXERR_GSTREAMER_NOT_FOUND_ERROR",
+            ErrorCode::InvalidLanguage => "SPXERR_INVALID_LANGUAGE",
+            ErrorCode::UnsupportedApiError => "SPXERR_UNSUPPORTED_API_ERROR",
+            ErrorCode::RingbufferDataUnavailable => "SPXERR_RINGBUFFER_DATA_UNAVAILABLE",
+            ErrorCode::UnexpectedConversationSiteFailure => {
+                "SPXERR_UNEXPECTED_CONVERSATION_SITE_FAILURE"
+            }
+            ErrorCode::UnexpectedConversationTranslatorSiteFailure => {
+                "SPXERR_UNEXPECTED_CONVERSATION_TRANSLATOR_SITE_FAILURE"
+            }
+            ErrorCode::Canceled => "SPXERR_CANCELED",
+            ErrorCode::CompressAudioCodecInitifailed => "SPXERR_COMPRESS_AUDIO_CODEC_INITIFAILED",
+            ErrorCode::DataNotAvailable => "SPXERR_DATA_NOT_AVAILABLE",
+            ErrorCode::InvalidResultReason => "SPXERR_INVALID_RESULT_REASON",
+            ErrorCode::UnexpectedRnntSiteFailure => "SPXERR_UNEXPECTED_RNNT_SITE_FAILURE",
+            ErrorCode::NetworkSendFailed => "SPXERR_NETWORK_SEND_FAILED",
+            ErrorCode::AudioSysLibraryNotFound => "SPXERR_AUDIO_SYS_LIBRARY_NOT_FOUND",
+            ErrorCode::LoudspeakerError => "SPXERR_LOUDSPEAKER_ERROR",
+            ErrorCode::VisionSiteFailure => "SPXERR_VISION_SITE_FAILURE",
+            ErrorCode::MediaInvalidStream => "SPXERR_MEDIA_INVALID_STREAM",
+            ErrorCode::MediaInvalidOffset => "SPXERR_MEDIA_INVALID_OFFSET",
+            ErrorCode::MediaNoMoreData => "SPXERR_MEDIA_NO_MORE_DATA",
+            ErrorCode::MediaNotStarted => "SPXERR_MEDIA_NOT_STARTED",
+            ErrorCode::MediaAlreadyStarted => "SPXERR_MEDIA_ALREADY_STARTED",
+            ErrorCode::MediaDeviceCreationFailed => "SPXERR_MEDIA_DEVICE_CREATION_FAILED",
+            ErrorCode::MediaNoDeviceAvailable => "SPXERR_MEDIA_NO_DEVICE_AVAILABLE",
+            ErrorCode::VadCouldNotUseWithKeywordRecognizer => {
+                "SPXERR_VAD_COULD_NOT_USE_WITH_KEYWORD_RECOGNIZER"
+            }
+            ErrorCode::CouldNotCreateEngineAdapter => "SPXERR_COULD_NOT_CREATE_ENGINE_ADAPTER",
+            ErrorCode::NotImpl => "SPXERR_NOT_IMPL",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:03x})", self.name(), self.value())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_match_the_c_api() {
+        assert_eq!(ErrorCode::NotImpl.value(), 0xfff);
+        assert_eq!(ErrorCode::InvalidArg.value(), 0x005);
+        assert_eq!(ErrorCode::RuntimeError.value(), 0x01b);
+        assert_eq!(ErrorCode::InvalidUrl.value(), 0x01c);
+        assert_eq!(ErrorCode::CouldNotCreateEngineAdapter.value(), 0x070);
+        assert_eq!(ErrorCode::InvalidRegion.name(), "SPXERR_INVALID_REGION");
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_value(code.value()), Some(code));
+            assert!(code.name().starts_with("SPXERR_"));
+        }
+        assert_eq!(ErrorCode::from_value(0x02d), None);
+    }
+
+    #[test]
+    fn errors_map_to_codes_and_cancellations() {
+        assert_eq!(
+            Error::InvalidRegion("".to_string()).code(),
+            ErrorCode::InvalidRegion
+        );
+        let eof = io::Error::new(io::ErrorKind::UnexpectedEof, "eof");
+        assert_eq!(Error::from(eof).code(), ErrorCode::UnexpectedEof);
+
+        let (error, details) = Error::Connection("refused".to_string()).cancellation();
+        assert_eq!(error, CancellationError::ConnectionFailure);
+        assert_eq!(details.reason, "SPXERR_UNEXPECTED_USP_SITE_FAILURE");
+        assert!(details.detailed_text.contains("refused"));
+
+        let quota = Error::Canceled(
+            CancellationError::Forbidden,
+            CancellationErrorDetails::new("Forbidden", "quota exceeded"),
+        );
+        assert_eq!(quota.code(), ErrorCode::Canceled);
+        assert_eq!(quota.cancellation().0, CancellationError::Forbidden);
+    }
+}
@@ -0,0 +1,18 @@
+use std::time::Duration;
+
+// SessionEventArgs represents the session identifier shared by the events of a recognition session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionEventArgs {
+    // session_id is the session identifier.
+    pub session_id: String,
+}
+
+// RecognitionEventArgs represents the recognition event arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecognitionEventArgs {
+    // session_id is the session identifier.
+    pub session_id: String,
+
+    // offset of the recognition event from the start of the audio.
+    pub offset: Duration,
+}
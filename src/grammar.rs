@@ -0,0 +1,297 @@
+use std::sync::{Arc, Mutex as StdMutex};
+
+use serde_json::json;
+
+use crate::error::{Error, Result};
+use crate::speech_recognizer::SpeechRecognizer;
+
+// PhraseListGrammar allows adding phrases to improve the recognition of words the service would not expect,
+// such as names. Phrases can be changed at any time; changes apply from the next turn of the recognition
+// session on.
+pub struct PhraseListGrammar {
+    grammars: Arc<StdMutex<DynamicGrammar>>,
+}
+
+impl PhraseListGrammar {
+    // from_recognizer gets the phrase list grammar of the specified recognizer.
+    pub fn from_recognizer(recognizer: &SpeechRecognizer) -> Self {
+        PhraseListGrammar {
+            grammars: recognizer.grammars(),
+        }
+    }
+
+    // add_phrase adds a simple phrase that may be spoken by the user.
+    pub fn add_phrase(&self, phrase: &str) -> Result<()> {
+        let phrase = phrase.trim();
+        if phrase.is_empty() {
+            return Err(Error::InvalidArgument(
+                "phrase must not be empty".to_string(),
+            ));
+        }
+        self.grammars
+            .lock()
+            .unwrap()
+            .phrases
+            .push(phrase.to_string());
+        Ok(())
+    }
+
+    // clear clears all phrases from the phrase list grammar.
+    pub fn clear(&self) {
+        self.grammars.lock().unwrap().phrases.clear();
+    }
+}
+
+// GrammarList represents the list of grammars for dynamic grammar scenarios, such as grammars stored with
+// the service. Like phrases, grammars apply from the next turn of the recognition session on.
+pub struct GrammarList {
+    grammars: Arc<StdMutex<DynamicGrammar>>,
+}
+
+impl GrammarList {
+    // from_recognizer gets the grammar list of the specified recognizer.
+    pub fn from_recognizer(recognizer: &SpeechRecognizer) -> Self {
+        GrammarList {
+            grammars: recognizer.grammars(),
+        }
+    }
+
+    // add_grammar adds a stored grammar or a class language model to the list.
+    pub fn add_grammar(&self, grammar: impl Into<Grammar>) {
+        self.grammars.lock().unwrap().grammars.push(grammar.into());
+    }
+
+    // set_recognition_factor sets how strongly the grammars of the list are favored over the base model.
+    // Factors above 1 favor the grammars, factors below 1 favor the base model.
+    pub fn set_recognition_factor(&self, factor: f64, scope: RecognitionFactorScope) -> Result<()> {
+        if !factor.is_finite() || factor < 0.0 {
+            return Err(Error::InvalidArgument(format!(
+                "recognition factor {} must not be negative",
+                factor
+            )));
+        }
+        self.grammars.lock().unwrap().recognition_factor = Some((factor, scope));
+        Ok(())
+    }
+}
+
+// RecognitionFactorScope defines the scope a recognition factor is applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecognitionFactorScope {
+    // PartialPhrase applies the factor to grammars that can match parts of a phrase.
+    PartialPhrase = 1,
+}
+
+impl RecognitionFactorScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecognitionFactorScope::PartialPhrase => "PartialPhrase",
+        }
+    }
+}
+
+// Grammar is a grammar stored with the service, referenced by its storage id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar {
+    storage_id: String,
+    // classes are the grammars assigned to the classes of a class language model.
+    classes: Vec<(String, String)>,
+}
+
+impl Grammar {
+    // from_storage_id creates a grammar from the storage id of a grammar stored with the service.
+    pub fn from_storage_id(storage_id: &str) -> Result<Self> {
+        Ok(Grammar {
+            storage_id: validate_storage_id(storage_id)?,
+            classes: Vec::new(),
+        })
+    }
+
+    // storage_id is the storage id of the grammar.
+    pub fn storage_id(&self) -> &str {
+        &self.storage_id
+    }
+}
+
+// ClassLanguageModel is a language model stored with the service whose classes are filled with other stored
+// grammars, e.g. a model with a class for product names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassLanguageModel {
+    storage_id: String,
+    classes: Vec<(String, String)>,
+}
+
+impl ClassLanguageModel {
+    // from_storage_id creates a class language model from the storage id of a model stored with the service.
+    pub fn from_storage_id(storage_id: &str) -> Result<Self> {
+        Ok(ClassLanguageModel {
+            storage_id: validate_storage_id(storage_id)?,
+            classes: Vec::new(),
+        })
+    }
+
+    // assign_class assigns a grammar to the class named class_name, replacing the grammar assigned before.
+    pub fn assign_class(&mut self, class_name: &str, grammar: &Grammar) -> Result<()> {
+        if class_name.trim().is_empty() {
+            return Err(Error::InvalidArgument(
+                "class name must not be empty".to_string(),
+            ));
+        }
+        if !grammar.classes.is_empty() {
+            return Err(Error::InvalidArgument(
+                "a class language model cannot be assigned to a class".to_string(),
+            ));
+        }
+        self.classes.retain(|(name, _)| name != class_name);
+        self.classes
+            .push((class_name.to_string(), grammar.storage_id.clone()));
+        Ok(())
+    }
+}
+
+impl From<ClassLanguageModel> for Grammar {
+    fn from(model: ClassLanguageModel) -> Self {
+        Grammar {
+            storage_id: model.storage_id,
+            classes: model.classes,
+        }
+    }
+}
+
+fn validate_storage_id(storage_id: &str) -> Result<String> {
+    let storage_id = storage_id.trim();
+    if storage_id.is_empty() {
+        return Err(Error::InvalidArgument(
+            "storage id must not be empty".to_string(),
+        ));
+    }
+    Ok(storage_id.to_string())
+}
+
+// DynamicGrammar holds the grammars of a recognizer, which are sent to the service in the dgi section of the
+// speech.context message at the start of every turn: phrases as a generic group, stored grammars by
+// reference, class language models with the grammars of their classes, and the recognition factor applied
+// to the referenced grammars.
+#[derive(Debug, Default)]
+pub(crate) struct DynamicGrammar {
+    phrases: Vec<String>,
+    grammars: Vec<Grammar>,
+    recognition_factor: Option<(f64, RecognitionFactorScope)>,
+}
+
+impl DynamicGrammar {
+    // speech_context is the speech.context message for the grammars, or None if there are none.
+    pub(crate) fn speech_context(&self) -> Option<serde_json::Value> {
+        if self.phrases.is_empty() && self.grammars.is_empty() {
+            return None;
+        }
+        let mut dgi = serde_json::Map::new();
+        if !self.phrases.is_empty() {
+            let items: Vec<_> = self
+                .phrases
+                .iter()
+                .map(|phrase| json!({ "Text": phrase }))
+                .collect();
+            dgi.insert(
+                "Groups".to_string(),
+                json!([{ "Type": "Generic", "Items": items }]),
+            );
+        }
+        let (models, grammars): (Vec<&Grammar>, Vec<&Grammar>) = self
+            .grammars
+            .iter()
+            .partition(|grammar| !grammar.classes.is_empty());
+        if !grammars.is_empty() {
+            let references: Vec<_> = grammars
+                .iter()
+                .map(|grammar| grammar.storage_id.as_str())
+                .collect();
+            dgi.insert("ReferenceGrammars".to_string(), json!(references));
+        }
+        if !models.is_empty() {
+            let models: Vec<_> = models
+                .iter()
+                .map(|model| {
+                    let classes: serde_json::Map<_, _> = model
+                        .classes
+                        .iter()
+                        .map(|(name, grammar)| (name.clone(), json!(grammar)))
+                        .collect();
+                    json!({ "ReferenceGrammar": model.storage_id, "Classes": classes })
+                })
+                .collect();
+            dgi.insert("ClassModels".to_string(), json!(models));
+        }
+        if let Some((factor, scope)) = self
+            .recognition_factor
+            .filter(|_| !self.grammars.is_empty())
+        {
+            dgi.insert(
+                "Bias".to_string(),
+                json!({ "RecognitionFactor": factor, "Scope": scope.as_str() }),
+            );
+        }
+        Some(json!({ "dgi": dgi }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrases_are_sent_as_generic_group() {
+        let mut grammar = DynamicGrammar::default();
+        assert_eq!(grammar.speech_context(), None);
+        grammar.phrases = vec!["Contoso".to_string(), "Jessie".to_string()];
+        assert_eq!(
+            grammar.speech_context().unwrap(),
+            json!({
+                "dgi": {
+                    "Groups": [{
+                        "Type": "Generic",
+                        "Items": [{ "Text": "Contoso" }, { "Text": "Jessie" }],
+                    }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn grammars_are_sent_by_reference_with_recognition_factor() {
+        let mut grammar = DynamicGrammar {
+            recognition_factor: Some((2.5, RecognitionFactorScope::PartialPhrase)),
+            ..Default::default()
+        };
+        // The factor only applies to referenced grammars.
+        assert_eq!(grammar.speech_context(), None);
+
+        let products = Grammar::from_storage_id("products").unwrap();
+        let mut model = ClassLanguageModel::from_storage_id(" orders-clm ").unwrap();
+        model
+            .assign_class("ProductName", &Grammar::from_storage_id("old").unwrap())
+            .unwrap();
+        model.assign_class("ProductName", &products).unwrap();
+        model
+            .assign_class("Customer", &Grammar::from_storage_id("customers").unwrap())
+            .unwrap();
+        assert!(model.assign_class("", &products).is_err());
+        assert!(model.assign_class("Nested", &model.clone().into()).is_err());
+        assert!(Grammar::from_storage_id("  ").is_err());
+
+        grammar.grammars = vec![products, model.into()];
+        assert_eq!(
+            grammar.speech_context().unwrap(),
+            json!({
+                "dgi": {
+                    "ReferenceGrammars": ["products"],
+                    "ClassModels": [{
+                        "ReferenceGrammar": "orders-clm",
+                        "Classes": { "ProductName": "products", "Customer": "customers" },
+                    }],
+                    "Bias": { "RecognitionFactor": 2.5, "Scope": "PartialPhrase" },
+                },
+            })
+        );
+    }
+}
@@ -1,4 +1,24 @@
-mod common;
+pub mod audio;
+pub mod auto_detect_source_language_config;
+pub mod common;
+mod connection;
+pub mod error;
+pub mod events;
+pub mod grammar;
+mod recognizer;
+mod session;
+pub mod source_language_config;
+pub mod source_language_recognizer;
+pub mod speech_config;
+pub mod speech_recognizer;
+pub mod speech_synthesizer;
+pub mod speech_translation_config;
+pub mod ssml;
+#[cfg(test)]
+mod test_server;
+pub mod translation_recognizer;
+pub mod usp;
+pub mod voice_info;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,486 @@
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::audio::InputSource;
+use crate::common::{
+    CancellationError, CancellationErrorDetails, CancellationReason, PropertyCollection,
+    PropertyId, RecognitionMode, ResultReason,
+};
+use crate::error::{Error, Result};
+use crate::events::{RecognitionEventArgs, SessionEventArgs};
+use crate::session::{self, Flow, Signal};
+use crate::speech_recognizer::{
+    ticks, RecognitionEvent, RecognitionEvents, SpeechRecognitionCanceledEventArgs,
+    SpeechRecognitionEventArgs,
+};
+use crate::usp::{Message, Path};
+
+// RecognitionResult is implemented by the results of the recognizers, which share the handling of recognition
+// sessions and events.
+pub(crate) trait RecognitionResult: Clone + Send + 'static {
+    // HYPOTHESIS and PHRASE are the paths of the messages carrying intermediate and final results.
+    const HYPOTHESIS: Path;
+    const PHRASE: Path;
+
+    // from_reason builds a result that carries nothing but its reason.
+    fn from_reason(reason: ResultReason) -> Self;
+
+    // from_cancellation builds a canceled result with the details of the cancellation.
+    fn from_cancellation(details: &CancellationErrorDetails) -> Self;
+
+    // from_hypothesis builds an intermediate result from a hypothesis message.
+    fn from_hypothesis(message: &Message, session_id: &str) -> Result<Self>;
+
+    // from_phrase builds a result from a phrase message, or None for phrases that do not carry a result.
+    fn from_phrase(message: &Message, session_id: &str) -> Result<Option<Self>>;
+
+    fn reason(&self) -> ResultReason;
+
+    fn offset(&self) -> Duration;
+
+    fn properties(&self) -> &PropertyCollection;
+}
+
+// Connection is how a recognition connects to the service: the url, and the speech.context message sent at the
+// start of every turn.
+pub(crate) struct Connection {
+    pub(crate) url: Url,
+    pub(crate) speech_context: Box<dyn FnMut() -> Option<serde_json::Value> + Send>,
+}
+
+// Recognizer holds the state shared by the recognizers: their properties, audio input, callbacks and running
+// continuous recognition. R is the type of their results.
+pub(crate) struct Recognizer<R> {
+    properties: PropertyCollection,
+    audio: Arc<Mutex<InputSource>>,
+    callbacks: Arc<StdMutex<Callbacks<R>>>,
+    continuous: Option<ContinuousRecognition>,
+}
+
+struct ContinuousRecognition {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    // events sends service events, such as changes of the target languages, to the running session.
+    events: mpsc::UnboundedSender<serde_json::Value>,
+}
+
+impl<R: RecognitionResult> Recognizer<R> {
+    pub(crate) fn new(properties: PropertyCollection, audio: InputSource) -> Self {
+        Recognizer {
+            properties,
+            audio: Arc::new(Mutex::new(audio)),
+            callbacks: Arc::new(StdMutex::new(Callbacks::default())),
+            continuous: None,
+        }
+    }
+
+    pub(crate) fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+
+    pub(crate) fn properties_mut(&mut self) -> &mut PropertyCollection {
+        &mut self.properties
+    }
+
+    // recognize_once runs a recognition until a single utterance is recognized. connect is called with the
+    // properties of the recognition, whose recognition mode is set, to connect to the service.
+    pub(crate) async fn recognize_once(
+        &self,
+        connect: impl FnOnce(&mut PropertyCollection) -> Result<Connection>,
+    ) -> Result<R> {
+        if self.continuous_in_progress() {
+            return Err(Error::InvalidState(
+                "continuous recognition is already in progress".to_string(),
+            ));
+        }
+        let callbacks = self.callbacks.clone();
+        let mut result = None;
+        recognize(
+            self.properties.clone(),
+            self.audio.clone(),
+            RecognitionMode::Interactive,
+            true,
+            None,
+            None,
+            connect,
+            |event| {
+                dispatch(&callbacks, &event);
+                match event {
+                    RecognitionEvent::Recognized(args) => {
+                        result.get_or_insert(args.result);
+                    }
+                    RecognitionEvent::Canceled(args) => {
+                        result.get_or_insert(args.result);
+                    }
+                    _ => {}
+                }
+            },
+        )
+        .await;
+        Ok(result.unwrap_or_else(|| R::from_reason(ResultReason::NoMatch)))
+    }
+
+    // start_continuous_recognition runs a recognition in the background until stop_continuous_recognition is
+    // called or the end of the audio is reached.
+    pub(crate) async fn start_continuous_recognition(
+        &mut self,
+        connect: impl FnOnce(&mut PropertyCollection) -> Result<Connection> + Send + 'static,
+    ) -> Result<RecognitionEvents<R>> {
+        if self.continuous_in_progress() {
+            return Err(Error::InvalidState(
+                "continuous recognition is already in progress".to_string(),
+            ));
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (stop, stopped) = oneshot::channel();
+        let (events, session_events) = mpsc::unbounded_channel();
+        let callbacks = self.callbacks.clone();
+        let properties = self.properties.clone();
+        let audio = self.audio.clone();
+        // The stream ends after recognize has returned and dropped stopped, so that a recognition can start
+        // again as soon as the stream has ended.
+        let task = tokio::spawn(async move {
+            recognize(
+                properties,
+                audio,
+                RecognitionMode::Conversation,
+                false,
+                Some(stopped),
+                Some(session_events),
+                connect,
+                |event| {
+                    dispatch(&callbacks, &event);
+                    // The caller may have dropped the stream and only rely on callbacks.
+                    let _ = sender.send(event);
+                },
+            )
+            .await;
+        });
+        self.continuous = Some(ContinuousRecognition { stop, task, events });
+        Ok(RecognitionEvents { receiver })
+    }
+
+    // stop_continuous_recognition stops a running continuous recognition and returns once its session has
+    // stopped.
+    pub(crate) async fn stop_continuous_recognition(&mut self) -> Result<()> {
+        let Some(continuous) = self.continuous.take() else {
+            return Ok(());
+        };
+        let _ = continuous.stop.send(());
+        continuous
+            .task
+            .await
+            .map_err(|e| Error::InvalidState(format!("recognition task failed: {}", e)))
+    }
+
+    // continuous_in_progress tells whether a continuous recognition is running. One whose session has ended by
+    // itself, at the end of the audio or on an error, no longer is, even if it was never stopped.
+    fn continuous_in_progress(&self) -> bool {
+        self.continuous
+            .as_ref()
+            .is_some_and(|continuous| !continuous.stop.is_closed())
+    }
+
+    // send_event sends an event to the session of the running continuous recognition, if any.
+    pub(crate) fn send_event(&self, event: serde_json::Value) {
+        if let Some(continuous) = &self.continuous {
+            // The session may have ended already, e.g. at the end of the audio.
+            let _ = continuous.events.send(event);
+        }
+    }
+
+    pub(crate) fn on_session_started(
+        &self,
+        callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().session_started = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_session_stopped(
+        &self,
+        callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().session_stopped = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_speech_start_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().speech_start_detected = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_speech_end_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().speech_end_detected = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_recognizing(
+        &self,
+        callback: impl Fn(&SpeechRecognitionEventArgs<R>) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().recognizing = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_recognized(
+        &self,
+        callback: impl Fn(&SpeechRecognitionEventArgs<R>) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().recognized = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn on_canceled(
+        &self,
+        callback: impl Fn(&SpeechRecognitionCanceledEventArgs<R>) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().canceled = Some(Arc::new(callback));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn recognize<R: RecognitionResult>(
+    mut properties: PropertyCollection,
+    audio: Arc<Mutex<InputSource>>,
+    default_mode: RecognitionMode,
+    once: bool,
+    stop: Option<oneshot::Receiver<()>>,
+    events: Option<mpsc::UnboundedReceiver<serde_json::Value>>,
+    connect: impl FnOnce(&mut PropertyCollection) -> Result<Connection>,
+    mut emit: impl FnMut(RecognitionEvent<R>),
+) {
+    if properties
+        .property(PropertyId::SpeechServiceConnectionRecoMode)
+        .is_none()
+    {
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoMode,
+            default_mode.as_str(),
+        );
+    }
+    let connection = connect(&mut properties);
+    let mode = properties
+        .get_property(PropertyId::SpeechServiceConnectionRecoMode, "")
+        .parse::<RecognitionMode>();
+    let (connection, mode) = match (connection, mode) {
+        (Ok(connection), Ok(mode)) => (connection, mode),
+        (Err(err), _) | (_, Err(err)) => {
+            emit(RecognitionEvent::Canceled(canceled_by_error("", err)));
+            return;
+        }
+    };
+
+    session::run(
+        &connection.url,
+        &properties,
+        audio,
+        mode,
+        once,
+        stop,
+        events,
+        connection.speech_context,
+        |session_id, signal| match signal {
+            Signal::Started => {
+                emit(RecognitionEvent::SessionStarted(SessionEventArgs {
+                    session_id: session_id.to_string(),
+                }));
+                Flow::Continue
+            }
+            Signal::Stopped => {
+                emit(RecognitionEvent::SessionStopped(SessionEventArgs {
+                    session_id: session_id.to_string(),
+                }));
+                Flow::Continue
+            }
+            Signal::EndOfStream => {
+                emit(RecognitionEvent::Canceled(canceled(
+                    session_id,
+                    CancellationReason::EndOfStream,
+                    R::from_reason(ResultReason::Canceled),
+                )));
+                Flow::Continue
+            }
+            Signal::Failed(err) => {
+                emit(RecognitionEvent::Canceled(canceled_by_error(
+                    session_id, err,
+                )));
+                Flow::Abort
+            }
+            Signal::Message(message) => match handle_message(session_id, &message) {
+                Ok((event, flow)) => {
+                    if let Some(event) = event {
+                        emit(event);
+                    }
+                    flow
+                }
+                Err(err) => {
+                    emit(RecognitionEvent::Canceled(canceled_by_error(
+                        session_id, err,
+                    )));
+                    Flow::Abort
+                }
+            },
+        },
+    )
+    .await;
+}
+
+fn handle_message<R: RecognitionResult>(
+    session_id: &str,
+    message: &Message,
+) -> Result<(Option<RecognitionEvent<R>>, Flow)> {
+    let path = message.path();
+    let event = if path == Path::SpeechStartDetected {
+        RecognitionEvent::SpeechStartDetected(RecognitionEventArgs {
+            session_id: session_id.to_string(),
+            offset: ticks(&message.json_body()?["Offset"]),
+        })
+    } else if path == Path::SpeechEndDetected {
+        RecognitionEvent::SpeechEndDetected(RecognitionEventArgs {
+            session_id: session_id.to_string(),
+            offset: ticks(&message.json_body()?["Offset"]),
+        })
+    } else if path == R::HYPOTHESIS {
+        let result = R::from_hypothesis(message, session_id)?;
+        RecognitionEvent::Recognizing(recognition(session_id, result))
+    } else if path == R::PHRASE {
+        let Some(result) = R::from_phrase(message, session_id)? else {
+            return Ok((None, Flow::Continue));
+        };
+        if result.reason() == ResultReason::Canceled {
+            let status = result
+                .properties()
+                .get_property(PropertyId::CancellationDetailsReasonText, "");
+            let reason = CancellationReason::Error(
+                CancellationError::from_recognition_status(&status)
+                    .unwrap_or(CancellationError::ServiceError),
+                CancellationErrorDetails::new(
+                    status,
+                    result
+                        .properties()
+                        .get_property(PropertyId::CancellationDetailsReasonDetailedText, ""),
+                ),
+            );
+            let event = canceled(session_id, reason, result);
+            return Ok((Some(RecognitionEvent::Canceled(event)), Flow::Abort));
+        }
+        let event = recognition(session_id, result);
+        return Ok((Some(RecognitionEvent::Recognized(event)), Flow::Final));
+    } else {
+        return Ok((None, Flow::Continue));
+    };
+    Ok((Some(event), Flow::Continue))
+}
+
+// recognition builds the arguments of an event carrying a result.
+fn recognition<R: RecognitionResult>(session_id: &str, result: R) -> SpeechRecognitionEventArgs<R> {
+    SpeechRecognitionEventArgs {
+        session_id: session_id.to_string(),
+        offset: result.offset(),
+        result,
+    }
+}
+
+// canceled builds the arguments of a canceled event.
+fn canceled<R>(
+    session_id: &str,
+    reason: CancellationReason,
+    result: R,
+) -> SpeechRecognitionCanceledEventArgs<R> {
+    let error_details = match &reason {
+        CancellationReason::Error(_, details) => details.to_string(),
+        _ => String::new(),
+    };
+    SpeechRecognitionCanceledEventArgs {
+        session_id: session_id.to_string(),
+        result,
+        reason,
+        error_details,
+    }
+}
+
+// canceled_by_error builds the arguments of the canceled event reporting err.
+fn canceled_by_error<R: RecognitionResult>(
+    session_id: &str,
+    err: Error,
+) -> SpeechRecognitionCanceledEventArgs<R> {
+    let (error, details) = err.cancellation();
+    let result = R::from_cancellation(&details);
+    canceled(
+        session_id,
+        CancellationReason::Error(error, details),
+        result,
+    )
+}
+
+type Callback<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+struct Callbacks<R> {
+    session_started: Option<Callback<SessionEventArgs>>,
+    session_stopped: Option<Callback<SessionEventArgs>>,
+    speech_start_detected: Option<Callback<RecognitionEventArgs>>,
+    speech_end_detected: Option<Callback<RecognitionEventArgs>>,
+    recognizing: Option<Callback<SpeechRecognitionEventArgs<R>>>,
+    recognized: Option<Callback<SpeechRecognitionEventArgs<R>>>,
+    canceled: Option<Callback<SpeechRecognitionCanceledEventArgs<R>>>,
+}
+
+// Callbacks is not derived as Default and Clone, which would require R to be Default and Clone as well.
+impl<R> Default for Callbacks<R> {
+    fn default() -> Self {
+        Callbacks {
+            session_started: None,
+            session_stopped: None,
+            speech_start_detected: None,
+            speech_end_detected: None,
+            recognizing: None,
+            recognized: None,
+            canceled: None,
+        }
+    }
+}
+
+impl<R> Clone for Callbacks<R> {
+    fn clone(&self) -> Self {
+        Callbacks {
+            session_started: self.session_started.clone(),
+            session_stopped: self.session_stopped.clone(),
+            speech_start_detected: self.speech_start_detected.clone(),
+            speech_end_detected: self.speech_end_detected.clone(),
+            recognizing: self.recognizing.clone(),
+            recognized: self.recognized.clone(),
+            canceled: self.canceled.clone(),
+        }
+    }
+}
+
+// dispatch calls the callback registered for event. The callbacks are cloned out of the lock first: a callback
+// may register callbacks itself, and a panicking callback must not poison the lock.
+fn dispatch<R>(callbacks: &StdMutex<Callbacks<R>>, event: &RecognitionEvent<R>) {
+    let callbacks = callbacks.lock().unwrap().clone();
+    callbacks.dispatch(event);
+}
+
+impl<R> Callbacks<R> {
+    fn dispatch(&self, event: &RecognitionEvent<R>) {
+        fn call<T>(callback: &Option<Callback<T>>, args: &T) {
+            if let Some(callback) = callback {
+                callback(args);
+            }
+        }
+        match event {
+            RecognitionEvent::SessionStarted(args) => call(&self.session_started, args),
+            RecognitionEvent::SessionStopped(args) => call(&self.session_stopped, args),
+            RecognitionEvent::SpeechStartDetected(args) => call(&self.speech_start_detected, args),
+            RecognitionEvent::SpeechEndDetected(args) => call(&self.speech_end_detected, args),
+            RecognitionEvent::Recognizing(args) => call(&self.recognizing, args),
+            RecognitionEvent::Recognized(args) => call(&self.recognized, args),
+            RecognitionEvent::Canceled(args) => call(&self.canceled, args),
+        }
+    }
+}
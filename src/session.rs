@@ -0,0 +1,372 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::audio::{AudioStreamFormat, InputSource};
+use crate::common::{PropertyCollection, RecognitionMode};
+use crate::connection::{self, ConnectionReceiver, ConnectionSender};
+use crate::error::{Error, Result};
+use crate::usp::{self, Message, Path};
+
+// Signal is something that happened during a recognition session, as reported by run.
+pub(crate) enum Signal {
+    // Started is reported once the connection to the service is established.
+    Started,
+
+    // Message is a message the service sent for the current turn.
+    Message(Message),
+
+    // EndOfStream is reported in continuous mode when the end of the audio was reached.
+    EndOfStream,
+
+    // Failed is reported when the session ends because of an error.
+    Failed(Error),
+
+    // Stopped is reported when a session that was started ends.
+    Stopped,
+}
+
+// Flow tells run how to proceed after a signal was handled.
+pub(crate) enum Flow {
+    // Continue keeps the session going.
+    Continue,
+
+    // Final indicates that the message carried a final result. In single-shot mode this ends the turn.
+    Final,
+
+    // Abort ends the session, e.g. because the service reported an error.
+    Abort,
+}
+
+// run drives a recognition session: it connects to url, streams audio in turns and hands every
+// service message of the current turn to handler. In single-shot mode the session ends after the first
+// turn; otherwise new turns are started until the audio is exhausted or stop fires, in which case the
+// audio is ended and the results pending for the current turn are flushed. speech_context is called at
+// the start of every turn; events received on the events channel are sent to the service as they arrive.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run(
+    url: &Url,
+    properties: &PropertyCollection,
+    audio: Arc<Mutex<InputSource>>,
+    mode: RecognitionMode,
+    once: bool,
+    mut stop: Option<oneshot::Receiver<()>>,
+    mut events: Option<mpsc::UnboundedReceiver<serde_json::Value>>,
+    mut speech_context: impl FnMut() -> Option<serde_json::Value>,
+    mut handler: impl FnMut(&str, Signal) -> Flow,
+) {
+    let session_id = usp::request_id();
+    let mut session = match Session::connect(url, properties, audio, mode, session_id.clone()).await
+    {
+        Ok(session) => session,
+        Err(err) => {
+            handler(&session_id, Signal::Failed(err));
+            return;
+        }
+    };
+    handler(&session_id, Signal::Started);
+
+    let mut stopping = false;
+    'turns: loop {
+        let context = speech_context();
+        let mut turn = match session.start_turn(context.as_ref()).await {
+            Ok(turn) => turn,
+            Err(err) => {
+                handler(&session_id, Signal::Failed(err));
+                break;
+            }
+        };
+        loop {
+            let received = tokio::select! {
+                _ = stopped(&mut stop), if !stopping => {
+                    stopping = true;
+                    turn.stop_audio();
+                    continue;
+                }
+                event = next_event(&mut events) => {
+                    if let Err(err) = session.send_event(&turn, &event).await {
+                        handler(&session_id, Signal::Failed(err));
+                        break 'turns;
+                    }
+                    continue;
+                }
+                received = session.receive() => received,
+            };
+            match received {
+                Ok(Some(message)) if turn.is_current(&message) => {
+                    if message.path() == Path::TurnEnd {
+                        break;
+                    }
+                    match handler(&session_id, Signal::Message(message)) {
+                        Flow::Continue => {}
+                        Flow::Final if once => turn.stop_audio(),
+                        Flow::Final => {}
+                        Flow::Abort => break 'turns,
+                    }
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    if !stopping {
+                        let err = Error::Connection("connection closed by the service".to_string());
+                        handler(&session_id, Signal::Failed(err));
+                    }
+                    break 'turns;
+                }
+                Err(err) => {
+                    handler(&session_id, Signal::Failed(err));
+                    break 'turns;
+                }
+            }
+        }
+        if let Err(err) = turn.finish().await {
+            handler(&session_id, Signal::Failed(err));
+            break;
+        }
+        if once || stopping {
+            break;
+        }
+        if session.audio_exhausted() {
+            handler(&session_id, Signal::EndOfStream);
+            break;
+        }
+    }
+    session.close().await;
+    handler(&session_id, Signal::Stopped);
+}
+
+async fn stopped(stop: &mut Option<oneshot::Receiver<()>>) {
+    match stop {
+        // A dropped sender also stops the session.
+        Some(stop) => {
+            let _ = stop.await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn next_event(
+    events: &mut Option<mpsc::UnboundedReceiver<serde_json::Value>>,
+) -> serde_json::Value {
+    if let Some(receiver) = events {
+        if let Some(event) = receiver.recv().await {
+            return event;
+        }
+    }
+    // No more events once the sender is gone.
+    *events = None;
+    std::future::pending().await
+}
+
+// Session is a connection to the speech service over which audio is streamed in turns.
+pub(crate) struct Session {
+    sender: Arc<Mutex<ConnectionSender>>,
+    receiver: ConnectionReceiver,
+    audio: Arc<Mutex<InputSource>>,
+    audio_exhausted: Arc<AtomicBool>,
+}
+
+impl Session {
+    // connect opens a connection to url and sends the speech.config message describing the client and
+    // the audio source.
+    pub(crate) async fn connect(
+        url: &Url,
+        properties: &PropertyCollection,
+        audio: Arc<Mutex<InputSource>>,
+        mode: RecognitionMode,
+        connection_id: String,
+    ) -> Result<Session> {
+        let (mut sender, receiver) = connection::connect(url, properties, &connection_id).await?;
+        let format = audio.lock().await.format().clone();
+        sender
+            .send(&Message::json(
+                Path::SpeechConfig,
+                &connection_id,
+                &speech_config_payload(&format, mode),
+            ))
+            .await?;
+        Ok(Session {
+            sender: Arc::new(Mutex::new(sender)),
+            receiver,
+            audio,
+            audio_exhausted: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    // start_turn sends the speech.context message, if any, and starts streaming audio for a new turn.
+    pub(crate) async fn start_turn(
+        &self,
+        speech_context: Option<&serde_json::Value>,
+    ) -> Result<Turn> {
+        let request_id = usp::request_id();
+        if let Some(context) = speech_context {
+            self.sender
+                .lock()
+                .await
+                .send(&Message::json(Path::SpeechContext, &request_id, context))
+                .await?;
+        }
+        let (stop, stopped) = oneshot::channel();
+        let pump = tokio::spawn(pump_audio(
+            self.sender.clone(),
+            self.audio.clone(),
+            request_id.clone(),
+            stopped,
+            self.audio_exhausted.clone(),
+        ));
+        Ok(Turn {
+            request_id,
+            stop: Some(stop),
+            pump: Some(pump),
+        })
+    }
+
+    // send_event sends an event message for turn, e.g. to change settings of the running session.
+    pub(crate) async fn send_event(&self, turn: &Turn, event: &serde_json::Value) -> Result<()> {
+        self.sender
+            .lock()
+            .await
+            .send(&Message::json(Path::Event, &turn.request_id, event))
+            .await
+    }
+
+    // receive waits for the next message from the service. None is returned once the connection is closed.
+    pub(crate) async fn receive(&mut self) -> Result<Option<Message>> {
+        self.receiver.receive().await
+    }
+
+    // audio_exhausted reports whether the end of the audio source has been reached.
+    pub(crate) fn audio_exhausted(&self) -> bool {
+        self.audio_exhausted.load(Ordering::SeqCst)
+    }
+
+    pub(crate) async fn close(self) {
+        self.sender.lock().await.close().await;
+    }
+}
+
+// Turn is a single request/response exchange with the service, identified by its request id.
+pub(crate) struct Turn {
+    request_id: String,
+    stop: Option<oneshot::Sender<()>>,
+    pump: Option<JoinHandle<Result<()>>>,
+}
+
+impl Turn {
+    // is_current reports whether a message received from the service belongs to this turn.
+    pub(crate) fn is_current(&self, message: &Message) -> bool {
+        match message.request_id() {
+            Some(id) => id.eq_ignore_ascii_case(&self.request_id),
+            None => true,
+        }
+    }
+
+    // stop_audio stops streaming audio and tells the service that the audio of this turn has ended, so
+    // that pending results are flushed.
+    pub(crate) fn stop_audio(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+
+    // finish stops streaming audio, if still running, and waits for the audio pump to exit.
+    pub(crate) async fn finish(mut self) -> Result<()> {
+        self.stop_audio();
+        match self.pump.take() {
+            Some(pump) => pump
+                .await
+                .map_err(|e| Error::RuntimeError(format!("audio streaming task failed: {}", e)))?,
+            None => Ok(()),
+        }
+    }
+}
+
+// Dropping a turn that was not finished stops its audio pump, so that it releases the audio source.
+impl Drop for Turn {
+    fn drop(&mut self) {
+        self.stop_audio();
+    }
+}
+
+async fn pump_audio(
+    sender: Arc<Mutex<ConnectionSender>>,
+    audio: Arc<Mutex<InputSource>>,
+    request_id: String,
+    mut stopped: oneshot::Receiver<()>,
+    audio_exhausted: Arc<AtomicBool>,
+) -> Result<()> {
+    let mut audio = audio.lock().await;
+    // Audio is sent in chunks of 100ms.
+    let chunk_size = (audio.format().average_bytes_per_second() as usize / 10).max(1);
+    let content_type = audio.format().content_type();
+    let mut header = Some(audio.format().header());
+    loop {
+        let chunk = tokio::select! {
+            biased;
+            _ = &mut stopped => break,
+            chunk = audio.read_chunk(chunk_size) => chunk?,
+        };
+        let Some(chunk) = chunk else {
+            audio_exhausted.store(true, Ordering::SeqCst);
+            break;
+        };
+        let mut message = match header.take() {
+            Some(mut data) => {
+                data.extend_from_slice(&chunk.data);
+                Message::audio(&request_id, Some(content_type), data)
+            }
+            None => Message::audio(&request_id, None, chunk.data),
+        };
+        if let Some(timestamp) = &chunk.timestamp {
+            message.set_header(usp::HEADER_PTS, timestamp);
+        }
+        if let Some(user_id) = &chunk.user_id {
+            message.set_header(usp::HEADER_SPEAKER_ID, user_id);
+        }
+        sender.lock().await.send(&message).await?;
+    }
+    let mut sender = sender.lock().await;
+    // An empty header would be taken for the end of the audio.
+    if let Some(data) = header.filter(|data| !data.is_empty()) {
+        sender
+            .send(&Message::audio(&request_id, Some(content_type), data))
+            .await?;
+    }
+    sender
+        .send(&Message::audio(&request_id, None, Vec::new()))
+        .await
+}
+
+fn speech_config_payload(format: &AudioStreamFormat, mode: RecognitionMode) -> serde_json::Value {
+    let mut source = format.speech_config_description();
+    source["connectivity"] = json!("Unknown");
+    source["manufacturer"] = json!("Speech SDK");
+    source["model"] = json!("Stream");
+    source["type"] = json!("Stream");
+    let mut context = client_context();
+    context["audio"] = json!({ "source": source });
+    json!({
+        "context": context,
+        "recognition": mode.as_str().to_ascii_lowercase(),
+    })
+}
+
+// client_context describes the sdk and the operating system in the speech.config message.
+pub(crate) fn client_context() -> serde_json::Value {
+    json!({
+        "system": {
+            "name": "SpeechSDK",
+            "version": env!("CARGO_PKG_VERSION"),
+            "build": "Rust",
+            "lang": "Rust",
+        },
+        "os": {
+            "platform": std::env::consts::OS,
+            "name": std::env::consts::FAMILY,
+            "version": std::env::consts::ARCH,
+        },
+    })
+}
@@ -0,0 +1,43 @@
+use crate::error::{Error, Result};
+use crate::speech_config::validate_language;
+
+// SourceLanguageConfig defines the language of the audio, optionally recognized with a custom model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLanguageConfig {
+    language: String,
+    endpoint_id: Option<String>,
+}
+
+impl SourceLanguageConfig {
+    // from_language creates a source language config for the specified language, in BCP-47 format.
+    pub fn from_language(language: &str) -> Result<Self> {
+        Ok(SourceLanguageConfig {
+            language: validate_language(language)?,
+            endpoint_id: None,
+        })
+    }
+
+    // from_language_and_endpoint_id creates a source language config for the specified language, recognized
+    // with the custom model deployed to the specified endpoint.
+    pub fn from_language_and_endpoint_id(language: &str, endpoint_id: &str) -> Result<Self> {
+        if endpoint_id.trim().is_empty() {
+            return Err(Error::InvalidArgument(
+                "endpoint id must not be empty".to_string(),
+            ));
+        }
+        Ok(SourceLanguageConfig {
+            language: validate_language(language)?,
+            endpoint_id: Some(endpoint_id.to_string()),
+        })
+    }
+
+    // language is the source language.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    // endpoint_id is the endpoint id of the custom model for the language, if any.
+    pub fn endpoint_id(&self) -> Option<&str> {
+        self.endpoint_id.as_deref()
+    }
+}
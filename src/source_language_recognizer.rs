@@ -0,0 +1,234 @@
+use crate::audio::AudioConfig;
+use crate::auto_detect_source_language_config::{
+    self, AutoDetectSourceLanguageConfig, LANGUAGE_ID_ONLY,
+};
+use crate::common::PropertyCollection;
+use crate::error::Result;
+use crate::events::{RecognitionEventArgs, SessionEventArgs};
+use crate::speech_config::SpeechConfig;
+use crate::speech_recognizer::{
+    RecognitionEvents, SpeechRecognitionCanceledEventArgs, SpeechRecognitionEventArgs,
+    SpeechRecognitionResult, SpeechRecognizer,
+};
+
+// SourceLanguageRecognizer detects the language of the audio among the languages of an auto detect source
+// language config, without transcribing it. Its results carry no text; the detected language and the confidence
+// of the service in it are read with AutoDetectSourceLanguageResult::from_result.
+pub struct SourceLanguageRecognizer {
+    recognizer: SpeechRecognizer,
+}
+
+impl SourceLanguageRecognizer {
+    // from_auto_detect_source_language_config creates a source language recognizer from a speech config, the
+    // languages to detect among and an audio config.
+    pub fn from_auto_detect_source_language_config(
+        speech_config: &SpeechConfig,
+        auto_detect_config: &AutoDetectSourceLanguageConfig,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        let mut properties = speech_config.properties().clone();
+        auto_detect_config.apply(&mut properties);
+        properties.set_property_by_string(LANGUAGE_ID_ONLY, "true");
+        Ok(SourceLanguageRecognizer {
+            recognizer: SpeechRecognizer::new(properties, audio_config)?,
+        })
+    }
+
+    // authorization_token is the authorization token that will be used for connecting to the service.
+    pub fn authorization_token(&self) -> String {
+        self.recognizer.authorization_token()
+    }
+
+    // set_authorization_token sets the authorization token that will be used for connecting to the service.
+    pub fn set_authorization_token(&mut self, token: &str) {
+        self.recognizer.set_authorization_token(token);
+    }
+
+    // properties is the collection of properties and their values defined for this SourceLanguageRecognizer.
+    pub fn properties(&self) -> &PropertyCollection {
+        self.recognizer.properties()
+    }
+
+    // recognize_once detects the language of a single utterance.
+    pub async fn recognize_once(&self) -> Result<SpeechRecognitionResult> {
+        self.recognizer.recognize_once().await
+    }
+
+    // start_continuous_recognition starts detecting the language of every utterance of a continuous audio
+    // stream, until stop_continuous_recognition is called or the end of the audio is reached.
+    pub async fn start_continuous_recognition(&mut self) -> Result<RecognitionEvents> {
+        // The recognition takes its own copy of the properties when it starts, so the continuous detection
+        // is only set for it.
+        let properties = self.recognizer.properties().clone();
+        auto_detect_source_language_config::detect_continuously(self.recognizer.properties_mut());
+        let events = self.recognizer.start_continuous_recognition().await;
+        *self.recognizer.properties_mut() = properties;
+        events
+    }
+
+    // stop_continuous_recognition stops a running language detection and returns once the session has stopped.
+    pub async fn stop_continuous_recognition(&mut self) -> Result<()> {
+        self.recognizer.stop_continuous_recognition().await
+    }
+
+    // on_session_started registers a callback for events indicating the start of a recognition session.
+    pub fn on_session_started(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_started(callback);
+    }
+
+    // on_session_stopped registers a callback for events indicating the end of a recognition session.
+    pub fn on_session_stopped(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_stopped(callback);
+    }
+
+    // on_speech_start_detected registers a callback for events indicating the start of speech.
+    pub fn on_speech_start_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_start_detected(callback);
+    }
+
+    // on_speech_end_detected registers a callback for events indicating the end of speech.
+    pub fn on_speech_end_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_end_detected(callback);
+    }
+
+    // on_recognized registers a callback for events containing a detected language.
+    pub fn on_recognized(
+        &self,
+        callback: impl Fn(&SpeechRecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_recognized(callback);
+    }
+
+    // on_canceled registers a callback for events indicating that language detection was canceled.
+    pub fn on_canceled(
+        &self,
+        callback: impl Fn(&SpeechRecognitionCanceledEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_canceled(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioStreamFormat;
+    use crate::auto_detect_source_language_config::AutoDetectSourceLanguageResult;
+    use crate::speech_recognizer::RecognitionEvent;
+    use crate::test_server;
+    use crate::usp::Path;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn languages_are_detected_per_utterance_without_transcription() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            let language_id = &context.json_body().unwrap()["languageId"];
+            assert_eq!(language_id["onSuccess"]["action"], "None");
+            assert_eq!(language_id["languages"], serde_json::json!(["en-US", "fr-FR"]));
+            assert_eq!(language_id["mode"], "DetectContinuous");
+            assert_eq!(language_id["priority"], "PrioritizeLatency");
+
+            let (request_id, _) = connection.receive_audio().await;
+            connection.send_json(Path::TurnStart, &request_id, "{}").await;
+            for (language, confidence) in [("fr-FR", "High"), ("en-US", "Low")] {
+                let phrase = format!(
+                    r#"{{"RecognitionStatus":"Success","PrimaryLanguage":{{"Language":"{}","Confidence":"{}"}}}}"#,
+                    language, confidence
+                );
+                connection.send_json(Path::SpeechPhrase, &request_id, &phrase).await;
+            }
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let auto_detect =
+            AutoDetectSourceLanguageConfig::from_languages(&["en-US", "fr-FR"]).unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let mut recognizer = SourceLanguageRecognizer::from_auto_detect_source_language_config(
+            &config,
+            &auto_detect,
+            audio,
+        )
+        .unwrap();
+
+        let mut events = recognizer.start_continuous_recognition().await.unwrap();
+        let mut detected = Vec::new();
+        while let Some(event) = events.next().await {
+            if let RecognitionEvent::Recognized(args) = event {
+                assert!(args.result.text.is_empty());
+                detected.push(AutoDetectSourceLanguageResult::from_result(&args.result));
+            }
+        }
+        recognizer.stop_continuous_recognition().await.unwrap();
+        server.await.unwrap();
+        let detected: Vec<_> = detected
+            .iter()
+            .map(|result| (result.language.as_str(), result.confidence.as_str()))
+            .collect();
+        assert_eq!(detected, [("fr-FR", "High"), ("en-US", "Low")]);
+    }
+
+    #[tokio::test]
+    async fn recognize_once_detects_the_language_at_the_start_of_the_audio() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            let language_id = &context.json_body().unwrap()["languageId"];
+            assert_eq!(language_id["onSuccess"]["action"], "None");
+            assert_eq!(language_id["mode"], "DetectAtAudioStart");
+            assert_eq!(language_id["priority"], "PrioritizeAccuracy");
+
+            let (request_id, _) = connection.receive_audio().await;
+            connection.send_json(Path::TurnStart, &request_id, "{}").await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","PrimaryLanguage":{"Language":"ja-JP","Confidence":"Medium"}}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let mut config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        config.set_property(
+            crate::common::PropertyId::SpeechServiceConnectionSingleLanguageIdPriority,
+            "Accuracy",
+        );
+        let auto_detect =
+            AutoDetectSourceLanguageConfig::from_languages(&["en-US", "ja-JP"]).unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SourceLanguageRecognizer::from_auto_detect_source_language_config(
+            &config,
+            &auto_detect,
+            audio,
+        )
+        .unwrap();
+
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert!(result.text.is_empty());
+        assert_eq!(
+            AutoDetectSourceLanguageResult::from_result(&result),
+            AutoDetectSourceLanguageResult {
+                language: "ja-JP".to_string(),
+                confidence: "Medium".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use url::Url;
+
+use crate::common::{
+    OutputFormat, ProfanityOption, PropertyCollection, PropertyId, RecognitionMode,
+    ServicePropertyChannel, SpeechSynthesisOutputFormat,
+};
+use crate::error::{Error, Result};
+
+// SpeechConfig is the class that defines configurations for speech / intent recognition, or speech synthesis.
+#[derive(Debug, Clone, Default)]
+pub struct SpeechConfig {
+    properties: PropertyCollection,
+}
+
+impl SpeechConfig {
+    // from_subscription creates a speech config instance with specified subscription key and region.
+    pub fn from_subscription(subscription_key: &str, region: &str) -> Result<Self> {
+        let mut config = SpeechConfig::default();
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionKey,
+            validate_subscription_key(subscription_key)?,
+        );
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionRegion,
+            validate_region(region)?,
+        );
+        Ok(config)
+    }
+
+    // from_authorization_token creates a speech config instance with specified authorization token and region.
+    // Note: The caller needs to ensure that the authorization token is valid. Before the authorization token
+    // expires, the caller needs to refresh it by calling set_authorization_token with the new valid token.
+    pub fn from_authorization_token(authorization_token: &str, region: &str) -> Result<Self> {
+        let mut config = SpeechConfig::default();
+        config.set_authorization_token(authorization_token)?;
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionRegion,
+            validate_region(region)?,
+        );
+        Ok(config)
+    }
+
+    // from_endpoint creates a speech config instance with the specified endpoint. The authorization token
+    // must be set with set_authorization_token before the config is used.
+    // Note: The query parameters specified in the endpoint URL are not changed, even if they are set by any
+    // other APIs. For example, if the recognition language is defined in the URI as query parameter
+    // "language=de-DE", and also set by set_speech_recognition_language("en-US"), the language setting in
+    // the URI takes precedence, and the effective language is "de-DE".
+    pub fn from_endpoint(endpoint: &str) -> Result<Self> {
+        let mut config = SpeechConfig::default();
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionEndpoint,
+            parse_endpoint(endpoint)?.as_str(),
+        );
+        Ok(config)
+    }
+
+    // from_endpoint_with_subscription creates a speech config instance with the specified endpoint and
+    // subscription key. See from_endpoint for how query parameters in the endpoint are handled.
+    pub fn from_endpoint_with_subscription(endpoint: &str, subscription_key: &str) -> Result<Self> {
+        let mut config = SpeechConfig::from_endpoint(endpoint)?;
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionKey,
+            validate_subscription_key(subscription_key)?,
+        );
+        Ok(config)
+    }
+
+    // from_host creates a speech config instance with the specified host (protocol, host name and port,
+    // e.g. "wss://my.speech.host:443"). The authorization token must be set with set_authorization_token
+    // before the config is used.
+    // Note: Query parameters and paths are not allowed in the host URI. Use from_endpoint instead.
+    pub fn from_host(host: &str) -> Result<Self> {
+        let mut config = SpeechConfig::default();
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionHost,
+            parse_host(host)?.as_str(),
+        );
+        Ok(config)
+    }
+
+    // from_host_with_subscription creates a speech config instance with the specified host and subscription key.
+    pub fn from_host_with_subscription(host: &str, subscription_key: &str) -> Result<Self> {
+        let mut config = SpeechConfig::from_host(host)?;
+        config.properties.set_property(
+            PropertyId::SpeechServiceConnectionKey,
+            validate_subscription_key(subscription_key)?,
+        );
+        Ok(config)
+    }
+
+    // subscription_key is the subscription key that is used to create Speech Recognizer or Intent Recognizer
+    // or Translation Recognizer or Speech Synthesizer.
+    pub fn subscription_key(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionKey)
+    }
+
+    // region is the region key that used to create Speech Recognizer or Intent Recognizer or Translation
+    // Recognizer or Speech Synthesizer.
+    pub fn region(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionRegion)
+    }
+
+    // endpoint is the endpoint the config was created with, if any.
+    pub fn endpoint(&self) -> Option<Url> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionEndpoint)
+            .and_then(|endpoint| Url::parse(endpoint).ok())
+    }
+
+    // host is the host the config was created with, if any.
+    pub fn host(&self) -> Option<Url> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionHost)
+            .and_then(|host| Url::parse(host).ok())
+    }
+
+    // authorization_token is the authorization token to connect to the service.
+    pub fn authorization_token(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceAuthorizationToken)
+    }
+
+    // set_authorization_token sets the authorization token to connect to the service.
+    // Note: The caller needs to ensure that the authorization token is valid. Before the authorization token
+    // expires, the caller needs to refresh it by calling this setter with a new valid token.
+    pub fn set_authorization_token(&mut self, authorization_token: &str) -> Result<()> {
+        self.properties.set_property(
+            PropertyId::SpeechServiceAuthorizationToken,
+            validate_authorization_token(authorization_token)?,
+        );
+        Ok(())
+    }
+
+    // speech_recognition_language is the input language to the speech recognition.
+    // The language is specified in BCP-47 format.
+    pub fn speech_recognition_language(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionRecoLanguage)
+    }
+
+    // set_speech_recognition_language sets the input language to the speech recognizer.
+    pub fn set_speech_recognition_language(&mut self, language: &str) -> Result<()> {
+        self.properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoLanguage,
+            validate_language(language)?,
+        );
+        Ok(())
+    }
+
+    // endpoint_id is the endpoint ID of a customized speech model that is used for speech recognition.
+    pub fn endpoint_id(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionEndpointId)
+    }
+
+    // set_endpoint_id sets the endpoint ID of a customized speech model that is used for speech recognition.
+    pub fn set_endpoint_id(&mut self, endpoint_id: &str) -> Result<()> {
+        if endpoint_id.trim().is_empty() {
+            return Err(Error::InvalidArgument(
+                "endpoint id must not be empty".to_string(),
+            ));
+        }
+        self.properties
+            .set_property(PropertyId::SpeechServiceConnectionEndpointId, endpoint_id);
+        Ok(())
+    }
+
+    // output_format is the speech recognition output format (simple or detailed).
+    pub fn output_format(&self) -> OutputFormat {
+        self.properties
+            .property(PropertyId::SpeechServiceResponseOutputFormatOption)
+            .and_then(|format| format.parse().ok())
+            .unwrap_or(OutputFormat::Simple)
+    }
+
+    // set_output_format sets the speech recognition output format (simple or detailed).
+    pub fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.properties.set_property(
+            PropertyId::SpeechServiceResponseOutputFormatOption,
+            output_format.as_str(),
+        );
+        self.properties.set_property(
+            PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse,
+            (output_format == OutputFormat::Detailed).to_string(),
+        );
+    }
+
+    // request_word_level_timestamps requests word level timestamps, which are part of the detailed results.
+    pub fn request_word_level_timestamps(&mut self) {
+        self.properties.set_property(
+            PropertyId::SpeechServiceResponseRequestWordLevelTimestamps,
+            "true",
+        );
+    }
+
+    // profanity is the profanity option requested from the service, if one was set.
+    pub fn profanity(&self) -> Option<ProfanityOption> {
+        self.properties
+            .property(PropertyId::SpeechServiceResponseProfanityOption)
+            .and_then(|profanity| profanity.parse().ok())
+    }
+
+    // set_profanity sets profanity option.
+    pub fn set_profanity(&mut self, profanity: ProfanityOption) {
+        self.properties.set_property(
+            PropertyId::SpeechServiceResponseProfanityOption,
+            profanity.as_str(),
+        );
+    }
+
+    // speech_synthesis_language is the language of the speech synthesizer.
+    pub fn speech_synthesis_language(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionSynthLanguage)
+    }
+
+    // set_speech_synthesis_language sets the language of the speech synthesizer.
+    pub fn set_speech_synthesis_language(&mut self, language: &str) -> Result<()> {
+        self.properties.set_property(
+            PropertyId::SpeechServiceConnectionSynthLanguage,
+            validate_language(language)?,
+        );
+        Ok(())
+    }
+
+    // speech_synthesis_voice_name is the voice of the speech synthesizer.
+    pub fn speech_synthesis_voice_name(&self) -> Option<&str> {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionSynthVoice)
+    }
+
+    // set_speech_synthesis_voice_name sets the voice of the speech synthesizer.
+    pub fn set_speech_synthesis_voice_name(&mut self, voice_name: &str) -> Result<()> {
+        if voice_name.trim().is_empty() {
+            return Err(Error::InvalidArgument(
+                "voice name must not be empty".to_string(),
+            ));
+        }
+        self.properties
+            .set_property(PropertyId::SpeechServiceConnectionSynthVoice, voice_name);
+        Ok(())
+    }
+
+    // speech_synthesis_output_format is the audio format synthesized by the speech synthesizer.
+    pub fn speech_synthesis_output_format(&self) -> SpeechSynthesisOutputFormat {
+        self.properties
+            .property(PropertyId::SpeechServiceConnectionSynthOutputFormat)
+            .and_then(|format| format.parse().ok())
+            .unwrap_or(SpeechSynthesisOutputFormat::Riff16Khz16BitMonoPcm)
+    }
+
+    // set_speech_synthesis_output_format sets the audio format synthesized by the speech synthesizer.
+    pub fn set_speech_synthesis_output_format(&mut self, format: SpeechSynthesisOutputFormat) {
+        self.properties.set_property(
+            PropertyId::SpeechServiceConnectionSynthOutputFormat,
+            format.as_str(),
+        );
+    }
+
+    // enable_dictation enables dictation. Only supported in speech continuous recognition.
+    pub fn enable_dictation(&mut self) {
+        self.properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoMode,
+            RecognitionMode::Dictation.as_str(),
+        );
+    }
+
+    // set_service_property sets a property value that will be passed to service using the specified channel.
+    pub fn set_service_property(
+        &mut self,
+        name: &str,
+        value: &str,
+        channel: ServicePropertyChannel,
+    ) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::InvalidArgument(
+                "service property name must not be empty".to_string(),
+            ));
+        }
+        match channel {
+            ServicePropertyChannel::UriQueryParameter => {
+                let existing = self.properties.get_property(
+                    PropertyId::SpeechServiceConnectionUserDefinedQueryParameters,
+                    "",
+                );
+                let mut parameters = url::form_urlencoded::Serializer::new(String::new());
+                for (existing_name, existing_value) in
+                    url::form_urlencoded::parse(existing.as_bytes())
+                {
+                    if existing_name != name {
+                        parameters.append_pair(&existing_name, &existing_value);
+                    }
+                }
+                parameters.append_pair(name, value);
+                self.properties.set_property(
+                    PropertyId::SpeechServiceConnectionUserDefinedQueryParameters,
+                    parameters.finish(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // set_property sets a property value by ID.
+    pub fn set_property(&mut self, id: PropertyId, value: &str) {
+        self.properties.set_property(id, value);
+    }
+
+    // get_property gets a property value by ID.
+    pub fn get_property(&self, id: PropertyId) -> String {
+        self.properties.get_property(id, "")
+    }
+
+    // set_property_by_string sets a property value by name.
+    pub fn set_property_by_string(&mut self, name: &str, value: &str) {
+        self.properties.set_property_by_string(name, value);
+    }
+
+    // get_property_by_string gets a property value by name.
+    pub fn get_property_by_string(&self, name: &str) -> String {
+        self.properties.get_property_by_string(name, "")
+    }
+
+    // properties is the collection of properties backing this config.
+    pub fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+
+    pub(crate) fn properties_mut(&mut self) -> &mut PropertyCollection {
+        &mut self.properties
+    }
+}
+
+fn validate_subscription_key(subscription_key: &str) -> Result<String> {
+    if subscription_key.is_empty()
+        || subscription_key
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(Error::InvalidArgument(
+            "subscription key must be non-empty and must not contain whitespace".to_string(),
+        ));
+    }
+    Ok(subscription_key.to_string())
+}
+
+fn validate_authorization_token(authorization_token: &str) -> Result<String> {
+    if authorization_token.trim().is_empty() {
+        return Err(Error::InvalidArgument(
+            "authorization token must not be empty".to_string(),
+        ));
+    }
+    Ok(authorization_token.to_string())
+}
+
+// Region names are short identifiers such as "westus" or "chinaeast2".
+fn validate_region(region: &str) -> Result<String> {
+    if region.is_empty() || !region.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(Error::InvalidRegion(region.to_string()));
+    }
+    Ok(region.to_ascii_lowercase())
+}
+
+// Languages are BCP-47 tags such as "en-US" or "zh-Hans-CN".
+pub(crate) fn validate_language(language: &str) -> Result<String> {
+    let valid = !language.is_empty()
+        && language.split('-').all(|subtag| {
+            !subtag.is_empty()
+                && subtag.len() <= 8
+                && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if !valid {
+        return Err(Error::InvalidArgument(format!(
+            "{} is not a valid BCP-47 language tag",
+            language
+        )));
+    }
+    Ok(language.to_string())
+}
+
+fn parse_url(url: &str) -> Result<Url> {
+    let parsed = Url::parse(url).map_err(|e| Error::InvalidUrl(format!("{}: {}", url, e)))?;
+    if !matches!(parsed.scheme(), "ws" | "wss" | "http" | "https") {
+        return Err(Error::InvalidUrl(format!(
+            "{}: unsupported scheme {}",
+            url,
+            parsed.scheme()
+        )));
+    }
+    if parsed.host_str().is_none() {
+        return Err(Error::InvalidUrl(format!("{}: missing host", url)));
+    }
+    Ok(parsed)
+}
+
+fn parse_endpoint(endpoint: &str) -> Result<Url> {
+    parse_url(endpoint)
+}
+
+fn parse_host(host: &str) -> Result<Url> {
+    let parsed = parse_url(host)?;
+    if parsed.path() != "/" || parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(Error::InvalidUrl(format!(
+            "{}: query parameters and paths are not allowed in the host URI, use from_endpoint instead",
+            host
+        )));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_subscription_validates_key_and_region() {
+        let config = SpeechConfig::from_subscription("0123456789abcdef", "WestUS").unwrap();
+        assert_eq!(config.subscription_key(), Some("0123456789abcdef"));
+        assert_eq!(config.region(), Some("westus"));
+
+        assert!(matches!(
+            SpeechConfig::from_subscription("", "westus"),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            SpeechConfig::from_subscription("key", ""),
+            Err(Error::InvalidRegion(_))
+        ));
+        assert!(matches!(
+            SpeechConfig::from_subscription("key", "west us"),
+            Err(Error::InvalidRegion(_))
+        ));
+    }
+
+    #[test]
+    fn from_endpoint_and_host_validate_urls() {
+        let config = SpeechConfig::from_endpoint_with_subscription(
+            "wss://westus.stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1?language=de-DE",
+            "key",
+        )
+        .unwrap();
+        assert_eq!(config.endpoint().unwrap().query(), Some("language=de-DE"));
+        assert!(matches!(
+            SpeechConfig::from_endpoint("not a url"),
+            Err(Error::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            SpeechConfig::from_endpoint("ftp://example.com"),
+            Err(Error::InvalidUrl(_))
+        ));
+
+        assert!(SpeechConfig::from_host("wss://my.speech.host:8443").is_ok());
+        assert!(matches!(
+            SpeechConfig::from_host("wss://my.speech.host/path"),
+            Err(Error::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            SpeechConfig::from_host("wss://my.speech.host?a=b"),
+            Err(Error::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn setters_validate_values() {
+        let mut config = SpeechConfig::from_authorization_token("token", "eastus").unwrap();
+        config
+            .set_speech_recognition_language("zh-Hans-CN")
+            .unwrap();
+        assert_eq!(config.speech_recognition_language(), Some("zh-Hans-CN"));
+        assert!(config.set_speech_recognition_language("en_US").is_err());
+        assert!(config.set_speech_synthesis_voice_name(" ").is_err());
+        assert_eq!(
+            config.get_property(PropertyId::SpeechServiceConnectionRecoLanguage),
+            "zh-Hans-CN"
+        );
+
+        config.set_output_format(OutputFormat::Detailed);
+        config.set_profanity(ProfanityOption::Raw);
+        assert_eq!(config.output_format(), OutputFormat::Detailed);
+        assert_eq!(config.profanity(), Some(ProfanityOption::Raw));
+        assert_eq!(
+            config.get_property(PropertyId::SpeechServiceResponseRequestDetailedResultTrueFalse),
+            "true"
+        );
+    }
+
+    #[test]
+    fn speech_synthesis_output_format_is_stored_by_name() {
+        let mut config = SpeechConfig::from_subscription("key", "westus").unwrap();
+        assert_eq!(
+            config.speech_synthesis_output_format(),
+            SpeechSynthesisOutputFormat::Riff16Khz16BitMonoPcm
+        );
+        config.set_speech_synthesis_output_format(
+            SpeechSynthesisOutputFormat::Riff44100Hz16BitMonoPcm,
+        );
+        assert_eq!(
+            config.get_property(PropertyId::SpeechServiceConnectionSynthOutputFormat),
+            "riff-44100hz-16bit-mono-pcm"
+        );
+        assert_eq!(
+            config.speech_synthesis_output_format(),
+            SpeechSynthesisOutputFormat::Riff44100Hz16BitMonoPcm
+        );
+    }
+
+    #[test]
+    fn service_properties_are_passed_as_query_parameters() {
+        let mut config = SpeechConfig::from_subscription("key", "westus").unwrap();
+        config.enable_dictation();
+        config
+            .set_service_property(
+                "punctuation",
+                "explicit",
+                ServicePropertyChannel::UriQueryParameter,
+            )
+            .unwrap();
+        config
+            .set_service_property("a b", "c&d", ServicePropertyChannel::UriQueryParameter)
+            .unwrap();
+        config
+            .set_service_property(
+                "punctuation",
+                "implicit",
+                ServicePropertyChannel::UriQueryParameter,
+            )
+            .unwrap();
+        assert_eq!(
+            config.properties().speech_service_connection_url().unwrap().as_str(),
+            "wss://westus.stt.speech.microsoft.com/speech/recognition/dictation/cognitiveservices/v1?language=en-US&a+b=c%26d&punctuation=implicit"
+        );
+    }
+}
@@ -0,0 +1,1223 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::Stream;
+use tokio::sync::mpsc;
+
+use crate::audio::AudioConfig;
+use crate::auto_detect_source_language_config::{self, AutoDetectSourceLanguageConfig};
+use crate::common::{
+    CancellationErrorDetails, CancellationReason, PropertyCollection, PropertyId, ResultReason,
+};
+use crate::error::{Error, Result};
+use crate::events::{RecognitionEventArgs, SessionEventArgs};
+use crate::grammar::DynamicGrammar;
+use crate::recognizer::{Connection, RecognitionResult, Recognizer};
+use crate::source_language_config::SourceLanguageConfig;
+use crate::speech_config::SpeechConfig;
+use crate::usp::{self, Message, Path};
+
+// SpeechRecognizer is the class for speech recognizers.
+pub struct SpeechRecognizer {
+    recognizer: Recognizer<SpeechRecognitionResult>,
+    // grammars holds the phrases and grammars sent to the service at the start of every turn.
+    grammars: Arc<StdMutex<DynamicGrammar>>,
+}
+
+impl SpeechRecognizer {
+    // from_config creates a speech recognizer from a speech config and audio config.
+    pub fn from_config(speech_config: &SpeechConfig, audio_config: AudioConfig) -> Result<Self> {
+        SpeechRecognizer::new(speech_config.properties().clone(), audio_config)
+    }
+
+    // from_auto_detect_source_language_config creates a speech recognizer that detects the language of the
+    // audio among the languages of the auto detect source language config.
+    pub fn from_auto_detect_source_language_config(
+        speech_config: &SpeechConfig,
+        auto_detect_config: &AutoDetectSourceLanguageConfig,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        let mut properties = speech_config.properties().clone();
+        auto_detect_config.apply(&mut properties);
+        SpeechRecognizer::new(properties, audio_config)
+    }
+
+    // from_source_language_config creates a speech recognizer for the language, and custom model if any, of
+    // the source language config.
+    pub fn from_source_language_config(
+        speech_config: &SpeechConfig,
+        source_language_config: &SourceLanguageConfig,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        let mut properties = speech_config.properties().clone();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionRecoLanguage,
+            source_language_config.language(),
+        );
+        if let Some(endpoint_id) = source_language_config.endpoint_id() {
+            properties.set_property(PropertyId::SpeechServiceConnectionEndpointId, endpoint_id);
+        }
+        SpeechRecognizer::new(properties, audio_config)
+    }
+
+    pub(crate) fn new(
+        mut properties: PropertyCollection,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        audio_config.properties().copy_into(&mut properties);
+        // Fail early rather than on the first recognition.
+        properties.speech_service_connection_url()?;
+        auto_detect_source_language_config::language_id_context(&properties)?;
+        let source = audio_config.source.ok_or_else(|| {
+            Error::InvalidArgument("a recognizer requires an audio input config".to_string())
+        })?;
+        Ok(SpeechRecognizer {
+            recognizer: Recognizer::new(properties, source),
+            grammars: Arc::new(StdMutex::new(DynamicGrammar::default())),
+        })
+    }
+
+    // endpoint_id is the endpoint ID of a customized speech model that is used for speech recognition.
+    pub fn endpoint_id(&self) -> String {
+        self.properties()
+            .get_property(PropertyId::SpeechServiceConnectionEndpointId, "")
+    }
+
+    // authorization_token is the authorization token that will be used for connecting to the service.
+    pub fn authorization_token(&self) -> String {
+        self.properties()
+            .get_property(PropertyId::SpeechServiceAuthorizationToken, "")
+    }
+
+    // set_authorization_token sets the authorization token that will be used for connecting to the service.
+    // Note: The caller needs to ensure that the authorization token is valid. Before the authorization token
+    // expires, the caller needs to refresh it by calling this setter with a new valid token.
+    // Otherwise, the recognizer will encounter errors during recognition.
+    pub fn set_authorization_token(&mut self, token: &str) {
+        self.recognizer
+            .properties_mut()
+            .set_property(PropertyId::SpeechServiceAuthorizationToken, token);
+    }
+
+    // properties is the collection of properties and their values defined for this SpeechRecognizer.
+    pub fn properties(&self) -> &PropertyCollection {
+        self.recognizer.properties()
+    }
+
+    pub(crate) fn properties_mut(&mut self) -> &mut PropertyCollection {
+        self.recognizer.properties_mut()
+    }
+
+    pub(crate) fn grammars(&self) -> Arc<StdMutex<DynamicGrammar>> {
+        self.grammars.clone()
+    }
+
+    // recognize_once starts speech recognition, and returns after a single utterance is recognized.
+    // The end of a single utterance is determined by listening for silence at the end or until a maximum
+    // of 15 seconds of audio is processed. Failures to reach or talk to the service are reported as a
+    // result with reason Canceled.
+    pub async fn recognize_once(&self) -> Result<SpeechRecognitionResult> {
+        self.recognizer.recognize_once(self.connect()).await
+    }
+
+    // start_continuous_recognition starts speech recognition on a continuous audio stream, until
+    // stop_continuous_recognition is called or the end of the audio is reached. Recognition events are
+    // delivered both through the returned stream and to the registered callbacks.
+    pub async fn start_continuous_recognition(&mut self) -> Result<RecognitionEvents> {
+        let connect = self.connect();
+        self.recognizer.start_continuous_recognition(connect).await
+    }
+
+    // stop_continuous_recognition stops a running recognition operation as soon as possible. The audio
+    // sent so far is still recognized: pending results are delivered before the session stopped event.
+    // This function returns once the session has stopped.
+    pub async fn stop_continuous_recognition(&mut self) -> Result<()> {
+        self.recognizer.stop_continuous_recognition().await
+    }
+
+    // connect returns how a recognition connects to the service: the phrases and grammars are sent at the
+    // start of every turn, along with the languages to detect among, if any.
+    fn connect(
+        &self,
+    ) -> impl FnOnce(&mut PropertyCollection) -> Result<Connection> + Send + 'static {
+        let grammars = self.grammars.clone();
+        move |properties| {
+            let url = properties.speech_service_connection_url()?;
+            let language_id = auto_detect_source_language_config::language_id_context(properties)?;
+            Ok(Connection {
+                url,
+                speech_context: Box::new(move || {
+                    let mut context = grammars.lock().unwrap().speech_context();
+                    if let Some(serde_json::Value::Object(language_id)) = &language_id {
+                        let context = context.get_or_insert_with(|| serde_json::json!({}));
+                        for (key, value) in language_id {
+                            context[key] = value.clone();
+                        }
+                    }
+                    context
+                }),
+            })
+        }
+    }
+
+    // on_session_started registers a callback for events indicating the start of a recognition session.
+    pub fn on_session_started(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_started(callback);
+    }
+
+    // on_session_stopped registers a callback for events indicating the end of a recognition session.
+    pub fn on_session_stopped(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_stopped(callback);
+    }
+
+    // on_speech_start_detected registers a callback for events indicating the start of speech.
+    pub fn on_speech_start_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_start_detected(callback);
+    }
+
+    // on_speech_end_detected registers a callback for events indicating the end of speech.
+    pub fn on_speech_end_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_end_detected(callback);
+    }
+
+    // on_recognizing registers a callback for events containing intermediate recognition results.
+    pub fn on_recognizing(
+        &self,
+        callback: impl Fn(&SpeechRecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_recognizing(callback);
+    }
+
+    // on_recognized registers a callback for events containing final recognition results (indicating a
+    // successful recognition attempt).
+    pub fn on_recognized(
+        &self,
+        callback: impl Fn(&SpeechRecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_recognized(callback);
+    }
+
+    // on_canceled registers a callback for events containing canceled recognition results (indicating a
+    // recognition attempt that was canceled as a result or a direct cancellation request or,
+    // alternatively, a transport or protocol failure).
+    pub fn on_canceled(
+        &self,
+        callback: impl Fn(&SpeechRecognitionCanceledEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_canceled(callback);
+    }
+}
+
+// RecognitionEvent is an event raised by a recognizer during recognition. R is the type of the results of the
+// recognizer.
+#[derive(Debug, Clone)]
+pub enum RecognitionEvent<R = SpeechRecognitionResult> {
+    // SessionStarted signals the start of a recognition session.
+    SessionStarted(SessionEventArgs),
+
+    // SessionStopped signals the end of a recognition session. It is the last event of a session.
+    SessionStopped(SessionEventArgs),
+
+    // SpeechStartDetected signals that the service detected the start of speech.
+    SpeechStartDetected(RecognitionEventArgs),
+
+    // SpeechEndDetected signals that the service detected the end of speech.
+    SpeechEndDetected(RecognitionEventArgs),
+
+    // Recognizing carries an intermediate recognition result.
+    Recognizing(SpeechRecognitionEventArgs<R>),
+
+    // Recognized carries a final recognition result.
+    Recognized(SpeechRecognitionEventArgs<R>),
+
+    // Canceled signals that recognition was canceled, because of an error or the end of the audio.
+    Canceled(SpeechRecognitionCanceledEventArgs<R>),
+}
+
+// RecognitionEvents is the stream of events of a continuous recognition. The stream ends after the
+// SessionStopped event.
+pub struct RecognitionEvents<R = SpeechRecognitionResult> {
+    pub(crate) receiver: mpsc::UnboundedReceiver<RecognitionEvent<R>>,
+}
+
+impl<R> Stream for RecognitionEvents<R> {
+    type Item = RecognitionEvent<R>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+// SpeechRecognitionEventArgs represents the speech recognition event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechRecognitionEventArgs<R = SpeechRecognitionResult> {
+    // session_id is the session identifier.
+    pub session_id: String,
+
+    // offset of the recognition event from the start of the audio.
+    pub offset: Duration,
+
+    // result represents the speech recognition result.
+    pub result: R,
+}
+
+// SpeechRecognitionCanceledEventArgs represents speech recognition canceled event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechRecognitionCanceledEventArgs<R = SpeechRecognitionResult> {
+    // session_id is the session identifier.
+    pub session_id: String,
+
+    // result represents the canceled speech recognition result.
+    pub result: R,
+
+    // reason specifies the reason canceled.
+    pub reason: CancellationReason,
+
+    // error_details presents error details.
+    pub error_details: String,
+}
+
+// SpeechRecognitionResult contains detailed information about result of a recognition operation.
+#[derive(Debug, Clone)]
+pub struct SpeechRecognitionResult {
+    // result_id specifies the result identifier.
+    pub result_id: String,
+
+    // reason specifies status of speech recognition result.
+    pub reason: ResultReason,
+
+    // text presents the recognized text in the result.
+    pub text: String,
+
+    // offset of the recognized speech from the start of the audio.
+    pub offset: Duration,
+
+    // duration of the recognized speech.
+    pub duration: Duration,
+
+    // n_best presents the alternatives of a detailed result, best first. It is empty unless the detailed output
+    // format was requested.
+    pub n_best: Vec<DetailedSpeechRecognitionResult>,
+
+    // properties is the collection of properties and their values defined for this result. The result as
+    // returned by the service is available as SpeechServiceResponseJsonResult.
+    pub properties: PropertyCollection,
+}
+
+impl SpeechRecognitionResult {
+    pub(crate) fn new(reason: ResultReason) -> Self {
+        SpeechRecognitionResult {
+            result_id: usp::request_id(),
+            reason,
+            text: String::new(),
+            offset: Duration::ZERO,
+            duration: Duration::ZERO,
+            n_best: Vec::new(),
+            properties: PropertyCollection::new(),
+        }
+    }
+
+    pub(crate) fn canceled(details: &CancellationErrorDetails) -> Self {
+        let mut result = SpeechRecognitionResult::new(ResultReason::Canceled);
+        result.set_cancellation_details(details);
+        result
+    }
+
+    fn set_cancellation_details(&mut self, details: &CancellationErrorDetails) {
+        self.properties
+            .set_property(PropertyId::CancellationDetailsReason, "Error");
+        self.properties
+            .set_property(PropertyId::CancellationDetailsReasonText, &details.reason);
+        self.properties.set_property(
+            PropertyId::CancellationDetailsReasonDetailedText,
+            &details.detailed_text,
+        );
+    }
+
+    // set_detected_language stores the language detected by the service, if any, in the properties of the
+    // result, from where AutoDetectSourceLanguageResult reads it.
+    fn set_detected_language(&mut self, json: &serde_json::Value) {
+        if let Some(language) = json["PrimaryLanguage"]["Language"].as_str() {
+            self.properties.set_property(
+                PropertyId::SpeechServiceConnectionAutoDetectSourceLanguageResult,
+                language,
+            );
+        }
+    }
+
+    // from_hypothesis builds an intermediate result from a speech.hypothesis message.
+    pub(crate) fn from_hypothesis(message: &Message, session_id: &str) -> Result<Self> {
+        let json = message.json_body()?;
+        let mut result = SpeechRecognitionResult::new(ResultReason::RecognizingSpeech);
+        result.text = json["Text"].as_str().unwrap_or_default().to_string();
+        result.offset = ticks(&json["Offset"]);
+        result.duration = ticks(&json["Duration"]);
+        result.set_detected_language(&json);
+        result.properties.set_property(
+            PropertyId::SpeechServiceResponseJsonResult,
+            message.text_body().unwrap_or_default(),
+        );
+        result
+            .properties
+            .set_property(PropertyId::SpeechSessionId, session_id);
+        Ok(result)
+    }
+
+    // from_phrase builds a result from a speech.phrase message. None is returned for phrases that do not
+    // carry a result, such as the end of dictation marker.
+    pub(crate) fn from_phrase(message: &Message, session_id: &str) -> Result<Option<Self>> {
+        let json = message.json_body()?;
+        let status = json["RecognitionStatus"].as_str().unwrap_or_default();
+        let reason = match status {
+            "Success" => ResultReason::RecognizedSpeech,
+            "NoMatch" | "InitialSilenceTimeout" | "BabbleTimeout" => ResultReason::NoMatch,
+            "EndOfDictation" => return Ok(None),
+            _ => ResultReason::Canceled,
+        };
+        let mut result = SpeechRecognitionResult::new(reason);
+        result.text = json["DisplayText"]
+            .as_str()
+            .or_else(|| json["NBest"][0]["Display"].as_str())
+            // Translation phrases carry the recognized text as Text.
+            .or_else(|| json["Text"].as_str())
+            .unwrap_or_default()
+            .to_string();
+        result.offset = ticks(&json["Offset"]);
+        result.duration = ticks(&json["Duration"]);
+        result.n_best = json["NBest"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(DetailedSpeechRecognitionResult::from_json)
+            .collect();
+        result.set_detected_language(&json);
+        result.properties.set_property(
+            PropertyId::SpeechServiceResponseJsonResult,
+            message.text_body().unwrap_or_default(),
+        );
+        result
+            .properties
+            .set_property(PropertyId::SpeechSessionId, session_id);
+        if reason == ResultReason::Canceled {
+            let message = json["Message"].as_str().unwrap_or_default();
+            result.set_cancellation_details(&CancellationErrorDetails::new(status, message));
+        }
+        Ok(Some(result))
+    }
+}
+
+impl RecognitionResult for SpeechRecognitionResult {
+    const HYPOTHESIS: Path = Path::SpeechHypothesis;
+    const PHRASE: Path = Path::SpeechPhrase;
+
+    fn from_reason(reason: ResultReason) -> Self {
+        SpeechRecognitionResult::new(reason)
+    }
+
+    fn from_cancellation(details: &CancellationErrorDetails) -> Self {
+        SpeechRecognitionResult::canceled(details)
+    }
+
+    fn from_hypothesis(message: &Message, session_id: &str) -> Result<Self> {
+        SpeechRecognitionResult::from_hypothesis(message, session_id)
+    }
+
+    fn from_phrase(message: &Message, session_id: &str) -> Result<Option<Self>> {
+        SpeechRecognitionResult::from_phrase(message, session_id)
+    }
+
+    fn reason(&self) -> ResultReason {
+        self.reason
+    }
+
+    fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+}
+
+// DetailedSpeechRecognitionResult is one of the alternatives of a detailed recognition result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailedSpeechRecognitionResult {
+    // confidence is the confidence of the service in the alternative, from 0 to 1.
+    pub confidence: f64,
+
+    // lexical is the lexical form of the recognized text, the actual words recognized.
+    pub lexical: String,
+
+    // itn is the inverse text normalized form of the recognized text, where phone numbers, numbers, abbreviations
+    // and other transformations are applied.
+    pub itn: String,
+
+    // masked_itn is the inverse text normalized form with profanity masking applied.
+    pub masked_itn: String,
+
+    // display is the display form of the recognized text, with punctuation and capitalization added.
+    pub display: String,
+
+    // words presents the timing of each word of the lexical form. It is only set when word level timestamps
+    // were requested.
+    pub words: Vec<WordLevelTimingResult>,
+}
+
+impl DetailedSpeechRecognitionResult {
+    fn from_json(json: &serde_json::Value) -> Self {
+        let text = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+        DetailedSpeechRecognitionResult {
+            confidence: json["Confidence"].as_f64().unwrap_or_default(),
+            lexical: text("Lexical"),
+            itn: text("ITN"),
+            masked_itn: text("MaskedITN"),
+            display: text("Display"),
+            words: json["Words"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|word| WordLevelTimingResult {
+                    word: word["Word"].as_str().unwrap_or_default().to_string(),
+                    offset: ticks(&word["Offset"]),
+                    duration: ticks(&word["Duration"]),
+                    confidence: word["Confidence"].as_f64(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// WordLevelTimingResult is the timing of a recognized word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordLevelTimingResult {
+    // word is the recognized word.
+    pub word: String,
+
+    // offset of the word from the start of the audio.
+    pub offset: Duration,
+
+    // duration of the word.
+    pub duration: Duration,
+
+    // confidence is the confidence of the service in the word, if the service reported one.
+    pub confidence: Option<f64>,
+}
+
+// ticks converts a service time value, in units of 100 nanoseconds, into a duration.
+pub(crate) fn ticks(value: &serde_json::Value) -> Duration {
+    Duration::from_nanos(value.as_u64().unwrap_or_default().saturating_mul(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioStreamFormat, PushAudioInputStream};
+    use crate::auto_detect_source_language_config::AutoDetectSourceLanguageResult;
+    use crate::common::CancellationError;
+    use crate::test_server;
+    use futures_util::StreamExt;
+    use tokio::sync::oneshot;
+
+    #[test]
+    fn detailed_phrases_are_parsed_into_n_best() {
+        let body = serde_json::json!({
+            "RecognitionStatus": "Success",
+            "Offset": 500000,
+            "Duration": 15000000,
+            "NBest": [
+                {
+                    "Confidence": 0.93,
+                    "Lexical": "call five five five one two three four",
+                    "ITN": "call 555-1234",
+                    "MaskedITN": "call 555-1234",
+                    "Display": "Call 555-1234.",
+                    "Words": [
+                        {"Word": "call", "Offset": 500000, "Duration": 2000000},
+                        {"Word": "five", "Offset": 2600000, "Duration": 1500000, "Confidence": 0.8}
+                    ]
+                },
+                {"Confidence": 0.41, "Lexical": "cole", "ITN": "cole", "MaskedITN": "cole", "Display": "Cole."}
+            ]
+        });
+        let message = Message::json(Path::SpeechPhrase, "request", &body);
+        let result = SpeechRecognitionResult::from_phrase(&message, "session")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.reason, ResultReason::RecognizedSpeech);
+        assert_eq!(result.text, "Call 555-1234.");
+        assert_eq!(result.n_best.len(), 2);
+        let best = &result.n_best[0];
+        assert_eq!(best.confidence, 0.93);
+        assert_eq!(best.lexical, "call five five five one two three four");
+        assert_eq!(best.itn, "call 555-1234");
+        assert_eq!(best.masked_itn, "call 555-1234");
+        assert_eq!(
+            best.words[1],
+            WordLevelTimingResult {
+                word: "five".to_string(),
+                offset: Duration::from_millis(260),
+                duration: Duration::from_millis(150),
+                confidence: Some(0.8),
+            }
+        );
+        assert_eq!(best.words[0].confidence, None);
+        assert!(result.n_best[1].words.is_empty());
+        assert_eq!(
+            result
+                .properties
+                .get_property(PropertyId::SpeechServiceResponseJsonResult, ""),
+            body.to_string()
+        );
+
+        let simple = Message::json(
+            Path::SpeechPhrase,
+            "request",
+            &serde_json::json!({"RecognitionStatus": "Success", "DisplayText": "Hi.", "Offset": 0, "Duration": 0}),
+        );
+        let result = SpeechRecognitionResult::from_phrase(&simple, "session")
+            .unwrap()
+            .unwrap();
+        assert!(result.n_best.is_empty());
+    }
+
+    #[tokio::test]
+    async fn recognize_once_against_stand_in_service() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            assert_eq!(connection.header("Ocp-Apim-Subscription-Key"), Some("key".to_string()));
+            assert!(connection.query().contains("language=en-US"));
+
+            let config = connection.receive().await.unwrap();
+            assert_eq!(config.path(), Path::SpeechConfig);
+            assert_eq!(config.json_body().unwrap()["context"]["audio"]["source"]["samplerate"], 16000);
+
+            let (request_id, audio) = connection.receive_audio().await;
+            assert_eq!(&audio[..4], b"RIFF");
+            assert_eq!(audio.len(), 44 + 32000);
+
+            connection.send_json(Path::TurnStart, &request_id, r#"{"context":{"serviceTag":"tag"}}"#).await;
+            connection.send_json(Path::SpeechStartDetected, &request_id, r#"{"Offset":1000000}"#).await;
+            connection
+                .send_json(Path::SpeechHypothesis, &request_id, r#"{"Text":"hello","Offset":1000000,"Duration":5000000}"#)
+                .await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Hello world.","Offset":1000000,"Duration":12000000}"#,
+                )
+                .await;
+            connection.send_json(Path::SpeechEndDetected, &request_id, r#"{"Offset":13000000}"#).await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let mut config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        config.set_speech_recognition_language("en-US").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 32000][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::RecognizedSpeech);
+        assert_eq!(result.text, "Hello world.");
+        assert_eq!(result.offset, Duration::from_millis(100));
+        assert_eq!(result.duration, Duration::from_millis(1200));
+        assert!(result
+            .properties
+            .get_property(PropertyId::SpeechServiceResponseJsonResult, "")
+            .contains("Hello world."));
+    }
+
+    #[tokio::test]
+    async fn detected_source_language_is_reported_with_the_result() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            assert_eq!(context.path(), Path::SpeechContext);
+            let language_id = &context.json_body().unwrap()["languageId"];
+            assert_eq!(language_id["languages"], serde_json::json!(["en-US", "de-DE"]));
+            assert_eq!(language_id["mode"], "DetectAtAudioStart");
+
+            let (request_id, _) = connection.receive_audio().await;
+            connection.send_json(Path::TurnStart, &request_id, "{}").await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Hallo.","PrimaryLanguage":{"Language":"de-DE","Confidence":"High"}}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let auto_detect =
+            AutoDetectSourceLanguageConfig::from_languages(&["en-US", "de-DE"]).unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer =
+            SpeechRecognizer::from_auto_detect_source_language_config(&config, &auto_detect, audio)
+                .unwrap();
+
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.text, "Hallo.");
+        assert_eq!(
+            AutoDetectSourceLanguageResult::from_result(&result).language,
+            "de-DE"
+        );
+    }
+
+    #[tokio::test]
+    async fn recognize_once_reports_no_match() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let (request_id, _) = connection.receive_audio().await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"InitialSilenceTimeout","Offset":0,"Duration":0}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::NoMatch);
+    }
+
+    #[tokio::test]
+    async fn recognize_once_reports_connection_failure_as_canceled() {
+        let config =
+            SpeechConfig::from_endpoint_with_subscription("ws://127.0.0.1:1/speech", "key")
+                .unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let result = recognizer.recognize_once().await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+    }
+
+    #[tokio::test]
+    async fn continuous_recognition_streams_events_until_end_of_audio() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            let config = connection.receive().await.unwrap();
+            assert_eq!(config.json_body().unwrap()["recognition"], "conversation");
+
+            let (request_id, _) = connection.receive_audio().await;
+            connection
+                .send_json(Path::SpeechStartDetected, &request_id, r#"{"Offset":0}"#)
+                .await;
+            connection
+                .send_json(
+                    Path::SpeechHypothesis,
+                    &request_id,
+                    r#"{"Text":"hello","Offset":0,"Duration":5000000}"#,
+                )
+                .await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Hello.","Offset":0,"Duration":6000000}"#,
+                )
+                .await;
+            connection
+                .send_json(Path::SpeechEndDetected, &request_id, r#"{"Offset":6000000}"#)
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 6400][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let recognized = Arc::new(StdMutex::new(Vec::new()));
+        let texts = recognized.clone();
+        recognizer
+            .on_recognized(move |event| texts.lock().unwrap().push(event.result.text.clone()));
+
+        let events: Vec<_> = recognizer
+            .start_continuous_recognition()
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        server.await.unwrap();
+        recognizer.stop_continuous_recognition().await.unwrap();
+
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                RecognitionEvent::SessionStarted(_) => "SessionStarted",
+                RecognitionEvent::SessionStopped(_) => "SessionStopped",
+                RecognitionEvent::SpeechStartDetected(_) => "SpeechStartDetected",
+                RecognitionEvent::SpeechEndDetected(_) => "SpeechEndDetected",
+                RecognitionEvent::Recognizing(_) => "Recognizing",
+                RecognitionEvent::Recognized(_) => "Recognized",
+                RecognitionEvent::Canceled(_) => "Canceled",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "SessionStarted",
+                "SpeechStartDetected",
+                "Recognizing",
+                "Recognized",
+                "SpeechEndDetected",
+                "Canceled",
+                "SessionStopped"
+            ]
+        );
+        match &events[5] {
+            RecognitionEvent::Canceled(args) => {
+                assert_eq!(args.reason, CancellationReason::EndOfStream)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert_eq!(*recognized.lock().unwrap(), ["Hello."]);
+    }
+
+    #[tokio::test]
+    async fn stop_continuous_recognition_flushes_pending_results() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let request_id = loop {
+                let message = connection.receive().await.unwrap();
+                if message.path() == Path::Audio {
+                    break message.request_id().unwrap().to_string();
+                }
+            };
+            connection
+                .send_json(
+                    Path::SpeechHypothesis,
+                    &request_id,
+                    r#"{"Text":"pending","Offset":0,"Duration":1000000}"#,
+                )
+                .await;
+            // The client ends the audio when recognition is stopped.
+            connection.receive_audio().await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Pending.","Offset":0,"Duration":1000000}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let (mut writer, reader) = tokio::io::duplex(64 * 1024);
+        tokio::io::AsyncWriteExt::write_all(&mut writer, &[0u8; 3200])
+            .await
+            .unwrap();
+        let audio = AudioConfig::from_reader(reader, AudioStreamFormat::get_default_input_format());
+        let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+
+        let mut events = recognizer.start_continuous_recognition().await.unwrap();
+        loop {
+            match events.next().await.unwrap() {
+                RecognitionEvent::Recognizing(args) => {
+                    assert_eq!(args.result.text, "pending");
+                    break;
+                }
+                RecognitionEvent::Canceled(args) => panic!("canceled: {}", args.error_details),
+                _ => {}
+            }
+        }
+        recognizer.stop_continuous_recognition().await.unwrap();
+        server.await.unwrap();
+
+        let rest: Vec<_> = events.collect().await;
+        assert!(
+            matches!(&rest[0], RecognitionEvent::Recognized(args) if args.result.text == "Pending.")
+        );
+        assert!(matches!(
+            rest.last(),
+            Some(RecognitionEvent::SessionStopped(_))
+        ));
+        assert_eq!(rest.len(), 2);
+        drop(writer);
+    }
+
+    #[tokio::test]
+    async fn callbacks_can_register_callbacks() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            let (request_id, _) = connection.receive_audio().await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Hello.","Offset":0,"Duration":6000000}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = Arc::new(SpeechRecognizer::from_config(&config, audio).unwrap());
+        let recognized = Arc::new(StdMutex::new(Vec::new()));
+        let (weak, texts) = (Arc::downgrade(&recognizer), recognized.clone());
+        // Callbacks are called without holding the lock they are registered under.
+        recognizer.on_session_started(move |_| {
+            let texts = texts.clone();
+            weak.upgrade().unwrap().on_recognized(move |event| {
+                texts.lock().unwrap().push(event.result.text.clone());
+            });
+        });
+
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.text, "Hello.");
+        assert_eq!(*recognized.lock().unwrap(), ["Hello."]);
+    }
+
+    #[tokio::test]
+    async fn phrase_list_changes_are_sent_on_the_next_turn() {
+        let (resume, resumed) = oneshot::channel::<()>();
+        let (checked, second_turn) = oneshot::channel::<()>();
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let phrases = |context: Message| -> Vec<String> {
+                assert_eq!(context.path(), Path::SpeechContext);
+                let json = context.json_body().unwrap();
+                json["dgi"]["Groups"][0]["Items"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|item| item["Text"].as_str().unwrap().to_string())
+                    .collect()
+            };
+            let context = connection.receive().await.unwrap();
+            let request_id = context.request_id().unwrap().to_string();
+            assert_eq!(phrases(context), ["Jessie"]);
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Hi Jessie.","Offset":0,"Duration":1000000}"#,
+                )
+                .await;
+            resumed.await.unwrap();
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+
+            let context = loop {
+                let message = connection.receive().await.unwrap();
+                if message.path() == Path::SpeechContext {
+                    break message;
+                }
+            };
+            assert_eq!(phrases(context), ["Jessie", "Contoso"]);
+            checked.send(()).unwrap();
+            let (request_id, _) = connection.receive_audio().await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let (writer, reader) = tokio::io::duplex(64 * 1024);
+        let audio = AudioConfig::from_reader(reader, AudioStreamFormat::get_default_input_format());
+        let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let phrase_list = crate::grammar::PhraseListGrammar::from_recognizer(&recognizer);
+        phrase_list.add_phrase("Jessie").unwrap();
+        assert!(matches!(
+            phrase_list.add_phrase(" "),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let mut events = recognizer.start_continuous_recognition().await.unwrap();
+        loop {
+            match events.next().await.unwrap() {
+                RecognitionEvent::Recognized(_) => break,
+                RecognitionEvent::Canceled(args) => panic!("canceled: {}", args.error_details),
+                _ => {}
+            }
+        }
+        phrase_list.add_phrase("Contoso").unwrap();
+        resume.send(()).unwrap();
+        // Stopping ends the audio of the second turn.
+        second_turn.await.unwrap();
+        recognizer.stop_continuous_recognition().await.unwrap();
+        server.await.unwrap();
+        drop(writer);
+
+        phrase_list.clear();
+        assert_eq!(recognizer.grammars().lock().unwrap().speech_context(), None);
+    }
+
+    #[tokio::test]
+    async fn grammar_list_is_sent_in_the_speech_context_of_a_turn() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            assert_eq!(context.path(), Path::SpeechContext);
+            let dgi = &context.json_body().unwrap()["dgi"];
+            assert_eq!(dgi["Groups"][0]["Items"], serde_json::json!([{ "Text": "Contoso" }]));
+            assert_eq!(dgi["ReferenceGrammars"], serde_json::json!(["products"]));
+            assert_eq!(
+                dgi["ClassModels"],
+                serde_json::json!([{
+                    "ReferenceGrammar": "orders-clm",
+                    "Classes": { "ProductName": "products" },
+                }])
+            );
+            assert_eq!(
+                dgi["Bias"],
+                serde_json::json!({ "RecognitionFactor": 1.5, "Scope": "PartialPhrase" })
+            );
+
+            let (request_id, _) = connection.receive_audio().await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","DisplayText":"Two Contoso mugs.","Offset":0,"Duration":1000000}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let phrase_list = crate::grammar::PhraseListGrammar::from_recognizer(&recognizer);
+        phrase_list.add_phrase("Contoso").unwrap();
+        let grammar_list = crate::grammar::GrammarList::from_recognizer(&recognizer);
+        let products = crate::grammar::Grammar::from_storage_id("products").unwrap();
+        let mut model = crate::grammar::ClassLanguageModel::from_storage_id("orders-clm").unwrap();
+        model.assign_class("ProductName", &products).unwrap();
+        grammar_list.add_grammar(products);
+        grammar_list.add_grammar(model);
+        grammar_list
+            .set_recognition_factor(1.5, crate::grammar::RecognitionFactorScope::PartialPhrase)
+            .unwrap();
+
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::RecognizedSpeech);
+        assert_eq!(result.text, "Two Contoso mugs.");
+    }
+
+    #[tokio::test]
+    async fn start_continuous_recognition_twice_is_rejected() {
+        let config =
+            SpeechConfig::from_endpoint_with_subscription("ws://127.0.0.1:1/speech", "key")
+                .unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        recognizer.start_continuous_recognition().await.unwrap();
+        assert!(matches!(
+            recognizer.start_continuous_recognition().await,
+            Err(Error::InvalidState(_))
+        ));
+        assert!(matches!(
+            recognizer.recognize_once().await,
+            Err(Error::InvalidState(_))
+        ));
+        recognizer.stop_continuous_recognition().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn recognition_starts_again_after_a_session_ended_by_itself() {
+        let config =
+            SpeechConfig::from_endpoint_with_subscription("ws://127.0.0.1:1/speech", "key")
+                .unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        for _ in 0..2 {
+            // The connection fails, which ends the session without stop_continuous_recognition.
+            let events: Vec<_> = recognizer
+                .start_continuous_recognition()
+                .await
+                .unwrap()
+                .collect()
+                .await;
+            assert!(matches!(events[..], [RecognitionEvent::Canceled(_)]));
+        }
+        let result = recognizer.recognize_once().await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+        recognizer.stop_continuous_recognition().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_handshakes_are_reported_with_typed_cancellation_errors() {
+        let cases = [
+            (401, "", CancellationError::AuthenticationFailure),
+            (
+                403,
+                r#"{"error":{"code":"QuotaExceeded","message":"Out of call volume quota."}}"#,
+                CancellationError::Forbidden,
+            ),
+            (429, "", CancellationError::TooManyRequests),
+            (400, "", CancellationError::BadRequest),
+        ];
+        for (status, body, expected) in cases {
+            let url = test_server::reject(status, body).await;
+            let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+            let audio = AudioConfig::from_reader(
+                &[0u8; 3200][..],
+                AudioStreamFormat::get_default_input_format(),
+            );
+            let mut recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+            let events: Vec<_> = recognizer
+                .start_continuous_recognition()
+                .await
+                .unwrap()
+                .collect()
+                .await;
+            let Some(RecognitionEvent::Canceled(args)) = events.first() else {
+                panic!("unexpected events {:?}", events);
+            };
+            let CancellationReason::Error(error, details) = &args.reason else {
+                panic!("unexpected reason {:?}", args.reason);
+            };
+            assert_eq!(*error, expected, "HTTP {}", status);
+            assert_eq!(args.result.reason, ResultReason::Canceled);
+            if status == 403 {
+                assert_eq!(details.reason, "QuotaExceeded");
+                assert_eq!(details.detailed_text, "Out of call volume quota.");
+                assert_eq!(
+                    args.result
+                        .properties
+                        .get_property(PropertyId::CancellationDetailsReasonText, ""),
+                    "QuotaExceeded"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_recognition_status_cancels_recognition() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let (request_id, _) = connection.receive_audio().await;
+            connection
+                .send_json(
+                    Path::SpeechPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"TooManyRequests","Offset":0,"Duration":0}"#,
+                )
+                .await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let audio = AudioConfig::from_reader(
+            &[0u8; 3200][..],
+            AudioStreamFormat::get_default_input_format(),
+        );
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+        let result = recognizer.recognize_once().await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+        assert_eq!(
+            result
+                .properties
+                .get_property(PropertyId::CancellationDetailsReasonText, ""),
+            "TooManyRequests"
+        );
+    }
+
+    #[tokio::test]
+    async fn push_stream_sends_per_buffer_properties_as_audio_headers() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let mut audio = Vec::new();
+            loop {
+                let message = connection.receive().await.unwrap();
+                if message.path() != Path::Audio {
+                    continue;
+                }
+                let data = message.binary_body().unwrap();
+                if data.is_empty() {
+                    let request_id = message.request_id().unwrap().to_string();
+                    connection.send_json(Path::TurnEnd, &request_id, "").await;
+                    break;
+                }
+                audio.push((
+                    data.len(),
+                    message.header(usp::HEADER_PTS).map(str::to_string),
+                    message.header(usp::HEADER_SPEAKER_ID).map(str::to_string),
+                ));
+            }
+            audio
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let stream = PushAudioInputStream::create_push_stream();
+        let audio = AudioConfig::from_stream_input(stream.clone()).unwrap();
+        let recognizer = SpeechRecognizer::from_config(&config, audio).unwrap();
+
+        stream
+            .set_property(PropertyId::DataBufferTimeStamp, "5000")
+            .unwrap();
+        stream
+            .set_property(PropertyId::DataBufferUserId, "agent")
+            .unwrap();
+        stream.write(&[0; 320]).await.unwrap();
+        stream.write(&[0; 320]).await.unwrap();
+        stream.close().await;
+
+        let result = recognizer.recognize_once().await.unwrap();
+        assert_eq!(result.reason, ResultReason::NoMatch);
+        let audio = server.await.unwrap();
+        assert_eq!(
+            audio,
+            [
+                (
+                    44 + 320,
+                    Some("5000".to_string()),
+                    Some("agent".to_string())
+                ),
+                (320, None, None)
+            ]
+        );
+    }
+}
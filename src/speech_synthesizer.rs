@@ -0,0 +1,1783 @@
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use tokio::sync::{oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::audio::{
+    AudioConfig, AudioDataStream, AudioDataWriter, AudioStreamContainerFormat, AudioStreamFormat,
+    OutputSink,
+};
+use crate::common::{
+    CancellationError, CancellationErrorDetails, CancellationReason, PropertyCollection,
+    PropertyId, ResultReason, SpeechSynthesisBoundaryType, SpeechSynthesisOutputCodec,
+    SpeechSynthesisOutputContainer, SpeechSynthesisOutputFormat,
+};
+use crate::connection::{self, ConnectionReceiver, ConnectionSender};
+use crate::error::{Error, Result};
+use crate::session;
+use crate::speech_config::SpeechConfig;
+use crate::speech_recognizer::ticks;
+use crate::ssml::{escape_xml, Speak};
+use crate::usp::{self, Message, Path};
+use crate::voice_info::{SynthesisVoicesResult, VoiceInfo};
+
+// DEFAULT_OUTPUT_FORMAT is the audio format synthesized when SpeechServiceConnectionSynthOutputFormat is not set.
+pub(crate) const DEFAULT_OUTPUT_FORMAT: SpeechSynthesisOutputFormat =
+    SpeechSynthesisOutputFormat::Riff16Khz16BitMonoPcm;
+
+// DEFAULT_LANGUAGE is the language plain text is spoken in when SpeechServiceConnectionSynthLanguage is not set.
+const DEFAULT_LANGUAGE: &str = "en-US";
+
+// SpeechSynthesizer is the class for speech synthesizer.
+pub struct SpeechSynthesizer {
+    properties: PropertyCollection,
+    connection: Arc<Mutex<Option<SynthesisConnection>>>,
+    // queue orders the requests, in the order they were made.
+    queue: Queue,
+    stop: watch::Sender<u64>,
+    callbacks: Arc<StdMutex<Callbacks>>,
+    // output is the audio output config the audio is written to, if any.
+    output: Arc<Mutex<Option<OutputSink>>>,
+    // voices is the voices retrieved by get_voices_list, used to validate SSML documents.
+    voices: StdMutex<Vec<VoiceInfo>>,
+}
+
+impl SpeechSynthesizer {
+    // from_config creates a speech synthesizer from a speech config.
+    pub fn from_config(speech_config: &SpeechConfig) -> Result<Self> {
+        SpeechSynthesizer::new(speech_config, None)
+    }
+
+    // from_config_with_audio creates a speech synthesizer that also writes the synthesized audio to an audio
+    // output config, such as a wave file or an output stream.
+    pub fn from_config_with_audio(
+        speech_config: &SpeechConfig,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        let sink = audio_config.sink.ok_or_else(|| {
+            Error::InvalidArgument("a synthesizer requires an audio output config".to_string())
+        })?;
+        SpeechSynthesizer::new(speech_config, Some(sink))
+    }
+
+    fn new(speech_config: &SpeechConfig, mut sink: Option<OutputSink>) -> Result<Self> {
+        let properties = speech_config.properties().clone();
+        // Fail early rather than on the first synthesis.
+        properties.speech_synthesis_connection_url()?;
+        if let Some(sink) = sink.as_mut() {
+            sink.begin(&OutputFormat::from_properties(&properties).format)?;
+        }
+        Ok(SpeechSynthesizer {
+            properties,
+            connection: Arc::new(Mutex::new(None)),
+            queue: Queue::default(),
+            stop: watch::channel(0).0,
+            callbacks: Arc::new(StdMutex::new(Callbacks::default())),
+            output: Arc::new(Mutex::new(sink)),
+            voices: StdMutex::new(Vec::new()),
+        })
+    }
+
+    // authorization_token is the authorization token that will be used for connecting to the service.
+    pub fn authorization_token(&self) -> String {
+        self.properties
+            .get_property(PropertyId::SpeechServiceAuthorizationToken, "")
+    }
+
+    // set_authorization_token sets the authorization token that will be used for connecting to the service.
+    // Note: The caller needs to ensure that the authorization token is valid. Before the authorization token
+    // expires, the caller needs to refresh it by calling this setter with a new valid token.
+    // Otherwise, the synthesizer will encounter errors during synthesis.
+    pub fn set_authorization_token(&mut self, token: &str) {
+        self.properties
+            .set_property(PropertyId::SpeechServiceAuthorizationToken, token);
+    }
+
+    // properties is the collection of properties and their values defined for this SpeechSynthesizer.
+    pub fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+
+    // speak_text synthesizes plain text to speech, in the voice and language configured in the speech config,
+    // and returns once the synthesis has completed. Failures to reach or talk to the service are reported as
+    // a result with reason Canceled.
+    pub async fn speak_text(&self, text: &str) -> Result<SpeechSynthesisResult> {
+        Ok(self.request().speak(Input::Text(text.to_string())).await)
+    }
+
+    // speak_ssml synthesizes a SSML document to speech and returns once the synthesis has completed.
+    pub async fn speak_ssml(&self, ssml: &str) -> Result<SpeechSynthesisResult> {
+        Ok(self.request().speak(Input::Ssml(ssml.to_string())).await)
+    }
+
+    // speak_ssml_document validates a SSML document and synthesizes it to speech. Once get_voices_list has
+    // retrieved voices, documents using other voices are rejected before anything is sent to the service.
+    pub async fn speak_ssml_document(&self, document: &Speak) -> Result<SpeechSynthesisResult> {
+        let ssml = document.build_with_voices(&self.voices.lock().unwrap())?;
+        self.speak_ssml(&ssml).await
+    }
+
+    // speak_text_async starts synthesizing plain text to speech and returns a handle to the result. Requests
+    // are synthesized one after another, in the order they were made.
+    pub fn speak_text_async(&self, text: &str) -> JoinHandle<Result<SpeechSynthesisResult>> {
+        let input = Input::Text(text.to_string());
+        let request = self.request();
+        tokio::spawn(async move { Ok(request.speak(input).await) })
+    }
+
+    // speak_ssml_async starts synthesizing a SSML document to speech and returns a handle to the result.
+    pub fn speak_ssml_async(&self, ssml: &str) -> JoinHandle<Result<SpeechSynthesisResult>> {
+        let input = Input::Ssml(ssml.to_string());
+        let request = self.request();
+        tokio::spawn(async move { Ok(request.speak(input).await) })
+    }
+
+    // start_speaking_text starts synthesizing plain text to speech and returns as soon as the first audio has
+    // arrived, with a result of reason SynthesizingAudioStarted. AudioDataStream::from_result gives the audio
+    // of the result, which keeps filling while the service is still sending. A request that ends before any
+    // audio arrived returns its final result.
+    pub async fn start_speaking_text(&self, text: &str) -> Result<SpeechSynthesisResult> {
+        self.request().start(Input::Text(text.to_string())).await
+    }
+
+    // start_speaking_ssml starts synthesizing a SSML document to speech and returns as soon as the first audio
+    // has arrived. See start_speaking_text.
+    pub async fn start_speaking_ssml(&self, ssml: &str) -> Result<SpeechSynthesisResult> {
+        self.request().start(Input::Ssml(ssml.to_string())).await
+    }
+
+    // stop_speaking stops the synthesis in progress and the requests waiting for it. Their results have the
+    // reason Canceled with CancellationReason::CancelledByUser. This function returns once they have stopped.
+    pub async fn stop_speaking(&self) -> Result<()> {
+        self.stop.send_modify(|generation| *generation += 1);
+        // Requests waiting for their turn stop right away, the one in progress once it releases the connection.
+        self.queue.join().turn().await;
+        drop(self.connection.lock().await);
+        Ok(())
+    }
+
+    // close closes the audio output config once the synthesis in progress has completed: a wave file gets its
+    // final header, output streams are closed. Audio synthesized afterwards is only returned in the results.
+    pub async fn close(&self) -> Result<()> {
+        let sink = self.output.lock().await.take();
+        match sink {
+            Some(sink) => sink.close(),
+            None => Ok(()),
+        }
+    }
+
+    // get_voices_list gets the voices available for synthesis in locale, or all voices if locale is empty.
+    // Failures to reach the service are reported as a result with reason Canceled.
+    pub async fn get_voices_list(&self, locale: &str) -> Result<SynthesisVoicesResult> {
+        let result_id = usp::request_id();
+        let voices = fetch_voices_list(&self.properties)
+            .await
+            .and_then(|body| SynthesisVoicesResult::from_voices_list(&result_id, &body));
+        let mut result =
+            voices.unwrap_or_else(|err| SynthesisVoicesResult::canceled(&result_id, err));
+        // Every voice returned is cached, not only those of locale, so that documents in other locales
+        // still validate.
+        {
+            let mut cached = self.voices.lock().unwrap();
+            for voice in &result.voices {
+                if !cached
+                    .iter()
+                    .any(|v| v.name == voice.name && v.short_name == voice.short_name)
+                {
+                    cached.push(voice.clone());
+                }
+            }
+        }
+        result
+            .voices
+            .retain(|voice| locale.is_empty() || voice.locale.eq_ignore_ascii_case(locale));
+        Ok(result)
+    }
+
+    // on_synthesis_started registers a callback for events indicating synthesis started.
+    pub fn on_synthesis_started(
+        &self,
+        callback: impl Fn(&SpeechSynthesisEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().synthesis_started = Some(Arc::new(callback));
+    }
+
+    // on_synthesizing registers a callback for events indicating synthesis is ongoing. The result of the event
+    // carries the chunk of audio that was received.
+    pub fn on_synthesizing(
+        &self,
+        callback: impl Fn(&SpeechSynthesisEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().synthesizing = Some(Arc::new(callback));
+    }
+
+    // on_synthesis_completed registers a callback for events indicating synthesis completed.
+    pub fn on_synthesis_completed(
+        &self,
+        callback: impl Fn(&SpeechSynthesisEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().synthesis_completed = Some(Arc::new(callback));
+    }
+
+    // on_synthesis_canceled registers a callback for events indicating synthesis was canceled.
+    pub fn on_synthesis_canceled(
+        &self,
+        callback: impl Fn(&SpeechSynthesisEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().synthesis_canceled = Some(Arc::new(callback));
+    }
+
+    // on_word_boundary registers a callback for word boundary events. Registering it requests word boundaries
+    // from the service; punctuation and sentence boundaries are controlled by the
+    // SpeechServiceResponseRequestPunctuationBoundary and SpeechServiceResponseRequestSentenceBoundary
+    // properties.
+    pub fn on_word_boundary(
+        &self,
+        callback: impl Fn(&SpeechSynthesisWordBoundaryEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().word_boundary = Some(Arc::new(callback));
+    }
+
+    // on_viseme_received registers a callback for viseme events, used for lip-sync. Visemes are only requested
+    // from the service while a callback is registered; blend shape animations are requested in the SSML with
+    // <mstts:viseme type="FacialExpression"/>.
+    pub fn on_viseme_received(
+        &self,
+        callback: impl Fn(&SpeechSynthesisVisemeEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().viseme_received = Some(Arc::new(callback));
+    }
+
+    // on_bookmark_reached registers a callback for events indicating a <bookmark> element of the SSML was
+    // reached.
+    pub fn on_bookmark_reached(
+        &self,
+        callback: impl Fn(&SpeechSynthesisBookmarkEventArgs) + Send + Sync + 'static,
+    ) {
+        self.callbacks.lock().unwrap().bookmark_reached = Some(Arc::new(callback));
+    }
+
+    fn request(&self) -> Request {
+        Request {
+            properties: self.properties.clone(),
+            connection: self.connection.clone(),
+            ticket: self.queue.join(),
+            stop: self.stop.subscribe(),
+            generation: *self.stop.borrow(),
+            callbacks: self.callbacks.clone(),
+            output: self.output.clone(),
+            first_audio: None,
+        }
+    }
+}
+
+// Request is a single speak request. It captures the synthesizer state it needs, so that it can outlive the call
+// that made it.
+struct Request {
+    properties: PropertyCollection,
+    connection: Arc<Mutex<Option<SynthesisConnection>>>,
+    // ticket is the place of the request in the queue, taken when the request was made.
+    ticket: Ticket,
+    stop: watch::Receiver<u64>,
+    // generation is the number of times stop_speaking was called before the request was made.
+    generation: u64,
+    callbacks: Arc<StdMutex<Callbacks>>,
+    output: Arc<Mutex<Option<OutputSink>>>,
+    // first_audio receives the started result once the first audio has arrived.
+    first_audio: Option<oneshot::Sender<SpeechSynthesisResult>>,
+}
+
+impl Request {
+    // start runs the request in the background and waits for its first audio.
+    async fn start(mut self, input: Input) -> Result<SpeechSynthesisResult> {
+        let (first_audio, started) = oneshot::channel();
+        self.first_audio = Some(first_audio);
+        let speaking = tokio::spawn(self.speak(input));
+        match started.await {
+            Ok(result) => Ok(result),
+            // The request ended before any audio arrived.
+            Err(_) => speaking
+                .await
+                .map_err(|e| Error::RuntimeError(format!("synthesis task failed: {}", e))),
+        }
+    }
+
+    async fn speak(self, input: Input) -> SpeechSynthesisResult {
+        let stop = stopped(self.stop.clone(), self.generation);
+        tokio::pin!(stop);
+
+        let ssml = match &input {
+            Input::Text(text) => text_to_ssml(&self.properties, text),
+            Input::Ssml(ssml) => ssml.clone(),
+        };
+        let options = MetadataOptions::new(&self.properties, &self.callbacks.lock().unwrap());
+        let output = OutputFormat::from_properties(&self.properties);
+        let (stream, writer) = AudioDataStream::new(output.format.clone());
+        let mut synthesis = Synthesis {
+            request_id: usp::request_id(),
+            output,
+            stream,
+            audio: Vec::new(),
+            first_byte_latency: None,
+            playback: Playback::default(),
+            callbacks: self.callbacks.clone(),
+            first_audio: self.first_audio,
+            boundaries: Boundaries::new(input, options),
+        };
+
+        let mut ticket = self.ticket;
+        tokio::select! {
+            _ = &mut stop => return synthesis.cancel(writer, CancellationReason::CancelledByUser),
+            _ = ticket.turn() => {}
+        }
+        let mut connection = self.connection.lock().await;
+        // Only requests holding the connection write to the output, so this waits for close at most.
+        let mut output = self.output.lock().await;
+        let outcome = tokio::select! {
+            _ = &mut stop => None,
+            outcome = synthesis.run(&mut connection, &mut output, &self.properties, &ssml, &writer) => {
+                Some(outcome)
+            }
+        };
+        if let Some(output) = output.as_mut().filter(|_| !matches!(outcome, Some(Ok(_)))) {
+            // The audio received before the request failed is kept.
+            let _ = output.flush();
+        }
+        match outcome {
+            Some(Ok(finish_latency)) => synthesis.complete(writer, finish_latency),
+            Some(Err(err)) => {
+                // The connection is in an unknown state; the next request opens a new one.
+                *connection = None;
+                let (error, details) = err.cancellation();
+                synthesis.cancel(writer, CancellationReason::Error(error, details))
+            }
+            None => {
+                // The service is still sending the audio of the stopped request.
+                *connection = None;
+                synthesis.cancel(writer, CancellationReason::CancelledByUser)
+            }
+        }
+    }
+}
+
+// Queue hands out tickets to the requests of a synthesizer, so that they are synthesized one after another in
+// the order they were made, even when their tasks start in a different order.
+#[derive(Default)]
+struct Queue {
+    // last is notified once the request that joined last has ended.
+    last: StdMutex<Option<oneshot::Receiver<()>>>,
+}
+
+impl Queue {
+    // join takes the next place in the queue.
+    fn join(&self) -> Ticket {
+        let (done, last) = oneshot::channel();
+        let previous = self.last.lock().unwrap().replace(last);
+        Ticket {
+            previous,
+            _done: done,
+        }
+    }
+}
+
+// Ticket is a place in the queue. Dropping it lets the next request take its turn.
+struct Ticket {
+    previous: Option<oneshot::Receiver<()>>,
+    _done: oneshot::Sender<()>,
+}
+
+impl Ticket {
+    // turn waits until the request that joined the queue before has ended.
+    async fn turn(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            // The sender is dropped, never used, when the previous request ends.
+            let _ = previous.await;
+        }
+    }
+}
+
+// stopped waits until stop_speaking is called after the request identified by generation was made.
+async fn stopped(mut stop: watch::Receiver<u64>, generation: u64) {
+    if stop
+        .wait_for(|current| *current != generation)
+        .await
+        .is_err()
+    {
+        // The synthesizer was dropped; requests already made still complete.
+        std::future::pending::<()>().await;
+    }
+}
+
+// Synthesis tracks the audio received for a speak request.
+struct Synthesis {
+    request_id: String,
+    output: OutputFormat,
+    stream: AudioDataStream,
+    audio: Vec<u8>,
+    first_byte_latency: Option<Duration>,
+    playback: Playback,
+    callbacks: Arc<StdMutex<Callbacks>>,
+    first_audio: Option<oneshot::Sender<SpeechSynthesisResult>>,
+    boundaries: Boundaries,
+}
+
+impl Synthesis {
+    // run sends the request over connection, opening it if needed, and receives the audio until the service
+    // ends the turn. It returns the time from sending the request to the end of the turn.
+    async fn run(
+        &mut self,
+        connection: &mut Option<SynthesisConnection>,
+        output: &mut Option<OutputSink>,
+        properties: &PropertyCollection,
+        ssml: &str,
+        writer: &AudioDataWriter,
+    ) -> Result<Duration> {
+        let connection = match connection {
+            Some(connection) => connection,
+            None => connection.insert(SynthesisConnection::connect(properties).await?),
+        };
+        connection
+            .sender
+            .send(&Message::json(
+                Path::SynthesisContext,
+                &self.request_id,
+                &synthesis_context(&self.output, &self.boundaries.options),
+            ))
+            .await?;
+        connection
+            .sender
+            .send(&Message::text(
+                Path::Ssml,
+                &self.request_id,
+                usp::CONTENT_TYPE_SSML,
+                ssml,
+            ))
+            .await?;
+        let sent = Instant::now();
+        self.emit(ResultReason::SynthesizingAudioStarted, Vec::new());
+
+        loop {
+            let message =
+                connection.receiver.receive().await?.ok_or_else(|| {
+                    Error::Connection("connection closed by the service".to_string())
+                })?;
+            let current = message
+                .request_id()
+                .is_some_and(|id| id.eq_ignore_ascii_case(&self.request_id));
+            if !current {
+                continue;
+            }
+            match message.path() {
+                Path::Audio => {
+                    let chunk = message.binary_body().unwrap_or_default();
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    self.first_byte_latency
+                        .get_or_insert_with(|| sent.elapsed());
+                    self.playback
+                        .receive(Instant::now(), self.output.duration(chunk.len()));
+                    writer.write(chunk);
+                    if let Some(output) = output.as_mut() {
+                        output.write(chunk)?;
+                    }
+                    if let Some(first_audio) = self.first_audio.take() {
+                        let _ = first_audio.send(SpeechSynthesisResult::new(
+                            &self.request_id,
+                            ResultReason::SynthesizingAudioStarted,
+                            Vec::new(),
+                            self.stream.clone(),
+                        ));
+                    }
+                    self.audio.extend_from_slice(chunk);
+                    self.emit(ResultReason::SynthesizingAudio, chunk.to_vec());
+                }
+                Path::AudioMetadata => self.handle_metadata(&message)?,
+                Path::TurnEnd => {
+                    if let Some(output) = output.as_mut() {
+                        output.flush()?;
+                    }
+                    return Ok(sent.elapsed());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // handle_metadata raises the events for the entries of an audio.metadata message.
+    fn handle_metadata(&mut self, message: &Message) -> Result<()> {
+        let json = message.json_body()?;
+        for entry in json["Metadata"].as_array().into_iter().flatten() {
+            let data = &entry["Data"];
+            match entry["Type"].as_str().unwrap_or_default() {
+                "WordBoundary" | "SentenceBoundary" => {
+                    let Some(args) = self.boundaries.locate(&self.request_id, data) else {
+                        continue;
+                    };
+                    let callback = self.callbacks.lock().unwrap().word_boundary.clone();
+                    if let Some(callback) = callback {
+                        callback(&args);
+                    }
+                }
+                "Viseme" => {
+                    let args = SpeechSynthesisVisemeEventArgs {
+                        result_id: self.request_id.clone(),
+                        audio_offset: ticks(&data["Offset"]),
+                        viseme_id: data["VisemeId"].as_u64().unwrap_or_default() as u32,
+                        animation: data["AnimationChunk"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    };
+                    let callback = self.callbacks.lock().unwrap().viseme_received.clone();
+                    if let Some(callback) = callback {
+                        callback(&args);
+                    }
+                }
+                "Bookmark" => {
+                    let args = SpeechSynthesisBookmarkEventArgs {
+                        result_id: self.request_id.clone(),
+                        audio_offset: ticks(&data["Offset"]),
+                        text: data["Bookmark"].as_str().unwrap_or_default().to_string(),
+                    };
+                    let callback = self.callbacks.lock().unwrap().bookmark_reached.clone();
+                    if let Some(callback) = callback {
+                        callback(&args);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn complete(self, writer: AudioDataWriter, finish_latency: Duration) -> SpeechSynthesisResult {
+        let first_byte_latency = self.first_byte_latency.unwrap_or(finish_latency);
+        let mut result = self.result(ResultReason::SynthesisCompleted);
+        result.properties.set_property(
+            PropertyId::SpeechServiceResponseSynthesisFirstByteLatencyMs,
+            first_byte_latency.as_millis().to_string(),
+        );
+        result.properties.set_property(
+            PropertyId::SpeechServiceResponseSynthesisFinishLatencyMs,
+            finish_latency.as_millis().to_string(),
+        );
+        // The playing time of compressed audio is unknown, so is the underrun time.
+        if self.output.has_fixed_bit_rate() {
+            result.properties.set_property(
+                PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs,
+                self.playback.underrun.as_millis().to_string(),
+            );
+        }
+        for id in [
+            PropertyId::SpeechServiceResponseSynthesisFirstByteLatencyMs,
+            PropertyId::SpeechServiceResponseSynthesisFinishLatencyMs,
+            PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs,
+        ] {
+            if let Some(value) = result.properties.property(id) {
+                writer.set_property(id, value);
+            }
+        }
+        writer.finish();
+        self.dispatch(&result);
+        result
+    }
+
+    fn cancel(self, writer: AudioDataWriter, reason: CancellationReason) -> SpeechSynthesisResult {
+        let mut result = self.result(ResultReason::Canceled);
+        let (error, details) = match &reason {
+            CancellationReason::Error(error, details) => {
+                result
+                    .properties
+                    .set_property(PropertyId::CancellationDetailsReason, "Error");
+                (*error, details.clone())
+            }
+            _ => {
+                result
+                    .properties
+                    .set_property(PropertyId::CancellationDetailsReason, "CancelledByUser");
+                (
+                    CancellationError::RuntimeError,
+                    CancellationErrorDetails::new("CancelledByUser", "speaking was stopped"),
+                )
+            }
+        };
+        result
+            .properties
+            .set_property(PropertyId::CancellationDetailsReasonText, &details.reason);
+        result.properties.set_property(
+            PropertyId::CancellationDetailsReasonDetailedText,
+            &details.detailed_text,
+        );
+        result.cancellation = Some(reason);
+        writer.cancel(error, details);
+        self.dispatch(&result);
+        result
+    }
+
+    // result builds the final result of the request; wave formats are handed out with their header.
+    fn result(&self, reason: ResultReason) -> SpeechSynthesisResult {
+        let mut result =
+            SpeechSynthesisResult::new(&self.request_id, reason, Vec::new(), self.stream.clone());
+        result.audio_duration = self.output.duration(self.audio.len());
+        if self.output.riff {
+            let length = u32::try_from(self.audio.len()).unwrap_or(u32::MAX);
+            result.audio_data = self.output.format.wave_file_header(length);
+        }
+        result.audio_data.extend_from_slice(&self.audio);
+        result
+    }
+
+    fn emit(&self, reason: ResultReason, audio_data: Vec<u8>) {
+        let result =
+            SpeechSynthesisResult::new(&self.request_id, reason, audio_data, self.stream.clone());
+        self.dispatch(&result);
+    }
+
+    // dispatch calls the callback registered for the reason of result. Callbacks are cloned out of the lock
+    // before they are called: a callback may register callbacks itself, and a panicking callback must not
+    // poison the lock.
+    fn dispatch(&self, result: &SpeechSynthesisResult) {
+        let callback = self.callbacks.lock().unwrap().synthesis(result.reason);
+        if let Some(callback) = callback {
+            callback(&SpeechSynthesisEventArgs {
+                result: result.clone(),
+            });
+        }
+    }
+}
+
+// SynthesisConnection is a connection to the text to speech service. It is kept open between requests.
+struct SynthesisConnection {
+    sender: ConnectionSender,
+    receiver: ConnectionReceiver,
+}
+
+impl SynthesisConnection {
+    // connect opens a connection to the text to speech service and sends the speech.config message describing
+    // the client.
+    async fn connect(properties: &PropertyCollection) -> Result<Self> {
+        let url = properties.speech_synthesis_connection_url()?;
+        let connection_id = usp::request_id();
+        let (mut sender, receiver) = connection::connect(&url, properties, &connection_id).await?;
+        sender
+            .send(&Message::json(
+                Path::SpeechConfig,
+                &connection_id,
+                &json!({ "context": session::client_context() }),
+            ))
+            .await?;
+        Ok(SynthesisConnection { sender, receiver })
+    }
+}
+
+// fetch_voices_list gets the voices list from the voices list REST api.
+async fn fetch_voices_list(properties: &PropertyCollection) -> Result<String> {
+    let url = properties.voices_list_url()?;
+    let mut request = reqwest::Client::new().get(url.as_str());
+    if let Some(key) = properties.property(PropertyId::SpeechServiceConnectionKey) {
+        request = request.header(connection::HEADER_SUBSCRIPTION_KEY, key);
+    }
+    if let Some(token) = properties.property(PropertyId::SpeechServiceAuthorizationToken) {
+        request = request.bearer_auth(token);
+    }
+    let failed = |e: reqwest::Error| {
+        Error::Connection(format!("failed to get the voices list from {}: {}", url, e))
+    };
+    let response = request.send().await.map_err(failed)?;
+    let status = response.status();
+    let body = response.text().await.map_err(failed)?;
+    if !status.is_success() {
+        let reason = status.canonical_reason().unwrap_or("HTTP error");
+        return Err(Error::Canceled(
+            CancellationError::from_http_status(status.as_u16()),
+            CancellationErrorDetails::from_service_payload(reason, &body),
+        ));
+    }
+    Ok(body)
+}
+
+fn synthesis_context(output: &OutputFormat, options: &MetadataOptions) -> serde_json::Value {
+    json!({
+        "synthesis": {
+            "audio": {
+                "metadataOptions": {
+                    "bookmarkEnabled": options.bookmark,
+                    "punctuationBoundaryEnabled": options.punctuation_boundary,
+                    "sentenceBoundaryEnabled": options.sentence_boundary,
+                    "wordBoundaryEnabled": options.word_boundary,
+                    "visemeEnabled": options.viseme,
+                    "sessionEndEnabled": true,
+                },
+                "outputFormat": output.name,
+            },
+            "language": {
+                "autoDetection": false,
+            },
+        },
+    })
+}
+
+// Input is the text of a speak request, as given by the caller.
+enum Input {
+    Text(String),
+    Ssml(String),
+}
+
+// MetadataOptions selects the audio.metadata events the service sends for a request.
+struct MetadataOptions {
+    word_boundary: bool,
+    punctuation_boundary: bool,
+    sentence_boundary: bool,
+    viseme: bool,
+    bookmark: bool,
+}
+
+impl MetadataOptions {
+    fn new(properties: &PropertyCollection, callbacks: &Callbacks) -> Self {
+        MetadataOptions {
+            word_boundary: callbacks.word_boundary.is_some()
+                || properties.get_bool(PropertyId::SpeechServiceResponseRequestWordBoundary, false),
+            punctuation_boundary: properties.get_bool(
+                PropertyId::SpeechServiceResponseRequestPunctuationBoundary,
+                true,
+            ),
+            sentence_boundary: properties.get_bool(
+                PropertyId::SpeechServiceResponseRequestSentenceBoundary,
+                false,
+            ),
+            viseme: callbacks.viseme_received.is_some(),
+            bookmark: callbacks.bookmark_reached.is_some(),
+        }
+    }
+
+    fn boundary_enabled(&self, boundary_type: SpeechSynthesisBoundaryType) -> bool {
+        match boundary_type {
+            SpeechSynthesisBoundaryType::WordBoundary => self.word_boundary,
+            SpeechSynthesisBoundaryType::PunctuationBoundary => {
+                self.word_boundary && self.punctuation_boundary
+            }
+            SpeechSynthesisBoundaryType::SentenceBoundary => self.sentence_boundary,
+        }
+    }
+}
+
+// Boundaries locates the boundaries reported by the service in the text of the request. Words and
+// punctuation are searched for after the previous word, sentences after the previous sentence.
+struct Boundaries {
+    input: Input,
+    options: MetadataOptions,
+    word_offset: usize,
+    sentence_offset: usize,
+}
+
+impl Boundaries {
+    fn new(input: Input, options: MetadataOptions) -> Self {
+        Boundaries {
+            input,
+            options,
+            word_offset: 0,
+            sentence_offset: 0,
+        }
+    }
+
+    // locate builds the event for the Data of a boundary entry, or None if the boundary type was not requested.
+    fn locate(
+        &mut self,
+        result_id: &str,
+        data: &serde_json::Value,
+    ) -> Option<SpeechSynthesisWordBoundaryEventArgs> {
+        let text = data["text"]["Text"].as_str().unwrap_or_default();
+        let boundary_type = match data["text"]["BoundaryType"].as_str() {
+            Some("PunctuationBoundary") => SpeechSynthesisBoundaryType::PunctuationBoundary,
+            Some("SentenceBoundary") => SpeechSynthesisBoundaryType::SentenceBoundary,
+            _ => SpeechSynthesisBoundaryType::WordBoundary,
+        };
+        if !self.options.boundary_enabled(boundary_type) {
+            return None;
+        }
+        let (input, ssml) = match &self.input {
+            Input::Text(text) => (text.as_str(), false),
+            Input::Ssml(ssml) => (ssml.as_str(), true),
+        };
+        let cursor = match boundary_type {
+            SpeechSynthesisBoundaryType::SentenceBoundary => &mut self.sentence_offset,
+            _ => &mut self.word_offset,
+        };
+        let text_offset = match find_text(input, ssml, *cursor, text) {
+            Some(offset) => {
+                *cursor = offset + text.len();
+                u32::try_from(offset).unwrap_or(u32::MAX)
+            }
+            None => u32::MAX,
+        };
+        Some(SpeechSynthesisWordBoundaryEventArgs {
+            result_id: result_id.to_string(),
+            audio_offset: ticks(&data["Offset"]),
+            duration: ticks(&data["Duration"]),
+            text_offset,
+            word_length: text.len() as u32,
+            text: text.to_string(),
+            boundary_type,
+        })
+    }
+}
+
+// find_text finds text in input, starting at from. In SSML, matches within markup are skipped.
+fn find_text(input: &str, ssml: bool, from: usize, text: &str) -> Option<usize> {
+    if text.is_empty() {
+        return None;
+    }
+    input
+        .get(from..)?
+        .match_indices(text)
+        .map(|(offset, _)| from + offset)
+        .find(|&offset| !ssml || !in_markup(&input[..offset]))
+}
+
+// in_markup reports whether the end of text is within a tag.
+fn in_markup(text: &str) -> bool {
+    match (text.rfind('<'), text.rfind('>')) {
+        (Some(open), Some(close)) => open > close,
+        (open, _) => open.is_some(),
+    }
+}
+
+// text_to_ssml wraps plain text into a SSML document using the configured language and voice.
+fn text_to_ssml(properties: &PropertyCollection, text: &str) -> String {
+    let language = properties
+        .property(PropertyId::SpeechServiceConnectionSynthLanguage)
+        .unwrap_or(DEFAULT_LANGUAGE);
+    let mut ssml = format!(
+        "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' \
+         xmlns:mstts='http://www.w3.org/2001/mstts' xml:lang='{}'>",
+        escape_xml(language)
+    );
+    match properties.property(PropertyId::SpeechServiceConnectionSynthVoice) {
+        Some(voice) => ssml.push_str(&format!(
+            "<voice name='{}'>{}</voice>",
+            escape_xml(voice),
+            escape_xml(text)
+        )),
+        None => ssml.push_str(&escape_xml(text)),
+    }
+    ssml.push_str("</speak>");
+    ssml
+}
+
+// OutputFormat is the audio format of a speak request.
+struct OutputFormat {
+    // name is the format requested from the service. Wave formats are requested as raw audio, so that the
+    // audio can be streamed; the header is added to the result.
+    name: String,
+    format: AudioStreamFormat,
+    riff: bool,
+}
+
+impl OutputFormat {
+    // from_properties reads SpeechServiceConnectionSynthOutputFormat. Formats not known to the sdk are passed to
+    // the service as they are.
+    fn from_properties(properties: &PropertyCollection) -> Self {
+        let Some(name) = properties.property(PropertyId::SpeechServiceConnectionSynthOutputFormat)
+        else {
+            return OutputFormat::new(DEFAULT_OUTPUT_FORMAT);
+        };
+        match name.parse() {
+            Ok(output) => OutputFormat::new(output),
+            Err(_) => OutputFormat {
+                name: name.to_string(),
+                format: AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::Any),
+                riff: false,
+            },
+        }
+    }
+
+    fn new(output: SpeechSynthesisOutputFormat) -> Self {
+        let compressed = |container| Some(AudioStreamFormat::get_compressed_format(container));
+        let format = match (output.container(), output.codec()) {
+            (_, SpeechSynthesisOutputCodec::Pcm) => AudioStreamFormat::get_wave_format_pcm(
+                output.samples_per_second(),
+                output.bits_per_sample() as u8,
+                1,
+            )
+            .ok(),
+            (_, SpeechSynthesisOutputCodec::MuLaw) => compressed(AudioStreamContainerFormat::MuLaw),
+            (_, SpeechSynthesisOutputCodec::ALaw) => compressed(AudioStreamContainerFormat::ALaw),
+            (_, SpeechSynthesisOutputCodec::Mp3) => compressed(AudioStreamContainerFormat::Mp3),
+            (SpeechSynthesisOutputContainer::Ogg, SpeechSynthesisOutputCodec::Opus) => {
+                compressed(AudioStreamContainerFormat::OggOpus)
+            }
+            _ => None,
+        };
+        match format {
+            // Uncompressed audio can be requested without the header.
+            Some(format)
+                if matches!(
+                    format.container_format(),
+                    None | Some(
+                        AudioStreamContainerFormat::ALaw | AudioStreamContainerFormat::MuLaw
+                    )
+                ) =>
+            {
+                let riff = output.container() == SpeechSynthesisOutputContainer::Riff;
+                OutputFormat {
+                    name: output.as_str().replacen("riff-", "raw-", 1),
+                    format,
+                    riff,
+                }
+            }
+            format => OutputFormat {
+                name: output.as_str().to_string(),
+                format: format.unwrap_or_else(|| {
+                    AudioStreamFormat::get_compressed_format(AudioStreamContainerFormat::Any)
+                }),
+                riff: false,
+            },
+        }
+    }
+
+    // has_fixed_bit_rate reports whether the playing time of the audio follows from its length: PCM and
+    // a-law/mu-law are, compressed formats are not.
+    fn has_fixed_bit_rate(&self) -> bool {
+        matches!(
+            self.format.container_format(),
+            None | Some(AudioStreamContainerFormat::ALaw | AudioStreamContainerFormat::MuLaw)
+        )
+    }
+
+    // duration is the playing time of length bytes of audio, if the format has a fixed bit rate.
+    fn duration(&self, length: usize) -> Duration {
+        if !self.has_fixed_bit_rate() {
+            return Duration::ZERO;
+        }
+        let bytes_per_second = u64::from(self.format.average_bytes_per_second()).max(1);
+        Duration::from_micros(length as u64 * 1_000_000 / bytes_per_second)
+    }
+}
+
+// Playback follows the playing of the audio in real time, from the arrival of the first chunk on, to measure
+// how long playback waits for audio that has not arrived yet (the underrun time).
+#[derive(Debug, Default)]
+struct Playback {
+    // end is when the audio received so far has finished playing.
+    end: Option<Instant>,
+    underrun: Duration,
+}
+
+impl Playback {
+    // receive adds a chunk of audio with the given playing time, arrived at now.
+    fn receive(&mut self, now: Instant, duration: Duration) {
+        let start = match self.end {
+            Some(end) if end < now => {
+                self.underrun += now - end;
+                now
+            }
+            Some(end) => end,
+            None => now,
+        };
+        self.end = Some(start + duration);
+    }
+}
+
+type Callback<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+#[derive(Default)]
+struct Callbacks {
+    synthesis_started: Option<Callback<SpeechSynthesisEventArgs>>,
+    synthesizing: Option<Callback<SpeechSynthesisEventArgs>>,
+    synthesis_completed: Option<Callback<SpeechSynthesisEventArgs>>,
+    synthesis_canceled: Option<Callback<SpeechSynthesisEventArgs>>,
+    word_boundary: Option<Callback<SpeechSynthesisWordBoundaryEventArgs>>,
+    viseme_received: Option<Callback<SpeechSynthesisVisemeEventArgs>>,
+    bookmark_reached: Option<Callback<SpeechSynthesisBookmarkEventArgs>>,
+}
+
+impl Callbacks {
+    // synthesis is the callback registered for the synthesis events with reason, if any.
+    fn synthesis(&self, reason: ResultReason) -> Option<Callback<SpeechSynthesisEventArgs>> {
+        match reason {
+            ResultReason::SynthesizingAudioStarted => self.synthesis_started.clone(),
+            ResultReason::SynthesizingAudio => self.synthesizing.clone(),
+            ResultReason::SynthesisCompleted => self.synthesis_completed.clone(),
+            ResultReason::Canceled => self.synthesis_canceled.clone(),
+            _ => None,
+        }
+    }
+}
+
+// SpeechSynthesisEventArgs represents the speech synthesis event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechSynthesisEventArgs {
+    // result represents the speech synthesis result.
+    pub result: SpeechSynthesisResult,
+}
+
+// SpeechSynthesisWordBoundaryEventArgs represents the speech synthesis word boundary event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechSynthesisWordBoundaryEventArgs {
+    // result_id specifies the identifier of the result the boundary belongs to.
+    pub result_id: String,
+
+    // audio_offset is the offset of the boundary from the start of the synthesized audio.
+    pub audio_offset: Duration,
+
+    // duration is the playing time of the word, punctuation or sentence.
+    pub duration: Duration,
+
+    // text_offset is the byte offset of the word in the plain text or SSML of the request, or u32::MAX if it
+    // could not be located.
+    pub text_offset: u32,
+
+    // word_length is the length of the word in bytes.
+    pub word_length: u32,
+
+    // text is the word, punctuation or sentence.
+    pub text: String,
+
+    // boundary_type is the kind of the boundary.
+    pub boundary_type: SpeechSynthesisBoundaryType,
+}
+
+// SpeechSynthesisVisemeEventArgs represents the speech synthesis viseme event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechSynthesisVisemeEventArgs {
+    // result_id specifies the identifier of the result the viseme belongs to.
+    pub result_id: String,
+
+    // audio_offset is the offset of the viseme from the start of the synthesized audio.
+    pub audio_offset: Duration,
+
+    // viseme_id is the viseme ID.
+    pub viseme_id: u32,
+
+    // animation is the animation chunk in JSON, e.g. blend shapes, or empty if no animation was requested.
+    pub animation: String,
+}
+
+// SpeechSynthesisBookmarkEventArgs represents the speech synthesis bookmark event arguments.
+#[derive(Debug, Clone)]
+pub struct SpeechSynthesisBookmarkEventArgs {
+    // result_id specifies the identifier of the result the bookmark belongs to.
+    pub result_id: String,
+
+    // audio_offset is the offset of the bookmark from the start of the synthesized audio.
+    pub audio_offset: Duration,
+
+    // text is the mark of the bookmark element.
+    pub text: String,
+}
+
+// SpeechSynthesisResult contains detailed information about result of a synthesis operation.
+#[derive(Debug, Clone)]
+pub struct SpeechSynthesisResult {
+    // result_id specifies the result identifier.
+    pub result_id: String,
+
+    // reason specifies status of speech synthesis result.
+    pub reason: ResultReason,
+
+    // audio_data presents the synthesized audio. Results of completed or canceled requests hold the whole
+    // audio received, including the wave header for riff formats; results of synthesizing events hold the
+    // chunk that was received.
+    pub audio_data: Vec<u8>,
+
+    // audio_duration is the playing time of the synthesized audio.
+    pub audio_duration: Duration,
+
+    // cancellation is the reason the synthesis was canceled, if it was.
+    pub cancellation: Option<CancellationReason>,
+
+    // properties is the collection of properties and their values defined for this result.
+    pub properties: PropertyCollection,
+
+    pub(crate) stream: AudioDataStream,
+}
+
+impl SpeechSynthesisResult {
+    fn new(
+        result_id: &str,
+        reason: ResultReason,
+        audio_data: Vec<u8>,
+        stream: AudioDataStream,
+    ) -> Self {
+        SpeechSynthesisResult {
+            result_id: result_id.to_string(),
+            reason,
+            audio_data,
+            audio_duration: Duration::ZERO,
+            cancellation: None,
+            properties: PropertyCollection::new(),
+            stream,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+
+    // serve_audio answers a speak request on the stand-in service with the given audio chunks. It returns the
+    // synthesis.context and ssml messages of the request.
+    async fn serve_audio(
+        connection: &mut test_server::StandInConnection,
+        chunks: &[&[u8]],
+    ) -> (Message, Message) {
+        let context = connection.receive().await.unwrap();
+        let ssml = connection.receive().await.unwrap();
+        let request_id = ssml.request_id().unwrap().to_string();
+        connection
+            .send_json(
+                Path::TurnStart,
+                &request_id,
+                r#"{"context":{"serviceTag":"tag"}}"#,
+            )
+            .await;
+        for chunk in chunks {
+            connection
+                .send(&Message::binary(
+                    Path::Audio,
+                    &request_id,
+                    "audio/x-wav",
+                    *chunk,
+                ))
+                .await;
+        }
+        connection.send_json(Path::TurnEnd, &request_id, "").await;
+        (context, ssml)
+    }
+
+    #[tokio::test]
+    async fn speak_text_against_stand_in_service() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            assert_eq!(
+                connection.header("Ocp-Apim-Subscription-Key"),
+                Some("key".to_string())
+            );
+            let config = connection.receive().await.unwrap();
+            assert_eq!(config.path(), Path::SpeechConfig);
+            assert_eq!(
+                config.json_body().unwrap()["context"]["system"]["lang"],
+                "Rust"
+            );
+
+            let (context, ssml) = serve_audio(&mut connection, &[&[1, 2, 3, 4], &[5, 6]]).await;
+            assert_eq!(context.path(), Path::SynthesisContext);
+            assert_eq!(
+                context.json_body().unwrap()["synthesis"]["audio"]["outputFormat"],
+                "raw-16khz-16bit-mono-pcm"
+            );
+            assert_eq!(ssml.path(), Path::Ssml);
+            assert_eq!(ssml.content_type(), Some(usp::CONTENT_TYPE_SSML));
+            assert_eq!(
+                ssml.text_body().unwrap(),
+                "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' \
+                 xmlns:mstts='http://www.w3.org/2001/mstts' xml:lang='de-DE'>\
+                 <voice name='de-DE-KatjaNeural'>Fish &amp; chips &lt;3</voice></speak>"
+            );
+
+            // The connection is kept for the next request, without a new speech.config.
+            let (context, ssml) = serve_audio(&mut connection, &[&[7, 8]]).await;
+            assert_eq!(context.path(), Path::SynthesisContext);
+            assert_eq!(ssml.text_body().unwrap(), "<speak/>");
+        })
+        .await;
+
+        let mut config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        config.set_speech_synthesis_language("de-DE").unwrap();
+        config
+            .set_speech_synthesis_voice_name("de-DE-KatjaNeural")
+            .unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let events = Arc::new(StdMutex::new(Vec::new()));
+        for register in [
+            SpeechSynthesizer::on_synthesis_started,
+            SpeechSynthesizer::on_synthesizing,
+            SpeechSynthesizer::on_synthesis_completed,
+            SpeechSynthesizer::on_synthesis_canceled,
+        ] {
+            let events = events.clone();
+            register(&synthesizer, move |event: &SpeechSynthesisEventArgs| {
+                let result = &event.result;
+                events
+                    .lock()
+                    .unwrap()
+                    .push((result.reason, result.audio_data.len()));
+            });
+        }
+
+        let result = synthesizer.speak_text("Fish & chips <3").await.unwrap();
+        assert_eq!(result.reason, ResultReason::SynthesisCompleted);
+        assert_eq!(&result.audio_data[..4], b"RIFF");
+        assert_eq!(&result.audio_data[44..], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(result.audio_duration, Duration::from_micros(187));
+        for id in [
+            PropertyId::SpeechServiceResponseSynthesisFirstByteLatencyMs,
+            PropertyId::SpeechServiceResponseSynthesisFinishLatencyMs,
+            PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs,
+        ] {
+            let value = result.properties.get_property(id, "");
+            assert!(value.parse::<u32>().is_ok(), "{} is {:?}", id, value);
+        }
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                (ResultReason::SynthesizingAudioStarted, 0),
+                (ResultReason::SynthesizingAudio, 4),
+                (ResultReason::SynthesizingAudio, 2),
+                (ResultReason::SynthesisCompleted, 50),
+            ]
+        );
+
+        let mut stream = AudioDataStream::from_result(&result);
+        assert_eq!(stream.get_status(), crate::common::StreamStatus::AllData);
+        let mut buffer = [0; 16];
+        assert_eq!(stream.read(&mut buffer).await.unwrap(), 6);
+        assert_eq!(
+            stream.get_property(PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs),
+            result
+                .properties
+                .get_property(PropertyId::SpeechServiceResponseSynthesisUnderrunTimeMs, "")
+        );
+
+        let result = synthesizer
+            .speak_ssml_async("<speak/>")
+            .await
+            .unwrap()
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::SynthesisCompleted);
+        assert_eq!(&result.audio_data[44..], [7, 8]);
+    }
+
+    #[tokio::test]
+    async fn audio_is_written_to_wave_file_and_output_streams() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            serve_audio(&mut connection, &[&[1, 2], &[3, 4]]).await;
+            serve_audio(&mut connection, &[&[5, 6]]).await;
+        })
+        .await;
+        let mut config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        config.set_speech_synthesis_output_format(
+            crate::common::SpeechSynthesisOutputFormat::Riff8Khz16BitMonoPcm,
+        );
+
+        let path = std::env::temp_dir().join(format!("speech-sdk-{}.wav", uuid::Uuid::new_v4()));
+        let audio = AudioConfig::from_wav_file_output(&path).unwrap();
+        let synthesizer = SpeechSynthesizer::from_config_with_audio(&config, audio).unwrap();
+        synthesizer.speak_text("one").await.unwrap();
+        // The header is complete after each synthesis.
+        let file = std::fs::read(&path).unwrap();
+        assert_eq!(file.len(), 44 + 4);
+        assert_eq!(u32::from_le_bytes(file[24..28].try_into().unwrap()), 8000);
+        assert_eq!(u32::from_le_bytes(file[40..44].try_into().unwrap()), 4);
+        synthesizer.speak_text("two").await.unwrap();
+        synthesizer.close().await.unwrap();
+        server.await.unwrap();
+        let file = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(u32::from_le_bytes(file[4..8].try_into().unwrap()), 36 + 6);
+        assert_eq!(u32::from_le_bytes(file[40..44].try_into().unwrap()), 6);
+        assert_eq!(&file[44..], [1, 2, 3, 4, 5, 6]);
+
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            serve_audio(&mut connection, &[&[1, 2], &[3]]).await;
+        })
+        .await;
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let stream = crate::audio::PullAudioOutputStream::create_pull_stream();
+        let audio = AudioConfig::from_stream_output(stream.clone());
+        let synthesizer = SpeechSynthesizer::from_config_with_audio(&config, audio).unwrap();
+        let result = synthesizer.speak_text("hello").await.unwrap();
+        server.await.unwrap();
+        // Results still carry the audio, with the header of the wave format.
+        assert_eq!(result.audio_data.len(), 44 + 3);
+        drop(synthesizer);
+        let mut buffer = [0; 8];
+        assert_eq!(stream.read(&mut buffer).await, 3);
+        assert_eq!(buffer[..3], [1, 2, 3]);
+        assert_eq!(stream.read(&mut buffer).await, 0);
+
+        let input = AudioConfig::from_reader(
+            tokio::io::empty(),
+            AudioStreamFormat::get_default_input_format(),
+        );
+        assert!(matches!(
+            SpeechSynthesizer::from_config_with_audio(&config, input),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn start_speaking_returns_once_audio_arrives() {
+        let (resume, resumed) = oneshot::channel::<()>();
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            connection.receive().await.unwrap();
+            let ssml = connection.receive().await.unwrap();
+            let request_id = ssml.request_id().unwrap().to_string();
+            connection
+                .send(&Message::binary(
+                    Path::Audio,
+                    &request_id,
+                    "audio/x-wav",
+                    vec![1, 2],
+                ))
+                .await;
+            resumed.await.unwrap();
+            connection
+                .send(&Message::binary(
+                    Path::Audio,
+                    &request_id,
+                    "audio/x-wav",
+                    vec![3],
+                ))
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.start_speaking_text("hello").await.unwrap();
+        assert_eq!(result.reason, ResultReason::SynthesizingAudioStarted);
+
+        let mut stream = AudioDataStream::from_result(&result);
+        let mut buffer = [0; 8];
+        assert_eq!(stream.read(&mut buffer).await.unwrap(), 2);
+        assert_eq!(
+            stream.get_status(),
+            crate::common::StreamStatus::PartialData
+        );
+        resume.send(()).unwrap();
+        assert_eq!(stream.read(&mut buffer).await.unwrap(), 1);
+        assert_eq!(buffer[0], 3);
+        assert_eq!(stream.read(&mut buffer).await.unwrap(), 0);
+        assert_eq!(stream.get_status(), crate::common::StreamStatus::AllData);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn start_speaking_without_audio_returns_final_result() {
+        let url = test_server::reject(429, "").await;
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.start_speaking_ssml("<speak/>").await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+    }
+
+    #[tokio::test]
+    async fn word_boundaries_are_located_in_the_text() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            let options = &context.json_body().unwrap()["synthesis"]["audio"]["metadataOptions"];
+            assert_eq!(options["wordBoundaryEnabled"], true);
+            assert_eq!(options["punctuationBoundaryEnabled"], false);
+            assert_eq!(options["sentenceBoundaryEnabled"], true);
+            let ssml = connection.receive().await.unwrap();
+            let request_id = ssml.request_id().unwrap().to_string();
+            let boundary = |kind: &str, offset: u64, text: &str, boundary_type: &str| {
+                format!(
+                    r#"{{"Type":"{}","Data":{{"Offset":{},"Duration":1000000,"text":{{"Text":"{}","Length":{},"BoundaryType":"{}"}}}}}}"#,
+                    kind,
+                    offset,
+                    text,
+                    text.len(),
+                    boundary_type
+                )
+            };
+            let metadata = [
+                boundary("SentenceBoundary", 500000, "Hi, speak to me.", "SentenceBoundary"),
+                boundary("WordBoundary", 500000, "Hi", "WordBoundary"),
+                boundary("WordBoundary", 1500000, ",", "PunctuationBoundary"),
+                boundary("WordBoundary", 2000000, "speak", "WordBoundary"),
+                boundary("WordBoundary", 3000000, "nowhere", "WordBoundary"),
+            ];
+            connection
+                .send_json(
+                    Path::AudioMetadata,
+                    &request_id,
+                    &format!(r#"{{"Metadata":[{}]}}"#, metadata.join(",")),
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let mut config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        config.set_property(
+            PropertyId::SpeechServiceResponseRequestPunctuationBoundary,
+            "false",
+        );
+        config.set_property(
+            PropertyId::SpeechServiceResponseRequestSentenceBoundary,
+            "true",
+        );
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let boundaries = Arc::new(StdMutex::new(Vec::new()));
+        let events = boundaries.clone();
+        synthesizer.on_word_boundary(move |event| events.lock().unwrap().push(event.clone()));
+
+        // "speak" in the <speak> tag is skipped.
+        let ssml = "<speak version='1.0' xml:lang='en-US'>Hi, speak to me.</speak>";
+        let result = synthesizer.speak_ssml(ssml).await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::SynthesisCompleted);
+
+        let boundaries = boundaries.lock().unwrap();
+        let located: Vec<_> = boundaries
+            .iter()
+            .map(|event| (event.boundary_type, event.text.as_str(), event.text_offset))
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (
+                    SpeechSynthesisBoundaryType::SentenceBoundary,
+                    "Hi, speak to me.",
+                    38
+                ),
+                (SpeechSynthesisBoundaryType::WordBoundary, "Hi", 38),
+                (SpeechSynthesisBoundaryType::WordBoundary, "speak", 42),
+                (
+                    SpeechSynthesisBoundaryType::WordBoundary,
+                    "nowhere",
+                    u32::MAX
+                ),
+            ]
+        );
+        let speak = &boundaries[2];
+        assert_eq!(speak.audio_offset, Duration::from_millis(200));
+        assert_eq!(speak.duration, Duration::from_millis(100));
+        assert_eq!(speak.word_length, 5);
+        assert_eq!(
+            &ssml[speak.text_offset as usize..][..speak.word_length as usize],
+            "speak"
+        );
+    }
+
+    #[tokio::test]
+    async fn viseme_and_bookmark_events() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            let context = connection.receive().await.unwrap();
+            let options = &context.json_body().unwrap()["synthesis"]["audio"]["metadataOptions"];
+            assert_eq!(options["visemeEnabled"], true);
+            assert_eq!(options["bookmarkEnabled"], true);
+            assert_eq!(options["wordBoundaryEnabled"], false);
+            let ssml = connection.receive().await.unwrap();
+            let request_id = ssml.request_id().unwrap().to_string();
+            connection
+                .send_json(
+                    Path::AudioMetadata,
+                    &request_id,
+                    r#"{"Metadata":[
+                        {"Type":"Viseme","Data":{"Offset":1000000,"VisemeId":21,"AnimationChunk":"{\"FrameIndex\":0}"}},
+                        {"Type":"Bookmark","Data":{"Offset":2000000,"Bookmark":"wave"}},
+                        {"Type":"WordBoundary","Data":{"Offset":2000000,"Duration":0,"text":{"Text":"hi"}}},
+                        {"Type":"SessionEnd","Data":{"Offset":3000000}}
+                    ]}"#,
+                )
+                .await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let visemes = Arc::new(StdMutex::new(Vec::new()));
+        let events = visemes.clone();
+        synthesizer.on_viseme_received(move |event| events.lock().unwrap().push(event.clone()));
+        let bookmarks = Arc::new(StdMutex::new(Vec::new()));
+        let events = bookmarks.clone();
+        synthesizer.on_bookmark_reached(move |event| events.lock().unwrap().push(event.clone()));
+
+        let result = synthesizer
+            .speak_ssml("<speak>hi <bookmark mark='wave'/></speak>")
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(result.reason, ResultReason::SynthesisCompleted);
+
+        let visemes = visemes.lock().unwrap();
+        assert_eq!(visemes.len(), 1);
+        assert_eq!(visemes[0].viseme_id, 21);
+        assert_eq!(visemes[0].audio_offset, Duration::from_millis(100));
+        assert_eq!(visemes[0].animation, r#"{"FrameIndex":0}"#);
+        assert_eq!(visemes[0].result_id, result.result_id);
+        let bookmarks = bookmarks.lock().unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].text, "wave");
+        assert_eq!(bookmarks[0].audio_offset, Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn stop_speaking_cancels_requests_in_progress_and_queued() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            connection.receive().await.unwrap();
+            let ssml = connection.receive().await.unwrap();
+            let request_id = ssml.request_id().unwrap().to_string();
+            connection
+                .send(&Message::binary(
+                    Path::Audio,
+                    &request_id,
+                    "audio/x-wav",
+                    vec![1, 2],
+                ))
+                .await;
+            // The synthesizer drops the connection when it is stopped.
+            while connection.receive().await.is_some() {}
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let (audio, mut audio_received) = tokio::sync::mpsc::unbounded_channel();
+        synthesizer.on_synthesizing(move |_| {
+            let _ = audio.send(());
+        });
+        let in_progress = synthesizer.speak_text_async("first");
+        let queued = synthesizer.speak_text_async("second");
+        audio_received.recv().await.unwrap();
+        synthesizer.stop_speaking().await.unwrap();
+        server.await.unwrap();
+
+        for request in [in_progress, queued] {
+            let result = request.await.unwrap().unwrap();
+            assert_eq!(result.reason, ResultReason::Canceled);
+            assert_eq!(
+                result.cancellation,
+                Some(CancellationReason::CancelledByUser)
+            );
+            assert_eq!(
+                result
+                    .properties
+                    .get_property(PropertyId::CancellationDetailsReason, ""),
+                "CancelledByUser"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn async_requests_are_synthesized_in_order() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            connection.receive().await.unwrap();
+            for chunk in 0..8u8 {
+                let (_, ssml) = serve_audio(&mut connection, &[&[chunk]]).await;
+                assert_eq!(
+                    ssml.text_body().unwrap(),
+                    format!("<speak>{}</speak>", chunk)
+                );
+            }
+        })
+        .await;
+
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let (completed, mut order) = tokio::sync::mpsc::unbounded_channel();
+        synthesizer.on_synthesis_completed(move |event| {
+            let _ = completed.send(event.result.audio_data[44]);
+        });
+        // Requests made from a worker thread start out of order: the last task spawned runs first.
+        let (_synthesizer, requests) = tokio::spawn(async move {
+            let requests: Vec<_> = (0..8)
+                .map(|chunk| synthesizer.speak_ssml_async(&format!("<speak>{}</speak>", chunk)))
+                .collect();
+            (synthesizer, requests)
+        })
+        .await
+        .unwrap();
+        for (chunk, request) in requests.into_iter().enumerate() {
+            let result = request.await.unwrap().unwrap();
+            assert_eq!(result.audio_data[44..], [chunk as u8]);
+            assert_eq!(order.recv().await, Some(chunk as u8));
+        }
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_connection_cancels_synthesis() {
+        let url =
+            test_server::reject(401, r#"{"error":{"code":"401","message":"invalid key"}}"#).await;
+        let config = SpeechConfig::from_endpoint_with_subscription(&url, "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.speak_text("hello").await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+        match &result.cancellation {
+            Some(CancellationReason::Error(error, details)) => {
+                assert_eq!(*error, CancellationError::AuthenticationFailure);
+                assert_eq!(details.detailed_text, "invalid key");
+            }
+            reason => panic!("unexpected cancellation {:?}", reason),
+        }
+        assert_eq!(
+            AudioDataStream::from_result(&result).get_status(),
+            crate::common::StreamStatus::Canceled
+        );
+    }
+
+    #[tokio::test]
+    async fn get_voices_list_from_stand_in_service() {
+        let voices = r#"[
+            {"Name":"Microsoft Server Speech Text to Speech Voice (en-US, JennyNeural)","LocalName":"Jenny",
+             "ShortName":"en-US-JennyNeural","Gender":"Female","Locale":"en-US","StyleList":["chat","cheerful"],
+             "VoiceType":"Neural"},
+            {"Name":"Microsoft Server Speech Text to Speech Voice (de-DE, ConradNeural)","LocalName":"Conrad",
+             "ShortName":"de-DE-ConradNeural","Gender":"Male","Locale":"de-DE","VoiceType":"Neural"},
+            {"Name":"Microsoft Server Speech Text to Speech Voice (en-US, GuyRUS)","LocalName":"Guy",
+             "ShortName":"en-US-GuyRUS","Gender":"Male","Locale":"en-US","VoiceType":"Standard"}
+        ]"#;
+        let (url, server) = test_server::serve_http(200, voices).await;
+        let config =
+            SpeechConfig::from_endpoint_with_subscription(&format!("{}/tts", url), "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.get_voices_list("en-us").await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /cognitiveservices/voices/list HTTP/1.1\r\n"));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("ocp-apim-subscription-key: key\r\n"));
+
+        assert_eq!(result.reason, ResultReason::VoicesListRetrieved);
+        let names: Vec<_> = result
+            .voices
+            .iter()
+            .map(|voice| voice.short_name.as_str())
+            .collect();
+        assert_eq!(names, ["en-US-JennyNeural", "en-US-GuyRUS"]);
+        let jenny = &result.voices[0];
+        assert_eq!(jenny.local_name, "Jenny");
+        assert_eq!(jenny.locale, "en-US");
+        assert_eq!(jenny.gender, crate::common::SynthesisVoiceGender::Female);
+        assert_eq!(
+            jenny.voice_type,
+            crate::common::SynthesisVoiceType::OnlineNeural
+        );
+        assert_eq!(jenny.style_list, ["chat", "cheerful"]);
+        assert_eq!(jenny.voice_path, "");
+        assert_eq!(
+            result.voices[1].voice_type,
+            crate::common::SynthesisVoiceType::OnlineStandard
+        );
+
+        // Documents are validated against the retrieved voices before anything is sent.
+        let document = Speak::new("en-US").voice(crate::ssml::Voice::new("en-US-AvaNeural"));
+        let err = synthesizer
+            .speak_ssml_document(&document)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidArgument(message) if message.contains("en-US-AvaNeural"))
+        );
+    }
+
+    #[tokio::test]
+    async fn documents_in_other_locales_validate_after_a_filtered_voices_list() {
+        let voices = r#"[
+            {"ShortName":"en-US-JennyNeural","Locale":"en-US","VoiceType":"Neural"},
+            {"ShortName":"de-DE-ConradNeural","Locale":"de-DE","VoiceType":"Neural"}
+        ]"#;
+        let (url, server) = test_server::serve_http(200, voices).await;
+        let config =
+            SpeechConfig::from_endpoint_with_subscription(&format!("{}/tts", url), "key").unwrap();
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.get_voices_list("en-US").await.unwrap();
+        server.await.unwrap();
+        assert_eq!(result.voices.len(), 1);
+
+        // The stand-in only serves the voices list, so the synthesis itself is canceled; what matters is
+        // that the document is not rejected as using an unknown voice.
+        let document = Speak::new("de-DE").voice(crate::ssml::Voice::new("de-DE-ConradNeural"));
+        let result = synthesizer.speak_ssml_document(&document).await.unwrap();
+        assert_eq!(result.reason, ResultReason::Canceled);
+        let document = Speak::new("de-DE").voice(crate::ssml::Voice::new("de-DE-KatjaNeural"));
+        assert!(synthesizer.speak_ssml_document(&document).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_voices_list_honors_endpoint_property_and_reports_errors() {
+        let (url, server) =
+            test_server::serve_http(401, r#"{"error":{"code":"401","message":"bad key"}}"#).await;
+        let mut config = SpeechConfig::from_subscription("key", "westus").unwrap();
+        config.set_property(
+            PropertyId::SpeechServiceConnectionVoicesListEndpoint,
+            &format!("{}/custom/voices", url),
+        );
+        let synthesizer = SpeechSynthesizer::from_config(&config).unwrap();
+        let result = synthesizer.get_voices_list("").await.unwrap();
+        assert!(server.await.unwrap().starts_with("GET /custom/voices "));
+        assert_eq!(result.reason, ResultReason::Canceled);
+        assert!(result.voices.is_empty());
+        assert!(
+            result.error_details.contains("bad key"),
+            "{}",
+            result.error_details
+        );
+    }
+
+    #[test]
+    fn output_formats_are_requested_as_raw_audio() {
+        let output = OutputFormat::new(SpeechSynthesisOutputFormat::Riff24Khz16BitMonoPcm);
+        assert_eq!(output.name, "raw-24khz-16bit-mono-pcm");
+        assert!(output.riff);
+        assert_eq!(output.format.samples_per_second(), 24000);
+        let output = OutputFormat::new(SpeechSynthesisOutputFormat::Riff22050Hz16BitMonoPcm);
+        assert_eq!(output.name, "raw-22050hz-16bit-mono-pcm");
+        assert_eq!(output.format.samples_per_second(), 22050);
+        let output = OutputFormat::new(SpeechSynthesisOutputFormat::Raw8Khz8BitMonoMULaw);
+        assert!(!output.riff);
+        assert_eq!(
+            output.format.container_format(),
+            Some(AudioStreamContainerFormat::MuLaw)
+        );
+        let output = OutputFormat::new(SpeechSynthesisOutputFormat::Audio24Khz48KBitRateMonoMp3);
+        assert_eq!(output.name, "audio-24khz-48kbitrate-mono-mp3");
+        assert_eq!(
+            output.format.container_format(),
+            Some(AudioStreamContainerFormat::Mp3)
+        );
+        assert_eq!(output.duration(1000), Duration::ZERO);
+
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceConnectionSynthOutputFormat,
+            "riff-96khz-24bit-mono-pcm",
+        );
+        let output = OutputFormat::from_properties(&properties);
+        assert_eq!(output.name, "riff-96khz-24bit-mono-pcm");
+        assert!(!output.riff);
+    }
+
+    #[test]
+    fn playback_waiting_for_audio_is_underrun_time() {
+        let start = Instant::now();
+        let mut playback = Playback::default();
+        playback.receive(start, Duration::from_millis(100));
+        playback.receive(
+            start + Duration::from_millis(50),
+            Duration::from_millis(100),
+        );
+        assert_eq!(playback.underrun, Duration::ZERO);
+        playback.receive(
+            start + Duration::from_millis(230),
+            Duration::from_millis(100),
+        );
+        assert_eq!(playback.underrun, Duration::from_millis(30));
+        playback.receive(
+            start + Duration::from_millis(300),
+            Duration::from_millis(100),
+        );
+        assert_eq!(playback.underrun, Duration::from_millis(30));
+        playback.receive(
+            start + Duration::from_millis(500),
+            Duration::from_millis(100),
+        );
+        assert_eq!(playback.underrun, Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::common::{PropertyCollection, PropertyId};
+use crate::error::Result;
+use crate::speech_config::{validate_language, SpeechConfig};
+
+// SpeechTranslationConfig is the class that defines configurations for translation with speech input. It
+// dereferences to SpeechConfig for the settings shared with speech recognition, such as the recognition
+// language, which is the language translated from.
+#[derive(Debug, Clone, Default)]
+pub struct SpeechTranslationConfig {
+    config: SpeechConfig,
+}
+
+impl SpeechTranslationConfig {
+    // from_subscription creates a speech translation config instance with specified subscription key and region.
+    pub fn from_subscription(subscription_key: &str, region: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_subscription(subscription_key, region)?,
+        })
+    }
+
+    // from_authorization_token creates a speech translation config instance with specified authorization token
+    // and region.
+    pub fn from_authorization_token(authorization_token: &str, region: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_authorization_token(authorization_token, region)?,
+        })
+    }
+
+    // from_endpoint creates a speech translation config instance with the specified endpoint. See
+    // SpeechConfig::from_endpoint for how query parameters in the endpoint are handled.
+    pub fn from_endpoint(endpoint: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_endpoint(endpoint)?,
+        })
+    }
+
+    // from_endpoint_with_subscription creates a speech translation config instance with the specified endpoint
+    // and subscription key.
+    pub fn from_endpoint_with_subscription(endpoint: &str, subscription_key: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_endpoint_with_subscription(endpoint, subscription_key)?,
+        })
+    }
+
+    // from_host creates a speech translation config instance with the specified host.
+    pub fn from_host(host: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_host(host)?,
+        })
+    }
+
+    // from_host_with_subscription creates a speech translation config instance with the specified host and
+    // subscription key.
+    pub fn from_host_with_subscription(host: &str, subscription_key: &str) -> Result<Self> {
+        Ok(SpeechTranslationConfig {
+            config: SpeechConfig::from_host_with_subscription(host, subscription_key)?,
+        })
+    }
+
+    // add_target_language adds a target language for translation, in BCP-47 format (e.g. "de" or "zh-Hans").
+    pub fn add_target_language(&mut self, language: &str) -> Result<()> {
+        add_target_language(self.config.properties_mut(), language)?;
+        Ok(())
+    }
+
+    // remove_target_language removes a target language for translation.
+    pub fn remove_target_language(&mut self, language: &str) {
+        remove_target_language(self.config.properties_mut(), language);
+    }
+
+    // target_languages is the list of target languages for translation.
+    pub fn target_languages(&self) -> Vec<String> {
+        target_languages(self.config.properties())
+    }
+}
+
+impl Deref for SpeechTranslationConfig {
+    type Target = SpeechConfig;
+
+    fn deref(&self) -> &SpeechConfig {
+        &self.config
+    }
+}
+
+impl DerefMut for SpeechTranslationConfig {
+    fn deref_mut(&mut self) -> &mut SpeechConfig {
+        &mut self.config
+    }
+}
+
+// target_languages reads the target languages stored in SpeechServiceConnectionTranslationToLanguages.
+pub(crate) fn target_languages(properties: &PropertyCollection) -> Vec<String> {
+    properties
+        .get_property(
+            PropertyId::SpeechServiceConnectionTranslationToLanguages,
+            "",
+        )
+        .split(',')
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// add_target_language adds language to the target languages stored in properties, returning whether it was
+// not a target language yet.
+pub(crate) fn add_target_language(
+    properties: &mut PropertyCollection,
+    language: &str,
+) -> Result<bool> {
+    let language = validate_language(language)?;
+    let mut languages = target_languages(properties);
+    if languages
+        .iter()
+        .any(|target| target.eq_ignore_ascii_case(&language))
+    {
+        return Ok(false);
+    }
+    languages.push(language);
+    properties.set_property(
+        PropertyId::SpeechServiceConnectionTranslationToLanguages,
+        languages.join(","),
+    );
+    Ok(true)
+}
+
+// remove_target_language removes language from the target languages stored in properties, returning whether it
+// was a target language.
+pub(crate) fn remove_target_language(properties: &mut PropertyCollection, language: &str) -> bool {
+    let mut languages = target_languages(properties);
+    let count = languages.len();
+    languages.retain(|target| !target.eq_ignore_ascii_case(language.trim()));
+    properties.set_property(
+        PropertyId::SpeechServiceConnectionTranslationToLanguages,
+        languages.join(","),
+    );
+    languages.len() != count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_languages_are_added_once_and_removed() {
+        let mut config = SpeechTranslationConfig::from_subscription("key", "westus").unwrap();
+        config.set_speech_recognition_language("en-US").unwrap();
+        config.add_target_language("de").unwrap();
+        config.add_target_language("zh-Hans").unwrap();
+        config.add_target_language("DE").unwrap();
+        assert!(config.add_target_language("not a language").is_err());
+        assert_eq!(config.target_languages(), ["de", "zh-Hans"]);
+        assert_eq!(
+            config.get_property(PropertyId::SpeechServiceConnectionTranslationToLanguages),
+            "de,zh-Hans"
+        );
+
+        config.remove_target_language("de");
+        config.remove_target_language("fr");
+        assert_eq!(config.target_languages(), ["zh-Hans"]);
+        assert_eq!(config.speech_recognition_language(), Some("en-US"));
+    }
+}
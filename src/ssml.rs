@@ -0,0 +1,788 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use url::Url;
+
+use crate::error::{Error, Result};
+use crate::voice_info::VoiceInfo;
+
+// MAX_BREAK is the longest pause the service accepts in a break element.
+const MAX_BREAK: Duration = Duration::from_secs(20);
+
+// SAY_AS_TYPES are the values of the interpret-as attribute of say-as elements supported by the service.
+const SAY_AS_TYPES: [&str; 16] = [
+    "address",
+    "cardinal",
+    "characters",
+    "currency",
+    "date",
+    "digits",
+    "duration",
+    "fraction",
+    "letters",
+    "name",
+    "number",
+    "number_digit",
+    "ordinal",
+    "spell-out",
+    "telephone",
+    "time",
+];
+
+// ROLES are the roles a voice can play in mstts:express-as elements.
+const ROLES: [&str; 8] = [
+    "Girl",
+    "Boy",
+    "YoungAdultFemale",
+    "YoungAdultMale",
+    "OlderAdultFemale",
+    "OlderAdultMale",
+    "SeniorFemale",
+    "SeniorMale",
+];
+
+// Speak is a SSML document, built from the voices that speak it.
+//
+// Speak::new("en-US")
+//     .voice(Voice::new("en-US-JennyNeural").text("Hello, ").break_time(Duration::from_millis(300)))
+//     .build()
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speak {
+    language: String,
+    voices: Vec<Voice>,
+}
+
+impl Speak {
+    // new creates a document in language, e.g. en-US.
+    pub fn new(language: &str) -> Self {
+        Speak {
+            language: language.to_string(),
+            voices: Vec::new(),
+        }
+    }
+
+    // voice appends a voice element to the document.
+    pub fn voice(mut self, voice: Voice) -> Self {
+        self.voices.push(voice);
+        self
+    }
+
+    // build validates the document and returns it as SSML.
+    pub fn build(&self) -> Result<String> {
+        self.build_with_voices(&[])
+    }
+
+    // build_with_voices validates the document and returns it as SSML. Unless voices is empty, voices and
+    // speaking styles not in voices are rejected.
+    pub fn build_with_voices(&self, voices: &[VoiceInfo]) -> Result<String> {
+        if self.language.is_empty() {
+            return Err(invalid("speak requires a language"));
+        }
+        if self.voices.is_empty() {
+            return Err(invalid("speak requires at least one voice"));
+        }
+        for voice in &self.voices {
+            voice.validate(voices)?;
+        }
+
+        let mut ssml = String::new();
+        let _ = write!(
+            ssml,
+            "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' \
+             xmlns:mstts='http://www.w3.org/2001/mstts' xml:lang='{}'>",
+            escape_xml(&self.language)
+        );
+        for voice in &self.voices {
+            voice.write(&mut ssml);
+        }
+        ssml.push_str("</speak>");
+        Ok(ssml)
+    }
+}
+
+// Voice is a voice element, the text spoken by one voice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    name: String,
+    lexicons: Vec<String>,
+    viseme: Option<VisemeType>,
+    content: Vec<Node>,
+}
+
+impl Voice {
+    // new creates a voice element spoken by the voice name, e.g. en-US-JennyNeural.
+    pub fn new(name: &str) -> Self {
+        Voice {
+            name: name.to_string(),
+            lexicons: Vec::new(),
+            viseme: None,
+            content: Vec::new(),
+        }
+    }
+
+    // lexicon adds a custom lexicon, located at uri, to the voice.
+    pub fn lexicon(mut self, uri: &str) -> Self {
+        self.lexicons.push(uri.to_string());
+        self
+    }
+
+    // viseme requests viseme events of the given type for the voice.
+    pub fn viseme(mut self, viseme_type: VisemeType) -> Self {
+        self.viseme = Some(viseme_type);
+        self
+    }
+
+    // express_as appends text spoken in a speaking style.
+    pub fn express_as(mut self, express_as: ExpressAs) -> Self {
+        self.content.push(Node::ExpressAs(express_as));
+        self
+    }
+
+    fn validate(&self, voices: &[VoiceInfo]) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(invalid("voice requires a name"));
+        }
+        let info = voices.iter().find(|voice| {
+            voice.name.eq_ignore_ascii_case(&self.name)
+                || voice.short_name.eq_ignore_ascii_case(&self.name)
+        });
+        if !voices.is_empty() && info.is_none() {
+            return Err(invalid(&format!("unknown voice {}", self.name)));
+        }
+        for uri in &self.lexicons {
+            validate_uri("lexicon", uri)?;
+        }
+        let styles = info
+            .map(|voice| voice.style_list.as_slice())
+            .unwrap_or_default();
+        validate_content(&self.content, &self.name, styles)
+    }
+
+    fn write(&self, ssml: &mut String) {
+        let _ = write!(ssml, "<voice name='{}'>", escape_xml(&self.name));
+        for uri in &self.lexicons {
+            let _ = write!(ssml, "<lexicon uri='{}'/>", escape_xml(uri));
+        }
+        if let Some(viseme) = self.viseme {
+            let _ = write!(ssml, "<mstts:viseme type='{}'/>", viseme.as_str());
+        }
+        write_content(ssml, &self.content);
+        ssml.push_str("</voice>");
+    }
+}
+
+// Prosody is a prosody element, text spoken with a changed rate, pitch or volume.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prosody {
+    rate: Option<String>,
+    pitch: Option<String>,
+    volume: Option<String>,
+    content: Vec<Node>,
+}
+
+impl Prosody {
+    // new creates a prosody element. At least one of rate, pitch and volume must be set.
+    pub fn new() -> Self {
+        Prosody::default()
+    }
+
+    // rate sets the speaking rate: x-slow, slow, medium, fast, x-fast, default, a relative value such as
+    // +10% or a multiplier such as 1.5.
+    pub fn rate(mut self, rate: &str) -> Self {
+        self.rate = Some(rate.to_string());
+        self
+    }
+
+    // pitch sets the baseline pitch: x-low, low, medium, high, x-high, default, an absolute value such as
+    // 600Hz or a relative value such as +80Hz, -2st or +10%.
+    pub fn pitch(mut self, pitch: &str) -> Self {
+        self.pitch = Some(pitch.to_string());
+        self
+    }
+
+    // volume sets the volume: silent, x-soft, soft, medium, loud, x-loud, default, an absolute value from 0 to
+    // 100 or a relative value such as -20 or +10%.
+    pub fn volume(mut self, volume: &str) -> Self {
+        self.volume = Some(volume.to_string());
+        self
+    }
+
+    fn validate(&self, voice: &str, styles: &[String]) -> Result<()> {
+        if self.rate.is_none() && self.pitch.is_none() && self.volume.is_none() {
+            return Err(invalid("prosody requires a rate, pitch or volume"));
+        }
+        let attributes = [
+            (
+                "rate",
+                &self.rate,
+                &["x-slow", "slow", "medium", "fast", "x-fast", "default"][..],
+                &["%"][..],
+            ),
+            (
+                "pitch",
+                &self.pitch,
+                &["x-low", "low", "medium", "high", "x-high", "default"][..],
+                &["Hz", "st", "%"][..],
+            ),
+            (
+                "volume",
+                &self.volume,
+                &[
+                    "silent", "x-soft", "soft", "medium", "loud", "x-loud", "default",
+                ][..],
+                &["%"][..],
+            ),
+        ];
+        for (attribute, value, names, units) in attributes {
+            if let Some(value) = value {
+                if !is_prosody_value(value, names, units) {
+                    return Err(invalid(&format!("invalid prosody {} {}", attribute, value)));
+                }
+            }
+        }
+        if let Some(volume) = self.volume.as_deref().and_then(|v| v.parse::<f64>().ok()) {
+            if volume > 100.0 {
+                return Err(invalid(&format!("prosody volume {} is above 100", volume)));
+            }
+        }
+        validate_content(&self.content, voice, styles)
+    }
+
+    fn write(&self, ssml: &mut String) {
+        ssml.push_str("<prosody");
+        for (attribute, value) in [
+            ("rate", &self.rate),
+            ("pitch", &self.pitch),
+            ("volume", &self.volume),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(ssml, " {}='{}'", attribute, escape_xml(value));
+            }
+        }
+        ssml.push('>');
+        write_content(ssml, &self.content);
+        ssml.push_str("</prosody>");
+    }
+}
+
+// ExpressAs is a mstts:express-as element, text spoken in a speaking style such as cheerful, optionally
+// playing a role.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressAs {
+    style: String,
+    style_degree: Option<f32>,
+    role: Option<String>,
+    content: Vec<Node>,
+}
+
+impl ExpressAs {
+    // new creates an express-as element in style.
+    pub fn new(style: &str) -> Self {
+        ExpressAs {
+            style: style.to_string(),
+            style_degree: None,
+            role: None,
+            content: Vec::new(),
+        }
+    }
+
+    // style_degree sets the intensity of the style, from 0.01 to 2.
+    pub fn style_degree(mut self, degree: f32) -> Self {
+        self.style_degree = Some(degree);
+        self
+    }
+
+    // role sets the age and gender the voice imitates, e.g. YoungAdultFemale.
+    pub fn role(mut self, role: &str) -> Self {
+        self.role = Some(role.to_string());
+        self
+    }
+
+    fn validate(&self, voice: &str, styles: &[String]) -> Result<()> {
+        if self.style.is_empty() {
+            return Err(invalid("express-as requires a style"));
+        }
+        if !styles.is_empty() && !styles.iter().any(|s| s.eq_ignore_ascii_case(&self.style)) {
+            return Err(invalid(&format!(
+                "voice {} does not support style {}",
+                voice, self.style
+            )));
+        }
+        if let Some(degree) = self.style_degree {
+            if !(0.01..=2.0).contains(&degree) {
+                return Err(invalid(&format!(
+                    "style degree {} is not between 0.01 and 2",
+                    degree
+                )));
+            }
+        }
+        if let Some(role) = &self.role {
+            if !ROLES.contains(&role.as_str()) {
+                return Err(invalid(&format!("unknown role {}", role)));
+            }
+        }
+        validate_content(&self.content, voice, styles)
+    }
+
+    fn write(&self, ssml: &mut String) {
+        let _ = write!(
+            ssml,
+            "<mstts:express-as style='{}'",
+            escape_xml(&self.style)
+        );
+        if let Some(degree) = self.style_degree {
+            let _ = write!(ssml, " styledegree='{}'", degree);
+        }
+        if let Some(role) = &self.role {
+            let _ = write!(ssml, " role='{}'", escape_xml(role));
+        }
+        ssml.push('>');
+        write_content(ssml, &self.content);
+        ssml.push_str("</mstts:express-as>");
+    }
+}
+
+// SayAs is a say-as element, text whose pronunciation is given by its content type, such as a date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SayAs {
+    interpret_as: String,
+    format: Option<String>,
+    detail: Option<String>,
+    text: String,
+}
+
+impl SayAs {
+    // new creates a say-as element speaking text as interpret_as, e.g. date, cardinal or telephone.
+    pub fn new(interpret_as: &str, text: &str) -> Self {
+        SayAs {
+            interpret_as: interpret_as.to_string(),
+            format: None,
+            detail: None,
+            text: text.to_string(),
+        }
+    }
+
+    // format sets the format of the text, e.g. mdy for a date.
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_string());
+        self
+    }
+
+    // detail sets the level of detail to be spoken.
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+}
+
+// BreakStrength is the relative duration of a pause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakStrength {
+    None,
+    XWeak,
+    Weak,
+    Medium,
+    Strong,
+    XStrong,
+}
+
+impl BreakStrength {
+    fn as_str(self) -> &'static str {
+        match self {
+            BreakStrength::None => "none",
+            BreakStrength::XWeak => "x-weak",
+            BreakStrength::Weak => "weak",
+            BreakStrength::Medium => "medium",
+            BreakStrength::Strong => "strong",
+            BreakStrength::XStrong => "x-strong",
+        }
+    }
+}
+
+// PhonemeAlphabet is the phonetic alphabet of a phoneme element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhonemeAlphabet {
+    Ipa,
+    Sapi,
+    Ups,
+    XSampa,
+}
+
+impl PhonemeAlphabet {
+    fn as_str(self) -> &'static str {
+        match self {
+            PhonemeAlphabet::Ipa => "ipa",
+            PhonemeAlphabet::Sapi => "sapi",
+            PhonemeAlphabet::Ups => "ups",
+            PhonemeAlphabet::XSampa => "x-sampa",
+        }
+    }
+}
+
+// VisemeType is the kind of viseme events requested by a mstts:viseme element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisemeType {
+    // RedlipsFront requests viseme images of the front of the lips.
+    RedlipsFront,
+
+    // FacialExpression requests blend shapes animating a face.
+    FacialExpression,
+}
+
+impl VisemeType {
+    fn as_str(self) -> &'static str {
+        match self {
+            VisemeType::RedlipsFront => "redlips_front",
+            VisemeType::FacialExpression => "FacialExpression",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    BreakTime(Duration),
+    BreakStrength(BreakStrength),
+    SayAs(SayAs),
+    Phoneme {
+        alphabet: PhonemeAlphabet,
+        ph: String,
+        text: String,
+    },
+    Bookmark(String),
+    Audio {
+        src: String,
+        fallback: String,
+    },
+    Prosody(Prosody),
+    ExpressAs(ExpressAs),
+}
+
+// content_methods adds the methods appending content shared by voice, prosody and express-as elements.
+macro_rules! content_methods {
+    ($($element:ty),*) => {$(
+        impl $element {
+            // text appends text, escaped as needed.
+            pub fn text(mut self, text: &str) -> Self {
+                self.content.push(Node::Text(text.to_string()));
+                self
+            }
+
+            // break_time appends a pause of duration, at most 20 seconds.
+            pub fn break_time(mut self, duration: Duration) -> Self {
+                self.content.push(Node::BreakTime(duration));
+                self
+            }
+
+            // break_strength appends a pause of relative duration strength.
+            pub fn break_strength(mut self, strength: BreakStrength) -> Self {
+                self.content.push(Node::BreakStrength(strength));
+                self
+            }
+
+            // say_as appends text spoken as its content type.
+            pub fn say_as(mut self, say_as: SayAs) -> Self {
+                self.content.push(Node::SayAs(say_as));
+                self
+            }
+
+            // phoneme appends text pronounced as the phonetic transcription ph.
+            pub fn phoneme(mut self, alphabet: PhonemeAlphabet, ph: &str, text: &str) -> Self {
+                self.content.push(Node::Phoneme {
+                    alphabet,
+                    ph: ph.to_string(),
+                    text: text.to_string(),
+                });
+                self
+            }
+
+            // bookmark appends a bookmark, reported by bookmark reached events when it is spoken.
+            pub fn bookmark(mut self, mark: &str) -> Self {
+                self.content.push(Node::Bookmark(mark.to_string()));
+                self
+            }
+
+            // audio appends the audio file at src, or fallback text spoken if it cannot be played.
+            pub fn audio(mut self, src: &str, fallback: &str) -> Self {
+                self.content.push(Node::Audio {
+                    src: src.to_string(),
+                    fallback: fallback.to_string(),
+                });
+                self
+            }
+
+            // prosody appends text spoken with a changed rate, pitch or volume.
+            pub fn prosody(mut self, prosody: Prosody) -> Self {
+                self.content.push(Node::Prosody(prosody));
+                self
+            }
+        }
+    )*};
+}
+
+content_methods!(Voice, Prosody, ExpressAs);
+
+fn validate_content(content: &[Node], voice: &str, styles: &[String]) -> Result<()> {
+    for node in content {
+        match node {
+            Node::Text(_) | Node::BreakStrength(_) => {}
+            Node::BreakTime(duration) => {
+                if *duration > MAX_BREAK {
+                    return Err(invalid(&format!(
+                        "break of {:?} is longer than {:?}",
+                        duration, MAX_BREAK
+                    )));
+                }
+            }
+            Node::SayAs(say_as) => {
+                if !SAY_AS_TYPES.contains(&say_as.interpret_as.as_str()) {
+                    return Err(invalid(&format!(
+                        "unknown say-as type {}",
+                        say_as.interpret_as
+                    )));
+                }
+            }
+            Node::Phoneme { ph, .. } => {
+                if ph.is_empty() {
+                    return Err(invalid("phoneme requires a pronunciation"));
+                }
+            }
+            Node::Bookmark(mark) => {
+                if mark.is_empty() {
+                    return Err(invalid("bookmark requires a mark"));
+                }
+            }
+            Node::Audio { src, .. } => validate_uri("audio", src)?,
+            Node::Prosody(prosody) => prosody.validate(voice, styles)?,
+            Node::ExpressAs(express_as) => express_as.validate(voice, styles)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_content(ssml: &mut String, content: &[Node]) {
+    for node in content {
+        match node {
+            Node::Text(text) => ssml.push_str(&escape_xml(text)),
+            Node::BreakTime(duration) => {
+                let _ = write!(ssml, "<break time='{}ms'/>", duration.as_millis());
+            }
+            Node::BreakStrength(strength) => {
+                let _ = write!(ssml, "<break strength='{}'/>", strength.as_str());
+            }
+            Node::SayAs(say_as) => {
+                let _ = write!(
+                    ssml,
+                    "<say-as interpret-as='{}'",
+                    escape_xml(&say_as.interpret_as)
+                );
+                if let Some(format) = &say_as.format {
+                    let _ = write!(ssml, " format='{}'", escape_xml(format));
+                }
+                if let Some(detail) = &say_as.detail {
+                    let _ = write!(ssml, " detail='{}'", escape_xml(detail));
+                }
+                let _ = write!(ssml, ">{}</say-as>", escape_xml(&say_as.text));
+            }
+            Node::Phoneme { alphabet, ph, text } => {
+                let _ = write!(
+                    ssml,
+                    "<phoneme alphabet='{}' ph='{}'>{}</phoneme>",
+                    alphabet.as_str(),
+                    escape_xml(ph),
+                    escape_xml(text)
+                );
+            }
+            Node::Bookmark(mark) => {
+                let _ = write!(ssml, "<bookmark mark='{}'/>", escape_xml(mark));
+            }
+            Node::Audio { src, fallback } => {
+                let _ = write!(
+                    ssml,
+                    "<audio src='{}'>{}</audio>",
+                    escape_xml(src),
+                    escape_xml(fallback)
+                );
+            }
+            Node::Prosody(prosody) => prosody.write(ssml),
+            Node::ExpressAs(express_as) => express_as.write(ssml),
+        }
+    }
+}
+
+// is_prosody_value reports whether value is one of names, or a number optionally signed and followed by one of
+// units.
+fn is_prosody_value(value: &str, names: &[&str], units: &[&str]) -> bool {
+    if names.contains(&value) {
+        return true;
+    }
+    let number = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let number = units
+        .iter()
+        .find_map(|unit| number.strip_suffix(unit))
+        .unwrap_or(number);
+    !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && number.parse::<f64>().is_ok()
+}
+
+fn validate_uri(element: &str, uri: &str) -> Result<()> {
+    Url::parse(uri)
+        .map(|_| ())
+        .map_err(|e| invalid(&format!("invalid {} uri {}: {}", element, uri, e)))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidArgument(message.to_string())
+}
+
+// escape_xml escapes text for use in SSML content and attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn voice_info(short_name: &str, styles: &[&str]) -> VoiceInfo {
+        VoiceInfo::from_json(&serde_json::json!({
+            "Name": format!("Microsoft Server Speech Text to Speech Voice (en-US, {})", short_name),
+            "ShortName": short_name,
+            "Locale": "en-US",
+            "StyleList": styles,
+        }))
+    }
+
+    #[test]
+    fn builds_escaped_ssml() {
+        let ssml = Speak::new("en-US")
+            .voice(
+                Voice::new("en-US-JennyNeural")
+                    .lexicon("https://example.com/lexicon.xml")
+                    .viseme(VisemeType::FacialExpression)
+                    .text("Tom & Jerry <3 ")
+                    .break_time(Duration::from_millis(250))
+                    .express_as(
+                        ExpressAs::new("cheerful")
+                            .style_degree(1.5)
+                            .role("YoungAdultFemale")
+                            .text("It's ")
+                            .say_as(SayAs::new("date", "1/2/2024").format("mdy")),
+                    )
+                    .prosody(Prosody::new().rate("+10%").pitch("-2st").phoneme(
+                        PhonemeAlphabet::Ipa,
+                        "təˈmeɪtoʊ",
+                        "tomato",
+                    ))
+                    .bookmark("end")
+                    .break_strength(BreakStrength::XStrong)
+                    .audio("https://example.com/chime.wav", "ding"),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            ssml,
+            "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' \
+             xmlns:mstts='http://www.w3.org/2001/mstts' xml:lang='en-US'>\
+             <voice name='en-US-JennyNeural'>\
+             <lexicon uri='https://example.com/lexicon.xml'/>\
+             <mstts:viseme type='FacialExpression'/>\
+             Tom &amp; Jerry &lt;3 <break time='250ms'/>\
+             <mstts:express-as style='cheerful' styledegree='1.5' role='YoungAdultFemale'>\
+             It&apos;s <say-as interpret-as='date' format='mdy'>1/2/2024</say-as>\
+             </mstts:express-as>\
+             <prosody rate='+10%' pitch='-2st'>\
+             <phoneme alphabet='ipa' ph='təˈmeɪtoʊ'>tomato</phoneme></prosody>\
+             <bookmark mark='end'/><break strength='x-strong'/>\
+             <audio src='https://example.com/chime.wav'>ding</audio>\
+             </voice></speak>"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        let code = |speak: Speak| speak.build().unwrap_err().code();
+        let voice = || Voice::new("en-US-JennyNeural");
+        assert_eq!(code(Speak::new("en-US")), ErrorCode::InvalidArg);
+        assert_eq!(code(Speak::new("").voice(voice())), ErrorCode::InvalidArg);
+        assert_eq!(
+            code(Speak::new("en-US").voice(Voice::new(""))),
+            ErrorCode::InvalidArg
+        );
+        let invalid = [
+            voice().break_time(Duration::from_secs(21)),
+            voice().prosody(Prosody::new()),
+            voice().prosody(Prosody::new().rate("quick")),
+            voice().prosody(Prosody::new().pitch("+5dB")),
+            voice().prosody(Prosody::new().volume("150")),
+            voice().say_as(SayAs::new("birthday", "today")),
+            voice().phoneme(PhonemeAlphabet::Sapi, "", "text"),
+            voice().bookmark(""),
+            voice().audio("chime.wav", ""),
+            voice().lexicon("not a uri"),
+            voice().express_as(ExpressAs::new("cheerful").style_degree(3.0)),
+            voice().express_as(ExpressAs::new("cheerful").role("Narrator")),
+            voice().express_as(ExpressAs::new("")),
+            voice().express_as(ExpressAs::new("sad").prosody(Prosody::new().pitch("loud"))),
+        ];
+        for voice in invalid {
+            assert_eq!(
+                code(Speak::new("en-US").voice(voice.clone())),
+                ErrorCode::InvalidArg,
+                "{:?}",
+                voice
+            );
+        }
+        for value in ["x-slow", "1.5", "-20%", "+0.5"] {
+            let speak = Speak::new("en-US").voice(voice().prosody(Prosody::new().rate(value)));
+            assert!(speak.build().is_ok(), "{}", value);
+        }
+        for value in ["600Hz", "+80Hz", "-2st", "x-high"] {
+            let speak = Speak::new("en-US").voice(voice().prosody(Prosody::new().pitch(value)));
+            assert!(speak.build().is_ok(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn validates_voices_and_styles_against_voice_list() {
+        let voices = [
+            voice_info("en-US-JennyNeural", &["cheerful", "sad"]),
+            voice_info("en-US-GuyNeural", &[]),
+        ];
+        let speak = |voice: Voice| Speak::new("en-US").voice(voice);
+
+        assert!(speak(Voice::new("en-us-jennyneural").text("hi"))
+            .build_with_voices(&voices)
+            .is_ok());
+        assert!(speak(Voice::new(&voices[1].name).text("hi"))
+            .build_with_voices(&voices)
+            .is_ok());
+        let err = speak(Voice::new("en-US-AvaNeural"))
+            .build_with_voices(&voices)
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown voice en-US-AvaNeural"));
+        assert!(speak(Voice::new("en-US-AvaNeural")).build().is_ok());
+
+        let angry = || ExpressAs::new("angry").text("hi");
+        let err = speak(Voice::new("en-US-JennyNeural").express_as(angry()))
+            .build_with_voices(&voices)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not support style angry"));
+        assert!(speak(Voice::new("en-US-GuyNeural").express_as(angry()))
+            .build_with_voices(&voices)
+            .is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+use std::future::Future;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderMap, StatusCode};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::usp::{self, Frame, Message, Path};
+
+// serve starts a stand-in speech service on a local port that accepts a single WebSocket connection and
+// hands it to handler. It returns the ws:// url of the stand-in and the handler task.
+pub(crate) async fn serve<F, Fut>(handler: F) -> (String, JoinHandle<Fut::Output>)
+where
+    F: FnOnce(StandInConnection) -> Fut + Send + 'static,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/speech", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        handler(StandInConnection::accept(stream).await).await
+    });
+    (url, handle)
+}
+
+// reject starts a stand-in speech service that refuses the WebSocket handshake of a single connection with
+// the given HTTP status and body. It returns the ws:// url of the stand-in.
+pub(crate) async fn reject(status: u16, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/speech", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let _ = reject_handshake(stream, status, body).await;
+    });
+    url
+}
+
+// serve_http starts a stand-in REST api on a local port that answers a single request with the given HTTP
+// status and JSON body. It returns the http:// url of the stand-in and a task returning the request head.
+pub(crate) async fn serve_http(status: u16, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0; 1];
+            if stream.read(&mut byte).await.unwrap() == 0 {
+                break;
+            }
+            head.push(byte[0]);
+        }
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            StatusCode::from_u16(status).unwrap().canonical_reason().unwrap_or_default(),
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        stream.shutdown().await.unwrap();
+        String::from_utf8(head).unwrap()
+    });
+    (url, handle)
+}
+
+#[allow(clippy::result_large_err)]
+async fn reject_handshake(
+    stream: TcpStream,
+    status: u16,
+    body: &'static str,
+) -> Result<WebSocketStream<TcpStream>, tokio_tungstenite::tungstenite::Error> {
+    tokio_tungstenite::accept_hdr_async(stream, move |_: &Request, _: Response| {
+        let mut response = ErrorResponse::new(Some(body.to_string()));
+        *response.status_mut() = StatusCode::from_u16(status).unwrap();
+        Err(response)
+    })
+    .await
+}
+
+// StandInConnection is the service side of a connection to the stand-in speech service.
+pub(crate) struct StandInConnection {
+    stream: WebSocketStream<TcpStream>,
+    headers: HeaderMap,
+    uri: String,
+}
+
+impl StandInConnection {
+    #[allow(clippy::result_large_err)]
+    async fn accept(stream: TcpStream) -> Self {
+        let mut headers = HeaderMap::new();
+        let mut uri = String::new();
+        let stream =
+            tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+                headers = request.headers().clone();
+                uri = request.uri().to_string();
+                Ok(response)
+            })
+            .await
+            .unwrap();
+        StandInConnection {
+            stream,
+            headers,
+            uri,
+        }
+    }
+
+    pub(crate) fn header(&self, name: &str) -> Option<String> {
+        self.headers
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    pub(crate) fn query(&self) -> String {
+        self.uri
+            .split_once('?')
+            .map(|(_, query)| query.to_string())
+            .unwrap_or_default()
+    }
+
+    // receive waits for the next message from the client. None is returned once the client closes.
+    pub(crate) async fn receive(&mut self) -> Option<Message> {
+        while let Some(frame) = self.stream.next().await {
+            let frame = match frame.ok()? {
+                WsMessage::Text(text) => Frame::Text(text),
+                WsMessage::Binary(data) => Frame::Binary(data),
+                WsMessage::Close(_) => return None,
+                _ => continue,
+            };
+            return Some(Message::decode(&frame).unwrap());
+        }
+        None
+    }
+
+    // receive_audio skips to the next audio message and collects audio until the end of audio marker. It
+    // returns the request id of the turn and the audio, including the wave header.
+    pub(crate) async fn receive_audio(&mut self) -> (String, Vec<u8>) {
+        let mut audio = Vec::new();
+        loop {
+            let message = self
+                .receive()
+                .await
+                .expect("connection closed before end of audio");
+            if message.path() != Path::Audio {
+                continue;
+            }
+            let chunk = message.binary_body().unwrap();
+            if chunk.is_empty() {
+                return (message.request_id().unwrap().to_string(), audio);
+            }
+            audio.extend_from_slice(chunk);
+        }
+    }
+
+    pub(crate) async fn send(&mut self, message: &Message) {
+        let frame = match message.encode().unwrap() {
+            Frame::Text(text) => WsMessage::Text(text),
+            Frame::Binary(data) => WsMessage::Binary(data),
+        };
+        let _ = self.stream.send(frame).await;
+    }
+
+    pub(crate) async fn send_json(&mut self, path: Path, request_id: &str, body: &str) {
+        self.send(&Message::text(
+            path,
+            request_id,
+            usp::CONTENT_TYPE_JSON,
+            body,
+        ))
+        .await;
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::audio::AudioConfig;
+use crate::common::{CancellationErrorDetails, PropertyCollection, PropertyId, ResultReason};
+use crate::error::{Error, Result};
+use crate::events::{RecognitionEventArgs, SessionEventArgs};
+use crate::recognizer::{Connection, RecognitionResult, Recognizer};
+use crate::speech_recognizer::{
+    RecognitionEvent, RecognitionEvents, SpeechRecognitionCanceledEventArgs,
+    SpeechRecognitionEventArgs, SpeechRecognitionResult,
+};
+use crate::speech_translation_config::{self, SpeechTranslationConfig};
+use crate::usp::{Message, Path};
+
+// TranslationRecognizer translates speech from the speech recognition language into one or more target
+// languages. Target languages can be added and removed during continuous recognition; the service applies the
+// change to the speech that follows.
+pub struct TranslationRecognizer {
+    recognizer: Recognizer<TranslationRecognitionResult>,
+}
+
+impl TranslationRecognizer {
+    // from_config creates a translation recognizer from a speech translation config and audio config.
+    pub fn from_config(
+        translation_config: &SpeechTranslationConfig,
+        audio_config: AudioConfig,
+    ) -> Result<Self> {
+        let mut properties = translation_config.properties().clone();
+        audio_config.properties().copy_into(&mut properties);
+        // Fail early rather than on the first recognition. The languages may also be part of an explicit
+        // endpoint.
+        let url = properties.speech_translation_connection_url()?;
+        for (parameter, setting) in [
+            ("from", "speech recognition language"),
+            ("to", "target language"),
+        ] {
+            if !url.query_pairs().any(|(name, _)| name == parameter) {
+                return Err(Error::InvalidArgument(format!(
+                    "translation requires a {}",
+                    setting
+                )));
+            }
+        }
+        let source = audio_config.source.ok_or_else(|| {
+            Error::InvalidArgument("a recognizer requires an audio input config".to_string())
+        })?;
+        Ok(TranslationRecognizer {
+            recognizer: Recognizer::new(properties, source),
+        })
+    }
+
+    // target_languages is the list of target languages for translation.
+    pub fn target_languages(&self) -> Vec<String> {
+        speech_translation_config::target_languages(self.properties())
+    }
+
+    // add_target_language adds a target language for translation. During continuous recognition the running
+    // session is updated, and the speech that follows is also translated into the language.
+    pub fn add_target_language(&mut self, language: &str) -> Result<()> {
+        if speech_translation_config::add_target_language(
+            self.recognizer.properties_mut(),
+            language,
+        )? {
+            self.update_target_languages();
+        }
+        Ok(())
+    }
+
+    // remove_target_language removes a target language for translation. During continuous recognition the
+    // running session is updated, and the speech that follows is no longer translated into the language.
+    pub fn remove_target_language(&mut self, language: &str) {
+        if speech_translation_config::remove_target_language(
+            self.recognizer.properties_mut(),
+            language,
+        ) {
+            self.update_target_languages();
+        }
+    }
+
+    fn update_target_languages(&self) {
+        self.recognizer.send_event(json!({
+            "id": "translation",
+            "name": "updateLanguage",
+            "to": self.target_languages(),
+        }));
+    }
+
+    // authorization_token is the authorization token that will be used for connecting to the service.
+    pub fn authorization_token(&self) -> String {
+        self.properties()
+            .get_property(PropertyId::SpeechServiceAuthorizationToken, "")
+    }
+
+    // set_authorization_token sets the authorization token that will be used for connecting to the service.
+    pub fn set_authorization_token(&mut self, token: &str) {
+        self.recognizer
+            .properties_mut()
+            .set_property(PropertyId::SpeechServiceAuthorizationToken, token);
+    }
+
+    // properties is the collection of properties and their values defined for this TranslationRecognizer.
+    pub fn properties(&self) -> &PropertyCollection {
+        self.recognizer.properties()
+    }
+
+    // recognize_once starts speech translation, and returns after a single utterance is recognized and
+    // translated. Failures to reach or talk to the service are reported as a result with reason Canceled.
+    pub async fn recognize_once(&self) -> Result<TranslationRecognitionResult> {
+        self.recognizer.recognize_once(connect).await
+    }
+
+    // start_continuous_recognition starts speech translation on a continuous audio stream, until
+    // stop_continuous_recognition is called or the end of the audio is reached. Events are delivered both
+    // through the returned stream and to the registered callbacks.
+    pub async fn start_continuous_recognition(&mut self) -> Result<TranslationRecognitionEvents> {
+        self.recognizer.start_continuous_recognition(connect).await
+    }
+
+    // stop_continuous_recognition stops a running translation as soon as possible. The audio sent so far is
+    // still translated: pending results are delivered before the session stopped event. This function returns
+    // once the session has stopped.
+    pub async fn stop_continuous_recognition(&mut self) -> Result<()> {
+        self.recognizer.stop_continuous_recognition().await
+    }
+
+    // on_session_started registers a callback for events indicating the start of a recognition session.
+    pub fn on_session_started(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_started(callback);
+    }
+
+    // on_session_stopped registers a callback for events indicating the end of a recognition session.
+    pub fn on_session_stopped(&self, callback: impl Fn(&SessionEventArgs) + Send + Sync + 'static) {
+        self.recognizer.on_session_stopped(callback);
+    }
+
+    // on_speech_start_detected registers a callback for events indicating the start of speech.
+    pub fn on_speech_start_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_start_detected(callback);
+    }
+
+    // on_speech_end_detected registers a callback for events indicating the end of speech.
+    pub fn on_speech_end_detected(
+        &self,
+        callback: impl Fn(&RecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_speech_end_detected(callback);
+    }
+
+    // on_recognizing registers a callback for events containing intermediate results and their translations.
+    pub fn on_recognizing(
+        &self,
+        callback: impl Fn(&TranslationRecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_recognizing(callback);
+    }
+
+    // on_recognized registers a callback for events containing final results and their translations.
+    pub fn on_recognized(
+        &self,
+        callback: impl Fn(&TranslationRecognitionEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_recognized(callback);
+    }
+
+    // on_canceled registers a callback for events indicating that translation was canceled, because of an
+    // error or the end of the audio.
+    pub fn on_canceled(
+        &self,
+        callback: impl Fn(&TranslationRecognitionCanceledEventArgs) + Send + Sync + 'static,
+    ) {
+        self.recognizer.on_canceled(callback);
+    }
+}
+
+// connect connects a translation to the service; the target languages are part of the url.
+fn connect(properties: &mut PropertyCollection) -> Result<Connection> {
+    Ok(Connection {
+        url: properties.speech_translation_connection_url()?,
+        speech_context: Box::new(|| None),
+    })
+}
+
+// TranslationRecognitionEvent is an event raised by a translation recognizer. Intermediate results have the
+// reason TranslatingSpeech, final results the reason TranslatedSpeech.
+pub type TranslationRecognitionEvent = RecognitionEvent<TranslationRecognitionResult>;
+
+// TranslationRecognitionEvents is the stream of events of a continuous translation. The stream ends after the
+// SessionStopped event.
+pub type TranslationRecognitionEvents = RecognitionEvents<TranslationRecognitionResult>;
+
+// TranslationRecognitionEventArgs represents the translation recognition event arguments.
+pub type TranslationRecognitionEventArgs = SpeechRecognitionEventArgs<TranslationRecognitionResult>;
+
+// TranslationRecognitionCanceledEventArgs represents translation recognition canceled event arguments.
+pub type TranslationRecognitionCanceledEventArgs =
+    SpeechRecognitionCanceledEventArgs<TranslationRecognitionResult>;
+
+// TranslationRecognitionResult contains the recognized text and its translations into the target languages.
+#[derive(Debug, Clone)]
+pub struct TranslationRecognitionResult {
+    // result_id specifies the result identifier.
+    pub result_id: String,
+
+    // reason specifies status of the translation result.
+    pub reason: ResultReason,
+
+    // text presents the recognized text in the result, in the speech recognition language.
+    pub text: String,
+
+    // offset of the recognized speech from the start of the audio.
+    pub offset: Duration,
+
+    // duration of the recognized speech.
+    pub duration: Duration,
+
+    // translations maps each target language to the translation of the text into it.
+    pub translations: HashMap<String, String>,
+
+    // properties is the collection of properties and their values defined for this result. The result as
+    // returned by the service is available as SpeechServiceResponseJsonResult.
+    pub properties: PropertyCollection,
+}
+
+impl TranslationRecognitionResult {
+    // from_speech adds the translations of a translation.hypothesis or translation.phrase message to the
+    // recognition result parsed from it. A phrase whose translation failed keeps the reason RecognizedSpeech,
+    // without translations; the failure is reported as SpeechServiceResponseJsonErrorDetails.
+    fn from_speech(result: SpeechRecognitionResult, json: &serde_json::Value) -> Self {
+        let translation = &json["Translation"];
+        let translations = translation["Translations"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|translation| {
+                let language = translation["Language"].as_str()?;
+                let text = translation["Text"].as_str().unwrap_or_default();
+                Some((language.to_string(), text.to_string()))
+            })
+            .collect();
+        let mut result = TranslationRecognitionResult {
+            result_id: result.result_id,
+            reason: result.reason,
+            text: result.text,
+            offset: result.offset,
+            duration: result.duration,
+            translations,
+            properties: result.properties,
+        };
+        let status = translation["TranslationStatus"]
+            .as_str()
+            .unwrap_or("Success");
+        match result.reason {
+            ResultReason::RecognizingSpeech => result.reason = ResultReason::TranslatingSpeech,
+            ResultReason::RecognizedSpeech if status != "Success" => {
+                let message = translation["FailureReason"].as_str().unwrap_or_default();
+                result.translations.clear();
+                result.properties.set_property(
+                    PropertyId::SpeechServiceResponseJsonErrorDetails,
+                    CancellationErrorDetails::new(status, message).to_string(),
+                );
+            }
+            ResultReason::RecognizedSpeech => result.reason = ResultReason::TranslatedSpeech,
+            _ => {}
+        }
+        result
+    }
+}
+
+impl RecognitionResult for TranslationRecognitionResult {
+    const HYPOTHESIS: Path = Path::TranslationHypothesis;
+    const PHRASE: Path = Path::TranslationPhrase;
+
+    fn from_reason(reason: ResultReason) -> Self {
+        let result = SpeechRecognitionResult::from_reason(reason);
+        TranslationRecognitionResult::from_speech(result, &serde_json::Value::Null)
+    }
+
+    fn from_cancellation(details: &CancellationErrorDetails) -> Self {
+        let result = SpeechRecognitionResult::from_cancellation(details);
+        TranslationRecognitionResult::from_speech(result, &serde_json::Value::Null)
+    }
+
+    fn from_hypothesis(message: &Message, session_id: &str) -> Result<Self> {
+        let result = SpeechRecognitionResult::from_hypothesis(message, session_id)?;
+        Ok(TranslationRecognitionResult::from_speech(
+            result,
+            &message.json_body()?,
+        ))
+    }
+
+    fn from_phrase(message: &Message, session_id: &str) -> Result<Option<Self>> {
+        let Some(result) = SpeechRecognitionResult::from_phrase(message, session_id)? else {
+            return Ok(None);
+        };
+        Ok(Some(TranslationRecognitionResult::from_speech(
+            result,
+            &message.json_body()?,
+        )))
+    }
+
+    fn reason(&self) -> ResultReason {
+        self.reason
+    }
+
+    fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    fn properties(&self) -> &PropertyCollection {
+        &self.properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::PushAudioInputStream;
+    use crate::common::CancellationReason;
+    use crate::test_server;
+    use futures_util::StreamExt;
+
+    fn translation_config(url: &str) -> SpeechTranslationConfig {
+        let mut config =
+            SpeechTranslationConfig::from_endpoint_with_subscription(url, "key").unwrap();
+        config.set_speech_recognition_language("en-US").unwrap();
+        config.add_target_language("de").unwrap();
+        config.add_target_language("fr").unwrap();
+        config
+    }
+
+    #[test]
+    fn translation_requires_source_and_target_languages() {
+        let audio =
+            || AudioConfig::from_stream_input(PushAudioInputStream::create_push_stream()).unwrap();
+        let mut config = SpeechTranslationConfig::from_subscription("key", "westus").unwrap();
+        config.add_target_language("de").unwrap();
+        assert!(TranslationRecognizer::from_config(&config, audio()).is_err());
+        config.set_speech_recognition_language("en-US").unwrap();
+        assert!(TranslationRecognizer::from_config(&config, audio()).is_ok());
+        config.remove_target_language("de");
+        assert!(TranslationRecognizer::from_config(&config, audio()).is_err());
+    }
+
+    #[tokio::test]
+    async fn translations_are_reported_and_target_languages_change_while_live() {
+        let (url, server) = test_server::serve(|mut connection| async move {
+            assert!(connection.query().contains("from=en-US&to=de&to=fr&stableTranslation=true"));
+            connection.receive().await.unwrap();
+            let audio = connection.receive().await.unwrap();
+            let request_id = audio.request_id().unwrap().to_string();
+            connection.send_json(Path::TurnStart, &request_id, "{}").await;
+            connection
+                .send_json(
+                    Path::TranslationHypothesis,
+                    &request_id,
+                    r#"{"Text":"good","Offset":0,"Duration":2000000,"Translation":{"TranslationStatus":"Success","Translations":[{"Language":"de","Text":"gut"},{"Language":"fr","Text":"bon"}]}}"#,
+                )
+                .await;
+            connection
+                .send_json(
+                    Path::TranslationPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","Text":"Good morning.","Offset":0,"Duration":9000000,"Translation":{"TranslationStatus":"Success","Translations":[{"Language":"de","Text":"Guten Morgen."},{"Language":"fr","Text":"Bonjour."}]}}"#,
+                )
+                .await;
+
+            // Every change of the target languages is sent as it is made.
+            let mut updates = Vec::new();
+            while updates.len() < 2 {
+                let message = connection.receive().await.unwrap();
+                if message.path() == Path::Event {
+                    assert_eq!(message.request_id(), Some(request_id.as_str()));
+                    let update = message.json_body().unwrap();
+                    assert_eq!(update["name"], "updateLanguage");
+                    updates.push(update["to"].clone());
+                }
+            }
+            assert_eq!(updates, [json!(["de", "fr", "it"]), json!(["de", "it"])]);
+            connection
+                .send_json(
+                    Path::TranslationPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","Text":"Thanks.","Translation":{"TranslationStatus":"Success","Translations":[{"Language":"de","Text":"Danke."},{"Language":"it","Text":"Grazie."}]}}"#,
+                )
+                .await;
+            connection
+                .send_json(
+                    Path::TranslationPhrase,
+                    &request_id,
+                    r#"{"RecognitionStatus":"Success","Text":"Bye.","Translation":{"TranslationStatus":"Error","FailureReason":"Translation unavailable"}}"#,
+                )
+                .await;
+            connection.receive_audio().await;
+            connection.send_json(Path::TurnEnd, &request_id, "").await;
+        })
+        .await;
+
+        let mut config = translation_config(&url);
+        config.set_property(
+            PropertyId::SpeechServiceResponseTranslationRequestStablePartialResult,
+            "true",
+        );
+        let stream = PushAudioInputStream::create_push_stream();
+        let audio = AudioConfig::from_stream_input(stream.clone()).unwrap();
+        let mut recognizer = TranslationRecognizer::from_config(&config, audio).unwrap();
+        stream.write(&[0; 3200]).await.unwrap();
+
+        let mut events = recognizer.start_continuous_recognition().await.unwrap();
+        let mut recognizing = Vec::new();
+        let mut recognized = Vec::new();
+        while let Some(event) = events.next().await {
+            match event {
+                TranslationRecognitionEvent::Recognizing(args) => recognizing.push(args.result),
+                TranslationRecognitionEvent::Recognized(args) => {
+                    recognized.push(args.result);
+                    match recognized.len() {
+                        1 => {
+                            recognizer.add_target_language("it").unwrap();
+                            recognizer.remove_target_language("fr");
+                        }
+                        3 => stream.close().await,
+                        _ => {}
+                    }
+                }
+                TranslationRecognitionEvent::Canceled(args) => {
+                    assert_eq!(args.reason, CancellationReason::EndOfStream);
+                }
+                _ => {}
+            }
+        }
+        recognizer.stop_continuous_recognition().await.unwrap();
+        server.await.unwrap();
+
+        assert_eq!(recognizing[0].reason, ResultReason::TranslatingSpeech);
+        assert_eq!(recognizing[0].translations["fr"], "bon");
+        assert_eq!(recognized.len(), 3);
+        assert_eq!(recognized[0].reason, ResultReason::TranslatedSpeech);
+        assert_eq!(recognized[0].text, "Good morning.");
+        assert_eq!(recognized[0].duration, Duration::from_millis(900));
+        assert_eq!(recognized[0].translations["de"], "Guten Morgen.");
+        assert_eq!(
+            recognized[1].translations,
+            HashMap::from([
+                ("de".to_string(), "Danke.".to_string()),
+                ("it".to_string(), "Grazie.".to_string())
+            ])
+        );
+        // A failed translation does not end the session.
+        assert_eq!(recognized[2].reason, ResultReason::RecognizedSpeech);
+        assert_eq!(recognized[2].text, "Bye.");
+        assert!(recognized[2].translations.is_empty());
+        assert_eq!(
+            recognized[2]
+                .properties
+                .get_property(PropertyId::SpeechServiceResponseJsonErrorDetails, ""),
+            "Error: Translation unavailable"
+        );
+        assert_eq!(recognizer.target_languages(), ["de", "it"]);
+    }
+}
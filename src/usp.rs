@@ -0,0 +1,574 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+
+pub const HEADER_PATH: &str = "Path";
+pub const HEADER_REQUEST_ID: &str = "X-RequestId";
+pub const HEADER_TIMESTAMP: &str = "X-Timestamp";
+pub const HEADER_CONTENT_TYPE: &str = "Content-Type";
+pub const HEADER_PTS: &str = "PTS";
+pub const HEADER_SPEAKER_ID: &str = "SpeakerId";
+
+pub const CONTENT_TYPE_JSON: &str = "application/json; charset=utf-8";
+pub const CONTENT_TYPE_WAVE: &str = "audio/x-wav";
+pub const CONTENT_TYPE_SSML: &str = "application/ssml+xml";
+
+// Path identifies the kind of a Unified Speech Protocol (USP) message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Path {
+    // SpeechConfig carries the client and audio source description, sent once per connection.
+    SpeechConfig,
+
+    // SpeechContext carries per-turn recognition settings, sent before the audio of a turn.
+    SpeechContext,
+
+    // Audio carries a chunk of audio. An audio message with an empty body marks the end of the audio.
+    Audio,
+
+    // TurnStart indicates that the service started processing a turn.
+    TurnStart,
+
+    // TurnEnd indicates that the service finished processing a turn.
+    TurnEnd,
+
+    // SpeechHypothesis carries an intermediate recognition result.
+    SpeechHypothesis,
+
+    // SpeechPhrase carries a final recognition result.
+    SpeechPhrase,
+
+    // SpeechStartDetected indicates that the service detected the start of speech.
+    SpeechStartDetected,
+
+    // SpeechEndDetected indicates that the service detected the end of speech.
+    SpeechEndDetected,
+
+    // Telemetry carries client side metrics, sent after each turn.
+    Telemetry,
+
+    // SynthesisContext carries per-request synthesis settings, sent before the ssml of a request.
+    SynthesisContext,
+
+    // Ssml carries the SSML document to synthesize.
+    Ssml,
+
+    // AudioMetadata carries boundary, viseme and bookmark events of a synthesis request.
+    AudioMetadata,
+
+    // TranslationHypothesis carries an intermediate recognition result and its translations.
+    TranslationHypothesis,
+
+    // TranslationPhrase carries a final recognition result and its translations.
+    TranslationPhrase,
+
+    // Event carries a change to the settings of a running session, such as the translation target languages.
+    Event,
+
+    // Other is any path not known to this sdk.
+    Other(String),
+}
+
+impl Path {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Path::SpeechConfig => "speech.config",
+            Path::SpeechContext => "speech.context",
+            Path::Audio => "audio",
+            Path::TurnStart => "turn.start",
+            Path::TurnEnd => "turn.end",
+            Path::SpeechHypothesis => "speech.hypothesis",
+            Path::SpeechPhrase => "speech.phrase",
+            Path::SpeechStartDetected => "speech.startDetected",
+            Path::SpeechEndDetected => "speech.endDetected",
+            Path::Telemetry => "telemetry",
+            Path::SynthesisContext => "synthesis.context",
+            Path::Ssml => "ssml",
+            Path::AudioMetadata => "audio.metadata",
+            Path::TranslationHypothesis => "translation.hypothesis",
+            Path::TranslationPhrase => "translation.phrase",
+            Path::Event => "event",
+            Path::Other(path) => path,
+        }
+    }
+
+    // parse maps a path header value to a Path. Paths are matched case insensitively.
+    pub fn parse(path: &str) -> Path {
+        let known = [
+            Path::SpeechConfig,
+            Path::SpeechContext,
+            Path::Audio,
+            Path::TurnStart,
+            Path::TurnEnd,
+            Path::SpeechHypothesis,
+            Path::SpeechPhrase,
+            Path::SpeechStartDetected,
+            Path::SpeechEndDetected,
+            Path::Telemetry,
+            Path::SynthesisContext,
+            Path::Ssml,
+            Path::AudioMetadata,
+            Path::TranslationHypothesis,
+            Path::TranslationPhrase,
+            Path::Event,
+        ];
+        known
+            .into_iter()
+            .find(|known| known.as_str().eq_ignore_ascii_case(path))
+            .unwrap_or_else(|| Path::Other(path.to_string()))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Frame is the payload of a single WebSocket data frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+// Body is the payload of a USP message. Text messages carry text bodies, binary messages carry binary bodies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+// Message is a single USP message: a set of headers followed by a body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+impl Message {
+    // text creates a text message with the standard Path, X-RequestId, X-Timestamp and Content-Type headers.
+    pub fn text(
+        path: Path,
+        request_id: &str,
+        content_type: &str,
+        body: impl Into<String>,
+    ) -> Message {
+        Message {
+            headers: standard_headers(&path, request_id, content_type),
+            body: Body::Text(body.into()),
+        }
+    }
+
+    // binary creates a binary message with the standard Path, X-RequestId, X-Timestamp and Content-Type headers.
+    pub fn binary(
+        path: Path,
+        request_id: &str,
+        content_type: &str,
+        body: impl Into<Vec<u8>>,
+    ) -> Message {
+        Message {
+            headers: standard_headers(&path, request_id, content_type),
+            body: Body::Binary(body.into()),
+        }
+    }
+
+    // audio creates an audio message. The first audio message of a turn carries the Content-Type header,
+    // an empty chunk marks the end of the audio stream.
+    pub fn audio(
+        request_id: &str,
+        content_type: Option<&str>,
+        chunk: impl Into<Vec<u8>>,
+    ) -> Message {
+        let mut message =
+            Message::binary(Path::Audio, request_id, content_type.unwrap_or(""), chunk);
+        if content_type.is_none() {
+            message.remove_header(HEADER_CONTENT_TYPE);
+        }
+        message
+    }
+
+    // json creates a text message with a JSON body.
+    pub fn json(path: Path, request_id: &str, body: &serde_json::Value) -> Message {
+        Message::text(path, request_id, CONTENT_TYPE_JSON, body.to_string())
+    }
+
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    // header returns the value of a header. Header names are matched case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // set_header sets the value of a header, replacing any existing value.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+    }
+
+    pub fn path(&self) -> Path {
+        Path::parse(self.header(HEADER_PATH).unwrap_or_default())
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(HEADER_REQUEST_ID)
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header(HEADER_CONTENT_TYPE)
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+
+    // text_body is the body of a text message.
+    pub fn text_body(&self) -> Option<&str> {
+        match &self.body {
+            Body::Text(text) => Some(text),
+            Body::Binary(_) => None,
+        }
+    }
+
+    // binary_body is the body of a binary message.
+    pub fn binary_body(&self) -> Option<&[u8]> {
+        match &self.body {
+            Body::Text(_) => None,
+            Body::Binary(data) => Some(data),
+        }
+    }
+
+    // json_body parses the body of a text message as JSON. An empty body is parsed as an empty object.
+    pub fn json_body(&self) -> Result<serde_json::Value> {
+        let text = self.text_body().ok_or_else(|| {
+            Error::Protocol(format!("{} message does not have a text body", self.path()))
+        })?;
+        if text.trim().is_empty() {
+            return Ok(serde_json::Value::Object(Default::default()));
+        }
+        serde_json::from_str(text)
+            .map_err(|e| Error::Protocol(format!("invalid {} body: {}", self.path(), e)))
+    }
+
+    // encode serializes the message into a WebSocket frame.
+    // Text messages are the headers, an empty line and the body. Binary messages are a 2-byte big endian
+    // header length, the headers and the body.
+    pub fn encode(&self) -> Result<Frame> {
+        let mut headers = String::new();
+        for (name, value) in &self.headers {
+            if name.contains([':', '\r', '\n']) || value.contains(['\r', '\n']) {
+                return Err(Error::Protocol(format!(
+                    "invalid header {}: {}",
+                    name, value
+                )));
+            }
+            headers.push_str(name);
+            headers.push_str(": ");
+            headers.push_str(value);
+            headers.push_str("\r\n");
+        }
+        match &self.body {
+            Body::Text(text) => {
+                headers.push_str("\r\n");
+                headers.push_str(text);
+                Ok(Frame::Text(headers))
+            }
+            Body::Binary(data) => {
+                let length = u16::try_from(headers.len()).map_err(|_| {
+                    Error::Protocol(format!("headers too long: {} bytes", headers.len()))
+                })?;
+                let mut frame = Vec::with_capacity(2 + headers.len() + data.len());
+                frame.extend_from_slice(&length.to_be_bytes());
+                frame.extend_from_slice(headers.as_bytes());
+                frame.extend_from_slice(data);
+                Ok(Frame::Binary(frame))
+            }
+        }
+    }
+
+    // decode parses a WebSocket frame into a message.
+    pub fn decode(frame: &Frame) -> Result<Message> {
+        match frame {
+            Frame::Text(text) => {
+                let (headers, body) = match text.find("\r\n\r\n") {
+                    Some(index) => (&text[..index], &text[index + 4..]),
+                    None => (text.trim_end_matches("\r\n"), ""),
+                };
+                Ok(Message {
+                    headers: parse_headers(headers)?,
+                    body: Body::Text(body.to_string()),
+                })
+            }
+            Frame::Binary(data) => {
+                if data.len() < 2 {
+                    return Err(Error::Protocol(
+                        "binary message shorter than its header length prefix".to_string(),
+                    ));
+                }
+                let length = u16::from_be_bytes([data[0], data[1]]) as usize;
+                if data.len() < 2 + length {
+                    return Err(Error::Protocol(format!(
+                        "binary message header length {} exceeds message size {}",
+                        length,
+                        data.len()
+                    )));
+                }
+                let headers = std::str::from_utf8(&data[2..2 + length]).map_err(|_| {
+                    Error::Protocol("binary message headers are not valid utf-8".to_string())
+                })?;
+                Ok(Message {
+                    headers: parse_headers(headers)?,
+                    body: Body::Binary(data[2 + length..].to_vec()),
+                })
+            }
+        }
+    }
+}
+
+fn standard_headers(path: &Path, request_id: &str, content_type: &str) -> Vec<(String, String)> {
+    vec![
+        (HEADER_PATH.to_string(), path.to_string()),
+        (HEADER_REQUEST_ID.to_string(), request_id.to_string()),
+        (HEADER_TIMESTAMP.to_string(), timestamp()),
+        (HEADER_CONTENT_TYPE.to_string(), content_type.to_string()),
+    ]
+}
+
+fn parse_headers(headers: &str) -> Result<Vec<(String, String)>> {
+    let mut parsed = Vec::new();
+    for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| Error::Protocol(format!("malformed header line {:?}", line)))?;
+        parsed.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    if !parsed
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case(HEADER_PATH))
+    {
+        return Err(Error::Protocol(
+            "message does not have a Path header".to_string(),
+        ));
+    }
+    Ok(parsed)
+}
+
+// request_id creates a new request id: a random UUID without dashes.
+pub fn request_id() -> String {
+    uuid::Uuid::new_v4()
+        .simple()
+        .to_string()
+        .to_ascii_uppercase()
+}
+
+// timestamp is the current UTC time in the ISO 8601 format the service expects, e.g. 2022-07-20T17:05:42.123Z.
+pub fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format_timestamp(now.as_secs(), now.subsec_millis())
+}
+
+fn format_timestamp(seconds: u64, millis: u32) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+    // Civil date from days since the unix epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60,
+        millis
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST_ID: &str = "8C1F36C8D1B44E9BBF7A5E2D6E7C2A10";
+
+    fn text_frame(path: &str, body: &str) -> Frame {
+        Frame::Text(format!(
+            "X-RequestId:{}\r\nContent-Type:application/json; charset=utf-8\r\nPath:{}\r\n\r\n{}",
+            REQUEST_ID, path, body
+        ))
+    }
+
+    #[test]
+    fn decodes_captured_service_messages() {
+        let captured = [
+            (
+                "turn.start",
+                Path::TurnStart,
+                r#"{"context":{"serviceTag":"4f2b6a1c9d0e4b7f8a3c5d6e7f809a1b"}}"#,
+            ),
+            (
+                "speech.startDetected",
+                Path::SpeechStartDetected,
+                r#"{"Offset":1800000}"#,
+            ),
+            (
+                "speech.hypothesis",
+                Path::SpeechHypothesis,
+                r#"{"Text":"what's the","Offset":1800000,"Duration":5400000}"#,
+            ),
+            (
+                "speech.phrase",
+                Path::SpeechPhrase,
+                r#"{"RecognitionStatus":"Success","Offset":1800000,"Duration":16300000,"DisplayText":"What's the weather like?"}"#,
+            ),
+            (
+                "speech.endDetected",
+                Path::SpeechEndDetected,
+                r#"{"Offset":19100000}"#,
+            ),
+            ("turn.end", Path::TurnEnd, ""),
+        ];
+        for (path, expected, body) in captured {
+            let message = Message::decode(&text_frame(path, body)).unwrap();
+            assert_eq!(message.path(), expected);
+            assert_eq!(message.request_id(), Some(REQUEST_ID));
+            assert_eq!(message.content_type(), Some(CONTENT_TYPE_JSON));
+            assert_eq!(message.text_body(), Some(body));
+            assert!(message.json_body().unwrap().is_object());
+        }
+    }
+
+    #[test]
+    fn round_trips_client_text_messages() {
+        let bodies = [
+            (
+                Path::SpeechConfig,
+                r#"{"context":{"system":{"name":"SpeechSDK"}},"recognition":"interactive"}"#,
+            ),
+            (
+                Path::SpeechContext,
+                r#"{"phraseDetection":{"mode":"interactive"}}"#,
+            ),
+            (
+                Path::Telemetry,
+                r#"{"ReceivedMessages":[{"turn.start":["2022-07-20T17:05:42.123Z"]}]}"#,
+            ),
+        ];
+        for (path, body) in bodies {
+            let message = Message::text(path.clone(), REQUEST_ID, CONTENT_TYPE_JSON, body);
+            let frame = message.encode().unwrap();
+            match &frame {
+                Frame::Text(text) => {
+                    assert!(text.starts_with(&format!(
+                        "Path: {}\r\nX-RequestId: {}\r\nX-Timestamp: ",
+                        path, REQUEST_ID
+                    )));
+                    assert!(text.ends_with(&format!("\r\n\r\n{}", body)));
+                }
+                Frame::Binary(_) => panic!("text message encoded as binary frame"),
+            }
+            assert_eq!(Message::decode(&frame).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn round_trips_audio_messages() {
+        let chunk = vec![0x52, 0x49, 0x46, 0x46, 0x00, 0xff, 0x0d, 0x0a];
+        let message = Message::audio(REQUEST_ID, Some(CONTENT_TYPE_WAVE), chunk.clone());
+        let frame = message.encode().unwrap();
+        let Frame::Binary(data) = &frame else {
+            panic!("audio message encoded as text frame")
+        };
+        let header_length = u16::from_be_bytes([data[0], data[1]]) as usize;
+        let headers = std::str::from_utf8(&data[2..2 + header_length]).unwrap();
+        assert!(headers.starts_with("Path: audio\r\n"));
+        assert!(headers.ends_with("Content-Type: audio/x-wav\r\n"));
+        assert_eq!(&data[2 + header_length..], &chunk[..]);
+
+        let decoded = Message::decode(&frame).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.binary_body(), Some(&chunk[..]));
+
+        let end_of_audio = Message::audio(REQUEST_ID, None, Vec::new());
+        let decoded = Message::decode(&end_of_audio.encode().unwrap()).unwrap();
+        assert_eq!(decoded.path(), Path::Audio);
+        assert_eq!(decoded.content_type(), None);
+        assert_eq!(decoded.binary_body(), Some(&[][..]));
+    }
+
+    #[test]
+    fn decodes_captured_binary_frame() {
+        let headers = "Path:audio\r\nX-RequestId:8C1F36C8D1B44E9BBF7A5E2D6E7C2A10\r\nContent-Type:audio/x-wav\r\n";
+        let mut data = (headers.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(headers.as_bytes());
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        let message = Message::decode(&Frame::Binary(data)).unwrap();
+        assert_eq!(message.path(), Path::Audio);
+        assert_eq!(message.binary_body(), Some(&[1, 2, 3, 4][..]));
+    }
+
+    #[test]
+    fn rejects_malformed_frames() {
+        assert!(Message::decode(&Frame::Binary(vec![0])).is_err());
+        assert!(Message::decode(&Frame::Binary(vec![0, 10, b'P'])).is_err());
+        assert!(Message::decode(&Frame::Text("X-RequestId:1\r\n\r\n{}".to_string())).is_err());
+        assert!(Message::decode(&Frame::Text("Path turn.end\r\n\r\n".to_string())).is_err());
+
+        let mut message = Message::text(Path::TurnEnd, REQUEST_ID, CONTENT_TYPE_JSON, "");
+        message.set_header("X-Bad", "a\r\nb");
+        assert!(message.encode().is_err());
+    }
+
+    #[test]
+    fn unknown_paths_are_preserved() {
+        let message = Message::decode(&text_frame("response", "{}")).unwrap();
+        assert_eq!(message.path(), Path::Other("response".to_string()));
+        assert_eq!(Path::parse("Speech.Phrase"), Path::SpeechPhrase);
+    }
+
+    #[test]
+    fn formats_timestamps_and_request_ids() {
+        assert_eq!(format_timestamp(0, 0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(1_658_336_742, 123),
+            "2022-07-20T17:05:42.123Z"
+        );
+        assert_eq!(format_timestamp(951_782_400, 7), "2000-02-29T00:00:00.007Z");
+        let id = request_id();
+        assert_eq!(id.len(), 32);
+        assert!(id
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase()));
+    }
+}
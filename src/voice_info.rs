@@ -0,0 +1,137 @@
+use crate::common::{
+    PropertyCollection, PropertyId, ResultReason, SynthesisVoiceGender, SynthesisVoiceType,
+};
+use crate::error::{Error, Result};
+
+// VoiceInfo contains information about a synthesis voice.
+#[derive(Debug, Clone)]
+pub struct VoiceInfo {
+    // name specifies the voice name.
+    pub name: String,
+
+    // locale specifies the locale of the voice.
+    pub locale: String,
+
+    // short_name specifies the voice name in short format.
+    pub short_name: String,
+
+    // local_name specifies the local name of the voice.
+    pub local_name: String,
+
+    // gender specifies the gender of the voice.
+    pub gender: SynthesisVoiceGender,
+
+    // voice_type specifies the voice type.
+    pub voice_type: SynthesisVoiceType,
+
+    // style_list specifies the styles the voice supports.
+    pub style_list: Vec<String>,
+
+    // voice_path specifies the voice path, which is only set for offline voices.
+    pub voice_path: String,
+
+    // properties is the collection of properties and their values defined for this VoiceInfo. The voice as
+    // returned by the service is available as SpeechServiceResponseJsonResult.
+    pub properties: PropertyCollection,
+}
+
+impl VoiceInfo {
+    // from_json builds a voice from an entry of the voices list returned by the service.
+    pub(crate) fn from_json(json: &serde_json::Value) -> Self {
+        let text = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+        let gender = match json["Gender"].as_str() {
+            Some(gender) if gender.eq_ignore_ascii_case("female") => SynthesisVoiceGender::Female,
+            Some(gender) if gender.eq_ignore_ascii_case("male") => SynthesisVoiceGender::Male,
+            _ => SynthesisVoiceGender::GenderUnknown,
+        };
+        let voice_type = match json["VoiceType"].as_str() {
+            Some(voice_type) if voice_type.eq_ignore_ascii_case("standard") => {
+                SynthesisVoiceType::OnlineStandard
+            }
+            _ => SynthesisVoiceType::OnlineNeural,
+        };
+        let style_list = json["StyleList"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|style| style.as_str().map(str::to_string))
+            .collect();
+        let mut properties = PropertyCollection::new();
+        properties.set_property(
+            PropertyId::SpeechServiceResponseJsonResult,
+            json.to_string(),
+        );
+        VoiceInfo {
+            name: text("Name"),
+            locale: text("Locale"),
+            short_name: text("ShortName"),
+            local_name: text("LocalName"),
+            gender,
+            voice_type,
+            style_list,
+            voice_path: String::new(),
+            properties,
+        }
+    }
+}
+
+// SynthesisVoicesResult contains information about result from voices list of speech synthesizers.
+#[derive(Debug, Clone)]
+pub struct SynthesisVoicesResult {
+    // result_id specifies the result identifier.
+    pub result_id: String,
+
+    // reason specifies status of the result, VoicesListRetrieved or Canceled.
+    pub reason: ResultReason,
+
+    // voices specifies the retrieved voices.
+    pub voices: Vec<VoiceInfo>,
+
+    // error_details presents error details if the request was canceled.
+    pub error_details: String,
+
+    // properties is the collection of properties and their values defined for this result.
+    pub properties: PropertyCollection,
+}
+
+impl SynthesisVoicesResult {
+    // from_voices_list builds the result from the voices list returned by the service.
+    pub(crate) fn from_voices_list(result_id: &str, body: &str) -> Result<SynthesisVoicesResult> {
+        let json: serde_json::Value = serde_json::from_str(body)
+            .map_err(|e| Error::Protocol(format!("invalid voices list: {}", e)))?;
+        let voices = json
+            .as_array()
+            .ok_or_else(|| Error::Protocol("voices list is not an array".to_string()))?
+            .iter()
+            .map(VoiceInfo::from_json)
+            .collect();
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::SpeechServiceResponseJsonResult, body);
+        Ok(SynthesisVoicesResult {
+            result_id: result_id.to_string(),
+            reason: ResultReason::VoicesListRetrieved,
+            voices,
+            error_details: String::new(),
+            properties,
+        })
+    }
+
+    // canceled builds the result of a request that failed.
+    pub(crate) fn canceled(result_id: &str, err: Error) -> SynthesisVoicesResult {
+        let (_, details) = err.cancellation();
+        let mut properties = PropertyCollection::new();
+        properties.set_property(PropertyId::CancellationDetailsReason, "Error");
+        properties.set_property(PropertyId::CancellationDetailsReasonText, &details.reason);
+        properties.set_property(
+            PropertyId::CancellationDetailsReasonDetailedText,
+            &details.detailed_text,
+        );
+        SynthesisVoicesResult {
+            result_id: result_id.to_string(),
+            reason: ResultReason::Canceled,
+            voices: Vec::new(),
+            error_details: details.to_string(),
+            properties,
+        }
+    }
+}